@@ -4,7 +4,7 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use cw_starter::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use cw_starter::state::{Config, CONFIG, Poll, Ballot};
+use cw_starter::state::{Config, Poll, Ballot};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
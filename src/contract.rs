@@ -1,274 +1,11833 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Order, to_binary};
-use cw2::set_contract_version;
-use crate::state::{Config, CONFIG, Poll, POLLS, Ballot, BALLOTS};
+use cosmwasm_std::{
+    coin, from_binary, to_binary, to_vec, Addr, Api, BankMsg, Binary, CanonicalAddr, Coin,
+    CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo, Order, Reply, Response,
+    StdError, StdResult, Storage, SubMsg, SubMsgResult, Timestamp, Uint128, WasmMsg,
+};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+use cw_storage_plus::Bound;
+use cw_utils::{nonpayable, parse_reply_instantiate_data, Expiration, PaymentError};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use crate::helpers::{vote_commitment, voter_hash};
+use crate::state::{
+    polls, reputations, vote_log, Ballot, Config, CreationWindow, Payout, PendingAdmin, Poll,
+    PollLimits, PollOption, PollOutcome, PollResult, PollStatus, PollSummary, RateLimit,
+    RecurringPoll, Reputation, RewardPool, Role, Stats, TallyRound, TallyType, TreasurySource,
+    VoteAction, VoteLogEntry, ABSTAIN_OPTION, ACTIVE_POLLS, ALLOWED_VOTERS, ARCHIVED_POLLS,
+    BALLOTS, BANNED, BANNED_WORDS, COMMITMENTS, CONFIG, CREATION_NUMBERS, CREATION_ORDER,
+    CREATION_WINDOWS, CREATOR_FEES, CW20_TREASURY, DELEGATIONS, DELEGATORS, FAILED_PAYOUTS,
+    FEE_CREDITS, HELD, HOOKS, KEEPER_BOUNTIES, LAST_POLL_ID, NAMESPACES, NAMESPACE_CREATORS,
+    NEXT_PAYOUT_REPLY_ID, PENDING_ADMIN, PENDING_PAYOUTS, PENDING_POLL_CONTRACT, POLL_CONTRACTS,
+    POLL_COUNT, POLL_VOTERS, POWER_CHECKPOINTS, PRIVATE_BALLOTS, RAFFLES, RECEIPT_COUNT,
+    RECURRING_INSTANCES, RECURRING_POLLS, REPORTS, REPORT_COUNTS, REPRESENTATIVES,
+    RESULT_ATTESTATIONS, ROLES, SIG_NONCES, STATS, TAG_POLLS, TALLIES, TALLY_ROUNDS, TREASURY,
+    TREASURY_SOURCES, VETO_OPTION, VIEWING_KEYS, VOTERS, VOTE_COUNTS, VOTE_FEES_COLLECTED,
+    VOTE_FEES_PAID, VOTE_LOG_COUNT,
+};
 
+use crate::cw3;
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, AllPollsResponse, PollResponse, VoteResponse};
+use crate::external::{
+    Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Cw4MemberResponse,
+    Cw4QueryMsg, Cw721ExecuteMsg, Cw721QueryMsg, Cw721TokensResponse,
+};
+use crate::msg::{
+    AcceptedToken, AllPollsResponse, AttestedResult, BannedAddressesResponse, BannedWordsResponse,
+    CreatePollResponse, CreatorFeesResponse, DelegationsResponse, ExecuteMsg,
+    ExportBallotsResponse, ExportStateResponse, FailedPayoutsResponse, HookExecuteMsg,
+    HooksResponse, ImportedBallot, InstantiateMsg, LeaderboardKind, LeaderboardResponse,
+    MigrateMsg, NamespaceResponse, NewPoll, OptionDetails, OptionResult, PollContractResponse,
+    PollCountResponse, PollExistsResponse, PollHookMsg, PollInfo, PollResponse,
+    PollResultsResponse, PollVotersResponse, QueryMsg, ReceiptExtension, ReceiveMsg,
+    RecurringInstancesResponse, ReportedPoll, ReportedPollsResponse, ResultAttestationResponse,
+    Settings, SigNonceResponse, SignedVote, SortOrder, SudoMsg, TallyAtHeightResponse,
+    TallyRoundsResponse, TreasuryResponse, VoteInfo, VoteLogResponse, VoteResponse, VoterInfo,
+    VotesByVoterResponse,
+};
+use crate::migrations;
+use crate::raffle;
+use crate::survey;
 
 const CONTRACT_NAME: &str = "crates.io:cw-starter";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Reply ids, one per kind of submessage the contract sends. Payouts each take their own id from
+// FIRST_PAYOUT_REPLY_ID up.
+const HOOK_REPLY_ID: u64 = 2;
+const POLL_CONTRACT_REPLY_ID: u64 = 3;
+const RECEIPT_REPLY_ID: u64 = 4;
+const FIRST_PAYOUT_REPLY_ID: u64 = 1 << 32;
+
+// Default NoWithVeto share that vetoes a gov style poll, 33.4%
+const DEFAULT_VETO_THRESHOLD: Decimal = Decimal::raw(334_000_000_000_000_000);
+
+// Size limits of the text attached to a poll
+const MAX_DESCRIPTION_LENGTH: usize = 1024;
+const MAX_REPORT_REASON_LENGTH: usize = 280;
+const MAX_METADATA_URI_LENGTH: usize = 256;
+const MAX_TAGS: usize = 5;
+const MAX_TAG_LENGTH: usize = 32;
+/// How long a proposed admin has to accept, unless the proposal sets its own deadline
+const ADMIN_CLAIM_PERIOD: u64 = 7 * 24 * 60 * 60;
+/// Length in seconds of the periods participation streaks are counted in
+pub(crate) const REPUTATION_PERIOD: u64 = 7 * 24 * 60 * 60;
+
+// Pagination bounds for list queries
+pub(crate) const DEFAULT_LIMIT: u32 = 10;
+pub(crate) const MAX_LIMIT: u32 = 30;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    let admin = msg.admin.unwrap_or(info.sender.to_string());
+    let admin = msg.admin.unwrap_or_else(|| info.sender.to_string());
     let validated_admin = deps.api.addr_validate(&admin)?;
-    let config = Config {
+    let mut config = Config {
         admin: validated_admin.clone(),
+        poll_fee: msg.poll_fee.filter(|fee| !fee.amount.is_zero()),
+        poll_deposit: msg.poll_deposit.filter(|deposit| !deposit.amount.is_zero()),
+        accepted_token: None,
+        cw20_poll_fee: None,
+        frozen: false,
+        poll_code_id: msg.poll_code_id,
+        limits: PollLimits::default(),
+        restricted_creation: false,
+        default_poll_duration: None,
+        paused: false,
+        rate_limit: None,
+        max_active_polls: None,
+        nois_proxy: None,
+        receipt_nft: None,
+        report_threshold: None,
+        vote_fee_share: Decimal::zero(),
+        keeper_bounty: None,
+        bootstrap: msg.bootstrap,
+    };
+    if let Some(limits) = msg.limits {
+        set_limits(&mut config, limits)?;
+    }
+    if let Some(token) = msg.accepted_token {
+        set_accepted_token(deps.as_ref(), &mut config, token)?;
+    }
+    if let Some(settings) = msg.settings {
+        apply_settings(&mut config, settings)?;
+    }
+    // Seed polls are created before the poll fee and deposit take effect
+    let poll_fee = config.poll_fee.take();
+    let poll_deposit = config.poll_deposit.take();
+    CONFIG.save(deps.storage, &config)?;
+    STATS.save(deps.storage, &Stats::default())?;
+    POLL_COUNT.save(deps.storage, &0)?;
+
+    let mut res = Response::new();
+    let creator = MessageInfo {
+        sender: validated_admin.clone(),
+        funds: vec![],
     };
+    for new_poll in msg.initial_polls {
+        // A factory mode poll is only registered in the reply, which can't wait for another
+        if new_poll.factory {
+            return Err(ContractError::FactorySeedPoll {});
+        }
+        let created = execute_create_poll(deps.branch(), env.clone(), creator.clone(), new_poll)?;
+        res = res
+            .add_submessages(created.messages)
+            .add_events(created.events);
+    }
+    config.poll_fee = poll_fee;
+    config.poll_deposit = poll_deposit;
     CONFIG.save(deps.storage, &config)?;
-    Ok(Response::new()
+
+    Ok(res
         .add_attribute("action", "instantiate")
         .add_attribute("admin", validated_admin.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut, // removed _ as needed later
-    env: Env, // removed _ as needed later
+    deps: DepsMut,     // removed _ as needed later
+    env: Env,          // removed _ as needed later
     info: MessageInfo, // removed _ as needed later
-    msg: ExecuteMsg, // remove _ as used now
+    msg: ExecuteMsg,   // remove _ as used now
 ) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.frozen {
+        return Err(ContractError::Frozen {});
+    }
+    if config.paused && !matches!(msg, ExecuteMsg::Unpause {}) {
+        return Err(ContractError::ContractPaused {});
+    }
+    // Only poll creation, raffles, imports and votes in polls with a vote fee take payment, coins
+    // sent with anything else would be stranded
+    if !matches!(
+        msg,
+        ExecuteMsg::CreatePoll(_)
+            | ExecuteMsg::Vote {
+                proposal_id: None,
+                ..
+            }
+            | ExecuteMsg::ClonePoll { .. }
+            | ExecuteMsg::SpawnNextInstance { .. }
+            | ExecuteMsg::CreateRaffle { .. }
+            | ExecuteMsg::DrawRaffle { .. }
+            | ExecuteMsg::ImportPolls { .. }
+    ) {
+        nonpayable(&info)?;
+    }
     match msg {
-        ExecuteMsg::CreatePoll {
+        ExecuteMsg::CreatePoll(new_poll) => execute_create_poll(deps, env, info, new_poll),
+        ExecuteMsg::Vote {
+            proposal_id: Some(proposal_id),
+            vote,
+            ..
+        } => cw3::execute_vote(deps, env, info, proposal_id, vote),
+        ExecuteMsg::Vote {
+            poll_id,
+            proposal_id: None,
+            vote,
+            option_index,
+            votes,
+            credits,
+            proof,
+        } => {
+            let selections = vote.into_iter().chain(votes).collect();
+            execute_vote(
+                deps,
+                env,
+                info,
+                poll_id,
+                selections,
+                option_index,
+                credits,
+                proof,
+            )
+        }
+        ExecuteMsg::VoteMany { votes } => execute_vote_many(deps, env, info, votes),
+        ExecuteMsg::VoteBySig {
+            poll_id,
+            vote,
+            voter_pubkey,
+            signature,
+            nonce,
+            expires,
+        } => execute_vote_by_sig(
+            deps,
+            env,
+            poll_id,
+            vote,
+            voter_pubkey,
+            signature,
+            nonce,
+            expires,
+        ),
+        ExecuteMsg::Delete { poll_id } => execute_delete(deps, env, info, poll_id),
+        ExecuteMsg::ReportPoll { poll_id, reason } => {
+            execute_report_poll(deps, env, info, poll_id, reason)
+        }
+        ExecuteMsg::ResolveReport { poll_id } => execute_resolve_report(deps, env, info, poll_id),
+        ExecuteMsg::HidePoll { poll_id } => execute_set_hidden(deps, env, info, poll_id, true),
+        ExecuteMsg::UnhidePoll { poll_id } => execute_set_hidden(deps, env, info, poll_id, false),
+        ExecuteMsg::ArchivePoll { poll_id } => execute_archive_poll(deps, env, info, poll_id),
+        ExecuteMsg::PruneExpired {
+            limit,
+            keep_summaries,
+        } => execute_prune_expired(deps, env, info, limit, keep_summaries),
+        ExecuteMsg::CreateSurvey {
+            survey_id,
+            title,
+            questions,
+            end_time,
+        } => survey::execute_create_survey(deps, env, info, survey_id, title, questions, end_time),
+        ExecuteMsg::SubmitSurveyResponse { survey_id, answers } => {
+            survey::execute_submit_survey_response(deps, env, info, survey_id, answers)
+        }
+        ExecuteMsg::CreateRaffle { poll_id, winners } => {
+            raffle::execute_create_raffle(deps, env, info, poll_id, winners)
+        }
+        ExecuteMsg::DrawRaffle { poll_id } => raffle::execute_draw_raffle(deps, env, info, poll_id),
+        ExecuteMsg::NoisReceive { callback } => {
+            raffle::execute_nois_receive(deps, env, info, callback)
+        }
+        ExecuteMsg::ClonePoll {
+            poll_id,
+            new_poll_id,
+        } => execute_clone_poll(deps, env, info, poll_id, new_poll_id),
+        ExecuteMsg::CreateRecurringPoll {
+            template_id,
+            poll,
+            period,
+        } => execute_create_recurring_poll(deps, env, info, template_id, poll, period),
+        ExecuteMsg::SpawnNextInstance { template_id } => {
+            execute_spawn_next_instance(deps, env, info, template_id)
+        }
+        ExecuteMsg::StopRecurringPoll { template_id } => {
+            execute_stop_recurring_poll(deps, env, info, template_id)
+        }
+        ExecuteMsg::Revoke { poll_id, vote } => execute_revoke(deps, env, info, poll_id, vote),
+        ExecuteMsg::AddVoters { poll_id, voters } => {
+            execute_update_voters(deps, env, info, poll_id, voters, true)
+        }
+        ExecuteMsg::RemoveVoters { poll_id, voters } => {
+            execute_update_voters(deps, env, info, poll_id, voters, false)
+        }
+        ExecuteMsg::RegisterNamespace { namespace } => {
+            execute_register_namespace(deps, env, info, namespace)
+        }
+        ExecuteMsg::AddNamespaceCreators {
+            namespace,
+            creators,
+        } => execute_update_namespace_creators(deps, env, info, namespace, creators, true),
+        ExecuteMsg::RemoveNamespaceCreators {
+            namespace,
+            creators,
+        } => execute_update_namespace_creators(deps, env, info, namespace, creators, false),
+        ExecuteMsg::CommitVote {
+            poll_id,
+            hash,
+            proof,
+        } => execute_commit_vote(deps, env, info, poll_id, hash, proof),
+        ExecuteMsg::RevealVote {
+            poll_id,
+            vote,
+            salt,
+        } => execute_reveal_vote(deps, env, info, poll_id, vote, salt),
+        ExecuteMsg::DelegateVote { delegate } => execute_delegate_vote(deps, env, info, delegate),
+        ExecuteMsg::UndelegateVote {} => execute_undelegate_vote(deps, env, info),
+        ExecuteMsg::CheckpointPower { poll_id, address } => {
+            execute_checkpoint_power(deps, env, info, poll_id, address)
+        }
+        ExecuteMsg::ClaimReward { poll_id } => execute_claim_reward(deps, env, info, poll_id),
+        ExecuteMsg::MyVote { poll_id } => execute_my_vote(deps, env, info, poll_id),
+        ExecuteMsg::SetViewingKey { key } => execute_set_viewing_key(deps, env, info, key),
+        ExecuteMsg::OpenPoll { poll_id } => {
+            execute_set_status(deps, env, info, poll_id, PollStatus::Active)
+        }
+        ExecuteMsg::ClosePoll { poll_id } => {
+            execute_set_status(deps, env, info, poll_id, PollStatus::Closed)
+        }
+        ExecuteMsg::FinalizePoll { poll_id } => {
+            execute_set_status(deps, env, info, poll_id, PollStatus::Finalized)
+        }
+        ExecuteMsg::EndPoll { poll_id } => execute_end_poll(deps, env, info, poll_id),
+        ExecuteMsg::TickExpired { limit } => execute_tick_expired(deps, env, info, limit),
+        ExecuteMsg::CancelPoll { poll_id } => execute_cancel_poll(deps, env, info, poll_id),
+        ExecuteMsg::ForceFinalize { poll_id } => execute_force_finalize(deps, env, info, poll_id),
+        ExecuteMsg::InvalidatePoll { poll_id, reason } => {
+            execute_invalidate_poll(deps, env, info, poll_id, reason)
+        }
+        ExecuteMsg::UpdatePoll {
             poll_id,
             question,
             options,
-        } => execute_create_poll(deps, env, info, poll_id, question, options),
-        ExecuteMsg::Vote { poll_id, vote } => execute_vote(deps, env, info, poll_id, vote),
-        ExecuteMsg::Delete { poll_id } => unimplemented!(),
-        ExecuteMsg::Revoke { poll_id, vote } => unimplemented!(),
+        } => execute_update_poll(deps, env, info, poll_id, question, options),
+        ExecuteMsg::UpdateConfig {
+            poll_fee,
+            poll_deposit,
+            accepted_token,
+            poll_code_id,
+            limits,
+            restricted_creation,
+            rate_limit,
+            max_active_polls,
+            nois_proxy,
+            receipt_nft,
+            report_threshold,
+            vote_fee_share,
+            keeper_bounty,
+        } => execute_update_config(
+            deps,
+            env,
+            info,
+            poll_fee,
+            poll_deposit,
+            accepted_token,
+            poll_code_id,
+            limits,
+            restricted_creation,
+            rate_limit,
+            max_active_polls,
+            nois_proxy,
+            receipt_nft,
+            report_threshold,
+            vote_fee_share,
+            keeper_bounty,
+        ),
+        ExecuteMsg::Pause {} => execute_set_paused(deps, env, info, true),
+        ExecuteMsg::Unpause {} => execute_set_paused(deps, env, info, false),
+        ExecuteMsg::GrantRole { address, role } => {
+            execute_set_role(deps, env, info, address, Some(role))
+        }
+        ExecuteMsg::RevokeRole { address } => execute_set_role(deps, env, info, address, None),
+        ExecuteMsg::BanAddress { address } => execute_set_banned(deps, env, info, address, true),
+        ExecuteMsg::UnbanAddress { address } => execute_set_banned(deps, env, info, address, false),
+        ExecuteMsg::AddBannedWords { words } => {
+            execute_update_banned_words(deps, env, info, words, true)
+        }
+        ExecuteMsg::RemoveBannedWords { words } => {
+            execute_update_banned_words(deps, env, info, words, false)
+        }
+        ExecuteMsg::ProposeNewAdmin { address, deadline } => {
+            execute_propose_new_admin(deps, env, info, address, deadline)
+        }
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, env, info),
+        ExecuteMsg::Receive(wrapper) => execute_receive(deps, env, info, wrapper),
+        ExecuteMsg::WithdrawFees { recipient } => execute_withdraw_fees(deps, env, info, recipient),
+        ExecuteMsg::Spend { to, amount } => execute_spend(deps, env, info, to, amount),
+        ExecuteMsg::ImportPolls { polls } => execute_import_polls(deps, env, info, polls),
+        ExecuteMsg::ImportBallots { ballots } => execute_import_ballots(deps, env, info, ballots),
+        ExecuteMsg::EndBootstrap {} => execute_end_bootstrap(deps, env, info),
+        ExecuteMsg::RetryPayouts {} => execute_retry_payouts(deps, env, info),
+        ExecuteMsg::ClaimCreatorFees {} => execute_claim_creator_fees(deps, env, info),
+        ExecuteMsg::Sweep { denom, recipient } => execute_sweep(deps, env, info, denom, recipient),
+        ExecuteMsg::AddHook { addr } => execute_update_hooks(deps, env, info, addr, true),
+        ExecuteMsg::RemoveHook { addr } => execute_update_hooks(deps, env, info, addr, false),
+        ExecuteMsg::Propose {
+            title,
+            description,
+            msgs,
+            latest,
+        } => cw3::execute_propose(deps, env, info, title, description, msgs, latest),
+        ExecuteMsg::Execute { proposal_id } => cw3::execute_end(deps, env, info, proposal_id, true),
+        ExecuteMsg::Close { proposal_id } => cw3::execute_end(deps, env, info, proposal_id, false),
     }
 }
 
-fn execute_create_poll(
+pub(crate) fn execute_create_poll(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    poll_id: String,
-    question: String,
-    options: Vec<String>,
+    mut new_poll: NewPoll,
 ) -> Result<Response, ContractError> {
-    if options.len() > 10 {
-        return Err(ContractError::TooManyOptions {});
+    let config = CONFIG.load(deps.storage)?;
+    if config.restricted_creation
+        && !has_role(deps.storage, &config, &info.sender, Role::PollCreator)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_not_banned(deps.storage, &info.sender)?;
+    if let Some(rate_limit) = config.rate_limit {
+        count_creation(deps.storage, &env, &info.sender, rate_limit)?;
+    }
+    if let Some(max) = config.max_active_polls {
+        let active = ACTIVE_POLLS
+            .may_load(deps.storage, info.sender.clone())?
+            .unwrap_or(0);
+        if active >= max {
+            return Err(ContractError::TooManyActivePolls { max });
+        }
+    }
+    if new_poll.poll_id.is_empty() {
+        new_poll.poll_id = next_poll_id(deps.storage)?;
+    }
+    ensure_namespace_creator(deps.storage, &new_poll.poll_id, &info.sender)?;
+    if new_poll.end_height.is_none() && new_poll.end_time.is_none() {
+        let start = new_poll.start_time.unwrap_or(env.block.time);
+        new_poll.end_time = config
+            .default_poll_duration
+            .map(|duration| start.plus_seconds(duration));
+    }
+    if new_poll.factory {
+        return create_poll_contract(deps, env, info, new_poll);
     }
+    let NewPoll {
+        poll_id,
+        question,
+        description,
+        metadata_uri,
+        tags,
+        options,
+        option_details,
+        start_time,
+        revote_deadline,
+        end_height,
+        end_time,
+        weight_denom,
+        cw20_address,
+        staking_weighted,
+        nft_collection,
+        nft_weighted,
+        cw4_group,
+        cw4_snapshot,
+        snapshot,
+        ibc_voting,
+        msgs,
+        executor,
+        payload_per_option,
+        max_selections,
+        tally_type,
+        credit_budget,
+        gov_style,
+        veto_threshold,
+        quorum,
+        threshold,
+        eligible_power,
+        commit_reveal,
+        reveal_end_height,
+        reveal_end_time,
+        private_ballots,
+        allow_write_ins,
+        locked_ballots,
+        allowed_voters,
+        merkle_root,
+        min_reputation,
+        vote_fee,
+        reward,
+        draft,
+        factory: _,
+    } = new_poll;
 
-    let mut opts: Vec<(String, u64)> = vec![];
-    for option in options {
-        opts.push((option, 0));
+    // Overwriting would wipe the tallies while leaving the old ballots behind
+    if polls().has(deps.storage, poll_id.clone())
+        || ARCHIVED_POLLS.has(deps.storage, poll_id.clone())
+    {
+        return Err(ContractError::PollAlreadyExists { poll_id });
+    }
+    // The deposit is held on the poll, whatever else is paid in the fee denom goes to the treasury
+    let mut funds = info.funds.clone();
+    if let Some(deposit) = &config.poll_deposit {
+        if !deduct(&mut funds, deposit) {
+            return Err(ContractError::InsufficientDeposit {
+                deposit: deposit.clone(),
+            });
+        }
+    }
+    if let Some(reward) = &reward {
+        if reward.amount.is_zero() || !deduct(&mut funds, reward) {
+            return Err(ContractError::InsufficientReward {
+                reward: reward.clone(),
+            });
+        }
+    }
+    let prepaid = FEE_CREDITS
+        .may_load(deps.storage, info.sender.clone())?
+        .unwrap_or(0);
+    // A creation bought with cw20 tokens stands in for the native fee
+    let use_credit = config.poll_fee.is_some() && prepaid > 0;
+    let mut fee_paid = None;
+    if let Some(fee) = config.poll_fee.clone().filter(|_| !use_credit) {
+        let paid = funds
+            .iter()
+            .find(|paid| paid.denom == fee.denom)
+            .map_or(Uint128::zero(), |paid| paid.amount);
+        if paid < fee.amount {
+            return Err(ContractError::InsufficientFee { fee });
+        }
+        funds.retain(|coin| coin.denom != fee.denom);
+        fee_paid = Some(coin(paid.u128(), fee.denom));
+    }
+    if let Some(extra) = funds.iter().find(|coin| !coin.amount.is_zero()) {
+        return Err(PaymentError::ExtraDenom(extra.denom.clone()).into());
+    }
+    if use_credit {
+        FEE_CREDITS.save(deps.storage, info.sender.clone(), &(prepaid - 1))?;
+    }
+    if let Some(fee_paid) = fee_paid {
+        add_to_treasury(deps.storage, TreasurySource::PollFees, &fee_paid)?;
+        reserve(deps.storage, &fee_paid)?;
+    }
+    for held in config.poll_deposit.iter().chain(&reward) {
+        reserve(deps.storage, held)?;
+    }
+    let mut options = options;
+    let veto_threshold = if gov_style {
+        if tally_type == TallyType::RankedChoice || allow_write_ins {
+            return Err(ContractError::InvalidGovStyle {});
+        }
+        for option in [ABSTAIN_OPTION, VETO_OPTION] {
+            if !options.iter().any(|existing| existing == option) {
+                options.push(option.to_string());
+            }
+        }
+        Some(veto_threshold.unwrap_or(DEFAULT_VETO_THRESHOLD))
+    } else {
+        None
+    };
+    validate_question(&config.limits, &question)?;
+    validate_options(&config.limits, &options)?;
+    ensure_no_banned_words(deps.storage, std::iter::once(&question).chain(&options))?;
+    if description.len() > MAX_DESCRIPTION_LENGTH
+        || metadata_uri
+            .as_ref()
+            .is_some_and(|uri| uri.len() > MAX_METADATA_URI_LENGTH)
+    {
+        return Err(ContractError::MetadataTooLong {});
+    }
+    if tags.len() > MAX_TAGS
+        || tags
+            .iter()
+            .enumerate()
+            .any(|(i, tag)| tag.is_empty() || tag.len() > MAX_TAG_LENGTH || tags[..i].contains(tag))
+    {
+        return Err(ContractError::InvalidTags {
+            max: MAX_TAGS,
+            max_length: MAX_TAG_LENGTH,
+        });
+    }
+    // A poll that is already expired could never receive a vote
+    if end_height.is_some_and(|height| height <= env.block.height)
+        || end_time.is_some_and(|time| time <= env.block.time)
+    {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    if start_time.is_some_and(|start| end_time.is_some_and(|end| start >= end)) {
+        return Err(ContractError::InvalidStartTime {});
+    }
+    if revote_deadline.is_some_and(|deadline| {
+        deadline <= start_time.unwrap_or(env.block.time)
+            || end_time.is_some_and(|end| deadline >= end)
+    }) {
+        return Err(ContractError::InvalidRevoteDeadline {});
+    }
+    let weight_sources = [
+        weight_denom.is_some(),
+        cw20_address.is_some(),
+        staking_weighted,
+        nft_weighted,
+        cw4_group.is_some(),
+    ];
+    if weight_sources.iter().filter(|source| **source).count() > 1 {
+        return Err(ContractError::MultipleWeightSources {});
+    }
+    if nft_weighted && nft_collection.is_none() {
+        return Err(ContractError::MissingNftCollection {});
+    }
+    if cw4_snapshot && cw4_group.is_none() {
+        return Err(ContractError::MissingCw4Group {});
+    }
+    // cw4 groups keep their own history
+    if snapshot && (cw4_group.is_some() || weight_sources.iter().all(|source| !source)) {
+        return Err(ContractError::InvalidSnapshot {});
+    }
+    // Remote voters have no balances, memberships or proofs here to check
+    if ibc_voting
+        && (weight_sources.iter().any(|source| *source)
+            || nft_collection.is_some()
+            || allowed_voters.is_some()
+            || merkle_root.is_some()
+            || commit_reveal)
+    {
+        return Err(ContractError::InvalidIbcVoting {});
+    }
+    if [veto_threshold, quorum, threshold]
+        .iter()
+        .flatten()
+        .any(|share| *share > Decimal::one())
+    {
+        return Err(ContractError::InvalidThreshold {});
+    }
+    if quorum.is_some() && eligible_power.unwrap_or(0) == 0 {
+        return Err(ContractError::MissingEligiblePower {});
+    }
+    // Neither reveals nor relayed votes come with funds to pay the fee from
+    let vote_fee = vote_fee.filter(|fee| !fee.amount.is_zero());
+    if vote_fee.is_some() && (commit_reveal || ibc_voting) {
+        return Err(ContractError::InvalidVoteFee {});
+    }
+    let (reveal_end_height, reveal_end_time) = if commit_reveal {
+        // A commitment hides one choice, and commits have to close before the reveal can end
+        if tally_type != TallyType::Plurality
+            || max_selections.unwrap_or(1) != 1
+            || (end_height.is_none() && end_time.is_none())
+            || (reveal_end_height.is_none() && reveal_end_time.is_none())
+        {
+            return Err(ContractError::InvalidCommitReveal {});
+        }
+        if reveal_end_height.is_some_and(|reveal| reveal <= end_height.unwrap_or(env.block.height))
+            || reveal_end_time.is_some_and(|reveal| reveal <= end_time.unwrap_or(env.block.time))
+        {
+            return Err(ContractError::InvalidExpiration {});
+        }
+        (reveal_end_height, reveal_end_time)
+    } else {
+        (None, None)
+    };
+    let cw20_address = cw20_address
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let nft_collection = nft_collection
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let cw4_group = cw4_group
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    let cw4_snapshot_height = cw4_snapshot.then_some(env.block.height);
+    if let Some(root) = &merkle_root {
+        decode_hash(root)?;
+    }
+    let merkle_root = merkle_root.map(|root| root.to_lowercase());
+    let allowlist = allowed_voters.is_some();
+    let allowed_voters = allowed_voters
+        .unwrap_or_default()
+        .iter()
+        .map(|voter| deps.api.addr_validate(voter))
+        .collect::<StdResult<Vec<_>>>()?;
+    // Single choice unless asked otherwise, a ranking may cover every option
+    let max_selections = max_selections.unwrap_or(match tally_type {
+        TallyType::Plurality => 1,
+        TallyType::RankedChoice | TallyType::Quadratic => options.len() as u32,
+    });
+    if max_selections == 0 || max_selections as usize > options.len().max(1) {
+        return Err(ContractError::InvalidMaxSelections {});
+    }
+    // The messages run as the contract, which holds everyone's deposits and rewards
+    if !msgs.is_empty() && !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some((option, _)) = msgs.iter().find(|(option, _)| !options.contains(option)) {
+        return Err(ContractError::OptionNotFound {
+            option: option.clone(),
+        });
+    }
+    // The executor is called by the contract just the same
+    let executor = executor
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    if executor.is_some() && !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if executor.is_some() == payload_per_option.is_empty()
+        || payload_per_option.len() > options.len()
+    {
+        return Err(ContractError::InvalidPayloads {
+            max: options.len() as u32,
+        });
+    }
+    if let Some((option, _)) = option_details
+        .iter()
+        .find(|(option, _)| !options.contains(option))
+    {
+        return Err(ContractError::OptionNotFound {
+            option: option.clone(),
+        });
+    }
+    if option_details.iter().any(|(_, details)| {
+        details.description.len() > MAX_DESCRIPTION_LENGTH
+            || details
+                .image_uri
+                .as_ref()
+                .is_some_and(|uri| uri.len() > MAX_METADATA_URI_LENGTH)
+    }) {
+        return Err(ContractError::MetadataTooLong {});
+    }
+
+    let mut opts: Vec<PollOption> = vec![];
+    for label in options {
+        let details = option_details
+            .iter()
+            .find(|(option, _)| *option == label)
+            .map(|(_, details)| details.clone())
+            .unwrap_or_default();
+        opts.push(PollOption {
+            label,
+            description: details.description,
+            image_uri: details.image_uri,
+        });
     }
 
+    let options_count = opts.len();
     let poll = Poll {
-        creator: info.sender,
+        creator: info.sender.clone(),
+        created_at_height: env.block.height,
+        created_at_time: env.block.time,
         question,
-        options: opts
+        description,
+        metadata_uri,
+        tags,
+        options: opts,
+        start_time,
+        revote_deadline,
+        end_height,
+        end_time,
+        weight_denom,
+        cw20_address,
+        staking_weighted,
+        nft_collection,
+        nft_weighted,
+        cw4_group,
+        cw4_snapshot_height,
+        // A draft starts once it is opened
+        snapshot_height: (snapshot && !draft).then_some(env.block.height),
+        ibc_voting,
+        msgs,
+        executor,
+        payload_per_option,
+        max_selections,
+        tally_type,
+        credit_budget,
+        gov_style,
+        veto_threshold,
+        quorum,
+        threshold,
+        eligible_power,
+        commit_reveal,
+        reveal_end_height,
+        reveal_end_time,
+        private_ballots,
+        allow_write_ins,
+        locked_ballots,
+        allowlist,
+        merkle_root,
+        min_reputation,
+        vote_fee,
+        deposit: config.poll_deposit,
+        reward: reward.map(|amount| RewardPool {
+            amount,
+            total_weight: Uint128::zero(),
+            claimed: Uint128::zero(),
+            cw20: false,
+        }),
+        status: if draft {
+            PollStatus::Draft
+        } else {
+            PollStatus::Active
+        },
+        result: None,
+        hidden: false,
+        invalid_reason: None,
     };
 
-    POLLS.save(deps.storage, poll_id, &poll)?;
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+    ACTIVE_POLLS.update(
+        deps.storage,
+        poll.creator.clone(),
+        |count| -> StdResult<_> { Ok(count.unwrap_or(0) + 1) },
+    )?;
+    for tag in &poll.tags {
+        TAG_POLLS.save(deps.storage, (tag.clone(), poll_id.clone()), &Empty {})?;
+    }
+    POLL_COUNT.update(deps.storage, |count| -> StdResult<_> { Ok(count + 1) })?;
+    update_stats(deps.storage, |stats| stats.polls_created += 1)?;
+    update_reputation(deps.storage, &info.sender, &env, |reputation| {
+        reputation.polls_created += 1
+    })?;
+    let number = STATS.load(deps.storage)?.polls_created;
+    CREATION_ORDER.save(deps.storage, number, &poll_id)?;
+    CREATION_NUMBERS.save(deps.storage, poll_id.clone(), &number)?;
+    for voter in allowed_voters {
+        ALLOWED_VOTERS.save(deps.storage, (poll_id.clone(), voter), &Empty {})?;
+    }
+
+    let event = Event::new("poll_created")
+        .add_attribute("poll_id", poll_id.clone())
+        .add_attribute("creator", info.sender.clone())
+        .add_attribute("question", poll.question)
+        .add_attribute("options", options_count.to_string())
+        .add_attribute("status", if draft { "draft" } else { "active" });
+    let hook = PollHookMsg::PollCreated {
+        poll_id: poll_id.clone(),
+        creator: info.sender.clone(),
+    };
+    Ok(Response::new()
+        .set_data(to_binary(&CreatePollResponse {
+            poll_id: poll_id.clone(),
+        })?)
+        .add_submessages(hook_msgs(deps.storage, hook)?)
+        .add_event(event)
+        .add_attribute("action", "create_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("creator", info.sender))
+}
 
-    Ok(Response::new())
+/// The next numeric id no poll has taken yet
+fn next_poll_id(storage: &mut dyn Storage) -> StdResult<String> {
+    let mut id = LAST_POLL_ID.may_load(storage)?.unwrap_or(0);
+    let poll_id = loop {
+        id += 1;
+        let poll_id = id.to_string();
+        if !polls().has(storage, poll_id.clone()) && !POLL_CONTRACTS.has(storage, poll_id.clone()) {
+            break poll_id;
+        }
+    };
+    LAST_POLL_ID.save(storage, &id)?;
+    Ok(poll_id)
 }
 
-fn execute_vote(
+/// Instantiates a contract for a factory mode poll, the poll itself is created there in the reply
+fn create_poll_contract(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
+    info: MessageInfo,
+    new_poll: NewPoll,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let code_id = config.poll_code_id.ok_or(ContractError::NoPollCodeId {})?;
+    let poll_id = new_poll.poll_id.clone();
+    if polls().has(deps.storage, poll_id.clone())
+        || ARCHIVED_POLLS.has(deps.storage, poll_id.clone())
+        || POLL_CONTRACTS.has(deps.storage, poll_id.clone())
+    {
+        return Err(ContractError::PollAlreadyExists { poll_id });
+    }
+    // The reward would be stuck here with nobody voting on this side to claim it
+    if new_poll.reward.is_some() {
+        return Err(ContractError::InvalidFactoryPoll {});
+    }
+    let child_poll = NewPoll {
+        factory: false,
+        ..new_poll
+    };
+    PENDING_POLL_CONTRACT.save(deps.storage, &child_poll)?;
+
+    // The admin runs the child, which is created free of fees and deposits but with our limits
+    let msg = InstantiateMsg {
+        admin: Some(info.sender.to_string()),
+        poll_fee: None,
+        poll_deposit: None,
+        accepted_token: None,
+        poll_code_id: None,
+        limits: Some(config.limits),
+        bootstrap: false,
+        initial_polls: vec![],
+        settings: None,
+    };
+    let instantiate = WasmMsg::Instantiate {
+        admin: Some(info.sender.to_string()),
+        code_id,
+        msg: to_binary(&msg)?,
+        funds: vec![],
+        label: format!("poll {} of {}", poll_id, env.contract.address),
+    };
+    Ok(Response::new()
+        .set_data(to_binary(&CreatePollResponse {
+            poll_id: poll_id.clone(),
+        })?)
+        .add_submessage(SubMsg::reply_on_success(
+            instantiate,
+            POLL_CONTRACT_REPLY_ID,
+        ))
+        .add_attribute("action", "create_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("creator", info.sender)
+        .add_attribute("factory", "true"))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn execute_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    mut selections: Vec<String>,
+    option_index: Option<u32>,
+    credits: Vec<u64>,
+    proof: Vec<String>,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}), // The poll does not exist so we just error
+    };
+    if let Some(index) = option_index {
+        let option = poll
+            .options
+            .get(index as usize)
+            .ok_or(ContractError::OptionIndexOutOfRange { index })?;
+        selections.insert(0, option.label.clone());
+    }
+    ensure_open(&poll, &env)?;
+    if poll.commit_reveal {
+        return Err(ContractError::CommitRevealRequired {});
+    }
+    ensure_not_banned(deps.storage, &info.sender)?;
+    verify_merkle_proof(&poll, &info.sender, &proof)?;
+    pay_vote_fee(deps.storage, &poll_id, &poll, &info)?;
+
+    cast_vote(
+        deps,
+        &env,
+        poll_id,
+        poll,
+        info.sender,
+        selections,
+        credits,
+        "vote",
+    )
+}
+
+fn execute_vote_many(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
+    votes: Vec<(String, String)>,
+) -> Result<Response, ContractError> {
+    if votes.is_empty() {
+        return Err(ContractError::NoSelection {});
+    }
+    // A second vote would quietly replace the first
+    for (i, (poll_id, _)) in votes.iter().enumerate() {
+        if votes[..i].iter().any(|(voted, _)| voted == poll_id) {
+            return Err(ContractError::DuplicatePollVote {
+                poll_id: poll_id.clone(),
+            });
+        }
+    }
+    let mut res = Response::new().add_attribute("action", "vote_many");
+    for (poll_id, vote) in votes {
+        let vote_res = execute_vote(
+            deps.branch(),
+            env.clone(),
+            info.clone(),
+            poll_id,
+            vec![vote],
+            None,
+            vec![],
+            vec![],
+        )?;
+        // Each vote's attributes follow its poll id, without repeating the action
+        res = res
+            .add_submessages(vote_res.messages)
+            .add_events(vote_res.events)
+            .add_attributes(
+                vote_res
+                    .attributes
+                    .into_iter()
+                    .filter(|attr| attr.key != "action"),
+            );
+    }
+    Ok(res)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_vote_by_sig(
+    deps: DepsMut,
+    env: Env,
     poll_id: String,
     vote: String,
+    voter_pubkey: Binary,
+    signature: Binary,
+    nonce: u64,
+    expires: Timestamp,
 ) -> Result<Response, ContractError> {
-    let poll = POLLS.may_load(deps.storage, poll_id.clone())?;
+    if env.block.time >= expires {
+        return Err(ContractError::SignatureExpired {});
+    }
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    ensure_open(&poll, &env)?;
+    if poll.commit_reveal {
+        return Err(ContractError::CommitRevealRequired {});
+    }
 
-    match poll {
-        Some(mut poll) => { // The poll exists
-            BALLOTS.update(
-                deps.storage,
-                (info.sender, poll_id.clone()),
-                |ballot| -> StdResult<Ballot> {
-                    match ballot {
-                        Some(ballot) => {
-                            // We need to revoke their old vote
-                            // Find the position
-                            let position_of_old_vote = poll
-                                .options
-                                .iter()
-                                .position(|option| option.0 == ballot.option)
-                                .unwrap();
-                            // Decrement by 1
-                            poll.options[position_of_old_vote].1 -= 1;
-                            // Update the ballot
-                            Ok(Ballot { option: vote.clone() })
-                        }
-                        None => {
-                            // Simply add the ballot
-                            Ok(Ballot { option: vote.clone() })
-                        }
-                    }
-                },
-            )?;
+    // Whoever relays the vote is irrelevant, it counts for the signer
+    let payload = SignedVote {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        poll_id: poll_id.clone(),
+        vote: vote.clone(),
+        nonce,
+        expires,
+    };
+    let hash = Sha256::digest(to_vec(&payload)?);
+    let verified = deps
+        .api
+        .secp256k1_verify(&hash, &signature, &voter_pubkey)
+        .map_err(|_| ContractError::InvalidSignature {})?;
+    if !verified {
+        return Err(ContractError::InvalidSignature {});
+    }
+    let voter = pubkey_address(deps.api, &voter_pubkey)?;
+    // Nonces are used in order, which also keeps a relayer from replaying an earlier vote
+    let expected = SIG_NONCES
+        .may_load(deps.storage, voter.clone())?
+        .unwrap_or_default();
+    if nonce != expected {
+        return Err(ContractError::InvalidNonce { expected });
+    }
+    SIG_NONCES.save(deps.storage, voter.clone(), &(nonce + 1))?;
+    ensure_not_banned(deps.storage, &voter)?;
+    verify_merkle_proof(&poll, &voter, &[])?;
 
-            // Find the position of the new vote option and increment it by 1
-            let position = poll
-                .options
-                .iter()
-                .position(|option| option.0 == vote);
-            if position.is_none() {
-                return Err(ContractError::Unauthorized {});
-            }
-            let position = position.unwrap();
-            poll.options[position].1 += 1;
+    cast_vote(
+        deps,
+        &env,
+        poll_id,
+        poll,
+        voter,
+        vec![vote],
+        vec![],
+        "vote_by_sig",
+    )
+}
 
-            // Save the update
-            POLLS.save(deps.storage, poll_id, &poll)?;
-            Ok(Response::new())
-        },
-        None => Err(ContractError::PollNotFound {}), // The poll does not exist so we just error
+/// Takes the poll's vote fee from a voter's first ballot, later ones are free
+fn pay_vote_fee(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+    info: &MessageInfo,
+) -> Result<(), ContractError> {
+    let key = (poll_id.to_string(), info.sender.clone());
+    let fee = match &poll.vote_fee {
+        Some(fee) if !VOTE_FEES_PAID.has(storage, key.clone()) => fee,
+        _ => {
+            nonpayable(info)?;
+            return Ok(());
+        }
+    };
+    // Exactly the fee, anything over it would be stranded
+    let paid = info
+        .funds
+        .iter()
+        .find(|paid| paid.denom == fee.denom)
+        .map_or(Uint128::zero(), |paid| paid.amount);
+    if paid != fee.amount {
+        return Err(ContractError::VoteFeeRequired { fee: fee.clone() });
     }
+    if let Some(extra) = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom != fee.denom && !coin.amount.is_zero())
+    {
+        return Err(PaymentError::ExtraDenom(extra.denom.clone()).into());
+    }
+    VOTE_FEES_PAID.save(storage, key, &fee.amount)?;
+    reserve(storage, fee)?;
+    VOTE_FEES_COLLECTED.update(storage, poll_id, |collected| -> StdResult<_> {
+        Ok(collected.unwrap_or_default() + fee.amount)
+    })?;
+    Ok(())
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::AllPolls {} => query_all_polls(deps, env),
-        QueryMsg::Poll { poll_id } => query_poll(deps, env, poll_id),
-        QueryMsg::Vote { address, poll_id } => query_vote(deps, env, address, poll_id),
+/// The address of a compressed secp256k1 public key, derived the way Cosmos SDK accounts are
+fn pubkey_address(api: &dyn Api, pubkey: &[u8]) -> Result<Addr, ContractError> {
+    if pubkey.len() != 33 {
+        return Err(ContractError::InvalidPubkey {});
     }
+    let hash = Ripemd160::digest(Sha256::digest(pubkey));
+    Ok(api.addr_humanize(&CanonicalAddr::from(hash.as_slice()))?)
 }
 
-fn query_all_polls(deps: Deps, _env: Env) -> StdResult<Binary> {
-    let polls = POLLS
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|p| Ok(p?.1))
-        .collect::<StdResult<Vec<_>>>()?;
+/// Validates a ballot and counts it, replacing the voter's previous ballot
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cast_vote(
+    deps: DepsMut,
+    env: &Env,
+    poll_id: String,
+    mut poll: Poll,
+    voter: Addr,
+    selections: Vec<String>,
+    credits: Vec<u64>,
+    action: &str,
+) -> Result<Response, ContractError> {
+    if selections.is_empty() {
+        return Err(ContractError::NoSelection {});
+    }
+    // Votes arriving any other way than Vote have nobody paying the fee
+    if let Some(fee) = &poll.vote_fee {
+        if !VOTE_FEES_PAID.has(deps.storage, (poll_id.clone(), voter.clone())) {
+            return Err(ContractError::VoteFeeRequired { fee: fee.clone() });
+        }
+    }
+    if selections.len() > poll.max_selections as usize {
+        return Err(ContractError::TooManySelections {
+            max: poll.max_selections,
+        });
+    }
+    // Check the new vote options exist before touching any state
+    let mut write_ins = vec![];
+    for (i, selection) in selections.iter().enumerate() {
+        if selections[..i].contains(selection) {
+            return Err(ContractError::DuplicateSelection {
+                option: selection.clone(),
+            });
+        }
+        if let Err(err) = option_position(&poll, selection) {
+            if !poll.allow_write_ins {
+                return Err(err);
+            }
+            write_ins.push(selection.clone());
+        }
+    }
+    if !write_ins.is_empty() {
+        // Held to the same limits as the options the poll was created with
+        let config = CONFIG.load(deps.storage)?;
+        let mut labels: Vec<String> = poll
+            .options
+            .iter()
+            .map(|option| option.label.clone())
+            .collect();
+        labels.extend(write_ins.iter().cloned());
+        validate_options(&config.limits, &labels)?;
+        ensure_no_banned_words(deps.storage, &write_ins)?;
+    }
+    if !is_eligible(deps.storage, &poll, &poll_id, &voter) {
+        return Err(ContractError::NotEligible {});
+    }
+    let (weight, represented) = ballot_power(deps.as_ref(), &poll, &poll_id, &voter)?;
 
-    to_binary(&AllPollsResponse { polls })
+    // Quadratic ballots spend credits per selection and have to stay within the budget
+    let quadratic = poll.tally_type == TallyType::Quadratic;
+    let expected_credits = if quadratic { selections.len() } else { 0 };
+    if credits.len() != expected_credits {
+        return Err(ContractError::CreditsMismatch {});
+    }
+    if quadratic {
+        let budget = poll.credit_budget.map_or(weight, Uint128::from);
+        let spent = credits.iter().fold(Uint128::zero(), |total, credit| {
+            total + Uint128::from(*credit)
+        });
+        if spent > budget {
+            return Err(ContractError::CreditBudgetExceeded { budget });
+        }
+    }
+
+    let previous = load_ballot(deps.storage, &poll, &poll_id, &voter)?;
+    if previous.is_some() {
+        ensure_revotable(&poll, env)?;
+    }
+    if !write_ins.is_empty() {
+        for label in &write_ins {
+            poll.options.push(PollOption {
+                label: label.clone(),
+                description: String::new(),
+                image_uri: None,
+            });
+        }
+        polls().save(deps.storage, poll_id.clone(), &poll)?;
+    }
+    if let Some(ballot) = &previous {
+        // We need to revoke their old vote
+        retract_vote(deps.storage, env.block.height, &poll_id, &poll, ballot)?;
+        release_represented(deps.storage, &poll_id, ballot);
+    }
+    let ballot = Ballot {
+        options: selections.clone(),
+        weight,
+        credits,
+        represented,
+        reward_claimed: false,
+        cast_at_height: Some(env.block.height),
+        cast_at_time: Some(env.block.time),
+        void: false,
+    };
+    save_ballot(deps.storage, &poll, &poll_id, &voter, &ballot)?;
+    let logged = if previous.is_some() {
+        VoteAction::Revote
+    } else {
+        VoteAction::Vote
+    };
+    log_vote(deps.storage, env, &poll_id, &poll, &voter, logged, &ballot)?;
+    for delegator in &ballot.represented {
+        REPRESENTATIVES.save(deps.storage, (poll_id.clone(), delegator.clone()), &voter)?;
+    }
+    let first_vote = !VOTERS.has(deps.storage, voter.clone());
+    if first_vote {
+        VOTERS.save(deps.storage, voter.clone(), &Empty {})?;
+    }
+    update_stats(deps.storage, |stats| {
+        stats.votes_cast += 1;
+        stats.unique_voters += first_vote as u64;
+    })?;
+    update_reputation(deps.storage, &voter, env, |reputation| {
+        reputation.polls_voted += previous.is_none() as u64
+    })?;
+
+    VOTE_COUNTS.update(deps.storage, &poll_id, |counts| -> StdResult<_> {
+        let mut counts = counts.unwrap_or_default();
+        counts.total_votes += weight;
+        counts.unique_voters += 1;
+        Ok(counts)
+    })?;
+    // Increment each counted option by what the ballot gives it, the poll itself is left as is
+    let mut tallies = vec![];
+    for (option, votes) in ballot_votes(&poll, &ballot) {
+        let position = option_position(&poll, &option)? as u32;
+        let tally = TALLIES
+            .may_load(deps.storage, (&poll_id, position))?
+            .unwrap_or_default()
+            .checked_add(votes)
+            .map_err(|_| ContractError::TallyOverflow { option })?;
+        TALLIES.save(deps.storage, (&poll_id, position), &tally, env.block.height)?;
+        tallies.push(tally.to_string());
+    }
+    let options = selections.join(",");
+    let tallies = tallies.join(",");
+
+    let hook = PollHookMsg::Voted {
+        poll_id: poll_id.clone(),
+        voter: voter.clone(),
+    };
+    let hooks = hook_msgs(deps.storage, hook)?;
+    let receipt = receipt_msg(deps.storage, &poll, &poll_id, &voter, &selections)?;
+
+    // Private polls only announce that a ballot was cast
+    if poll.private_ballots {
+        let event = Event::new("vote_cast")
+            .add_attribute("poll_id", poll_id.clone())
+            .add_attribute("voter", voter.clone())
+            .add_attribute("weight", weight.to_string());
+        return Ok(Response::new()
+            .add_submessages(hooks)
+            .add_submessages(receipt)
+            .add_event(event)
+            .add_attribute("action", action)
+            .add_attribute("poll_id", poll_id)
+            .add_attribute("voter", voter));
+    }
+
+    let mut event = Event::new("vote_cast")
+        .add_attribute("poll_id", poll_id.clone())
+        .add_attribute("voter", voter.clone())
+        .add_attribute("option", options.clone())
+        .add_attribute("weight", weight.to_string())
+        .add_attribute("tally", tallies.clone());
+    if let Some(previous) = previous {
+        event = event.add_attribute("previous_option", previous.options.join(","));
+    }
+    if !write_ins.is_empty() {
+        event = event.add_attribute("write_ins", write_ins.join(","));
+    }
+    Ok(Response::new()
+        .add_submessages(hooks)
+        .add_submessages(receipt)
+        .add_event(event)
+        .add_attribute("action", action)
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", voter)
+        .add_attribute("option", options)
+        .add_attribute("tally", tallies))
 }
 
-fn query_poll(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
-    let poll = POLLS.may_load(deps.storage, poll_id)?;
-    to_binary(&PollResponse { poll })
+/// Appends a ballot change to the vote log
+fn log_vote(
+    storage: &mut dyn Storage,
+    env: &Env,
+    poll_id: &str,
+    poll: &Poll,
+    voter: &Addr,
+    action: VoteAction,
+    ballot: &Ballot,
+) -> StdResult<()> {
+    let sequence = VOTE_LOG_COUNT.may_load(storage)?.unwrap_or_default();
+    let entry = VoteLogEntry {
+        poll_id: poll_id.to_string(),
+        voter: voter.clone(),
+        action,
+        options: if poll.private_ballots {
+            vec![]
+        } else {
+            ballot.options.clone()
+        },
+        weight: ballot.weight,
+        height: env.block.height,
+        time: env.block.time,
+    };
+    vote_log().save(storage, sequence, &entry)?;
+    VOTE_LOG_COUNT.save(storage, &(sequence + 1))
 }
 
-fn query_vote(deps: Deps, _env: Env, address: String, poll_id: String) -> StdResult<Binary> {
-    let validated_address = deps.api.addr_validate(&address).unwrap();
-    let vote = BALLOTS.may_load(deps.storage, (validated_address, poll_id))?;
+/// Mints the voter a receipt for their ballot when a receipt contract is configured. Voters
+/// relayed over IBC have no local address to own one.
+fn receipt_msg(
+    storage: &mut dyn Storage,
+    poll: &Poll,
+    poll_id: &str,
+    voter: &Addr,
+    selections: &[String],
+) -> StdResult<Option<SubMsg>> {
+    let nft = match CONFIG.load(storage)?.receipt_nft {
+        Some(nft) if !voter.as_str().contains('/') => nft,
+        _ => return Ok(None),
+    };
+    let number = RECEIPT_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    RECEIPT_COUNT.save(storage, &number)?;
 
-    to_binary(&VoteResponse { vote })
+    // The hash of a private ballot would give the choice away, there are only so many options
+    let choice_hash = if poll.private_ballots {
+        None
+    } else {
+        Some(hex::encode(Sha256::digest(selections.join(","))))
+    };
+    let mint = Cw721ExecuteMsg::Mint {
+        token_id: number.to_string(),
+        owner: voter.to_string(),
+        token_uri: None,
+        extension: ReceiptExtension {
+            poll_id: poll_id.to_string(),
+            choice_hash,
+        },
+    };
+    let execute = WasmMsg::Execute {
+        contract_addr: nft.into_string(),
+        msg: to_binary(&mint)?,
+        funds: vec![],
+    };
+    Ok(Some(SubMsg::reply_on_error(execute, RECEIPT_REPLY_ID)))
 }
 
-#[cfg(test)]
-mod tests {
-    use cosmwasm_std::attr; // helper to construct an attribute e.g. ("action", "instantiate")
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info}; // mock functions to mock an environment, message info, dependencies
-    use crate::contract::{instantiate, execute};
-    use crate::msg::{InstantiateMsg, ExecuteMsg};
+fn execute_update_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    question: Option<String>,
+    options: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    if info.sender != poll.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    match poll.status {
+        PollStatus::Draft => {}
+        PollStatus::Active if !has_votes(deps.storage, &poll, &poll_id) => {}
+        PollStatus::Active => return Err(ContractError::PollHasVotes {}),
+        _ => return Err(ContractError::PollClosed {}),
+    }
 
-    // Two fake addresses we will use to mock_info
-    pub const ADDR1: &str = "addr1";
-    pub const ADDR2: &str = "addr2";
+    let limits = CONFIG.load(deps.storage)?.limits;
+    if let Some(question) = question {
+        validate_question(&limits, &question)?;
+        ensure_no_banned_words(deps.storage, [&question])?;
+        poll.question = question;
+    }
+    if let Some(mut options) = options {
+        if poll.gov_style {
+            for option in [ABSTAIN_OPTION, VETO_OPTION] {
+                if !options.iter().any(|existing| existing == option) {
+                    options.push(option.to_string());
+                }
+            }
+        }
+        validate_options(&limits, &options)?;
+        ensure_no_banned_words(deps.storage, &options)?;
+        if poll.max_selections as usize > options.len() {
+            return Err(ContractError::InvalidMaxSelections {});
+        }
+        if poll.payload_per_option.len() > options.len() {
+            return Err(ContractError::InvalidPayloads {
+                max: options.len() as u32,
+            });
+        }
+        if let Some((option, _)) = poll
+            .msgs
+            .iter()
+            .find(|(option, _)| !options.contains(option))
+        {
+            return Err(ContractError::OptionNotFound {
+                option: option.clone(),
+            });
+        }
+        // Votes taken back leave zero tallies behind, at positions about to change
+        clear_tallies(deps.storage, env.block.height, &poll_id, &poll)?;
+        // Options that are kept keep their details
+        poll.options = options
+            .into_iter()
+            .map(|label| {
+                let existing = poll.options.iter().find(|option| option.label == label);
+                existing.cloned().unwrap_or(PollOption {
+                    label,
+                    description: String::new(),
+                    image_uri: None,
+                })
+            })
+            .collect();
+    }
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
 
-    #[test]
-    fn test_instantiate() {
-        // Mock the dependencies, must be mutable so we can pass it as a mutable, empty vector means our contract has no balance
-        let mut deps = mock_dependencies();
-        // Mock the contract environment, contains the block info, contract address, etc.
-        let env = mock_env();
-        // Mock the message info, ADDR1 will be the sender, the empty vec means we sent no funds.
-        let info = mock_info(ADDR1, &vec![]);
+    Ok(Response::new()
+        .add_attribute("action", "update_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("options", poll.options.len().to_string()))
+}
 
-        // Create a message where we (the sender) will be an admin
-        let msg = InstantiateMsg { admin: None };
-        // Call instantiate, unwrap to assert success
-        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+pub(crate) fn validate_question(limits: &PollLimits, question: &str) -> Result<(), ContractError> {
+    if question.chars().count() > limits.max_question_len as usize {
+        return Err(ContractError::QuestionTooLong {
+            max: limits.max_question_len,
+        });
+    }
+    Ok(())
+}
 
-        assert_eq!(
-            res.attributes,
-            vec![attr("action", "instantiate"), attr("admin", ADDR1)]
-        )
+/// Checks the labels a poll is created or updated with
+pub(crate) fn validate_options(
+    limits: &PollLimits,
+    options: &[String],
+) -> Result<(), ContractError> {
+    if options.len() < 2 {
+        return Err(ContractError::TooFewOptions {});
+    }
+    if options.len() > limits.max_options as usize {
+        return Err(ContractError::TooManyOptions {
+            max: limits.max_options,
+        });
+    }
+    if options
+        .iter()
+        .any(|option| option.chars().count() > limits.max_option_len as usize)
+    {
+        return Err(ContractError::OptionTooLong {
+            max: limits.max_option_len,
+        });
+    }
+    // Labels differing only in case would split votes meant for the same choice
+    let mut seen = vec![];
+    for option in options {
+        let normalized = option.trim().to_lowercase();
+        if normalized.is_empty() {
+            return Err(ContractError::EmptyOption {});
+        }
+        if seen.contains(&normalized) {
+            return Err(ContractError::DuplicateOption {
+                option: option.clone(),
+            });
+        }
+        seen.push(normalized);
     }
+    Ok(())
+}
 
-    #[test]
-    fn test_instantiate_with_admin() {
-        /* 
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        let info = mock_info(ADDR2, &vec![]);
+/// Whether anyone has voted, or committed to a vote, in the poll
+fn has_votes(storage: &dyn Storage, poll: &Poll, poll_id: &str) -> bool {
+    let prefix = poll_id.to_string();
+    let ballot = if poll.private_ballots {
+        PRIVATE_BALLOTS
+            .prefix(prefix.clone())
+            .keys_raw(storage, None, None, Order::Ascending)
+            .next()
+    } else {
+        POLL_VOTERS
+            .prefix(prefix.clone())
+            .keys_raw(storage, None, None, Order::Ascending)
+            .next()
+    };
+    let commitment = COMMITMENTS
+        .prefix(prefix)
+        .keys_raw(storage, None, None, Order::Ascending)
+        .next();
+    ballot.is_some() || commitment.is_some()
+}
 
-        let msg = InstantiateMsg { admin: Some("Boss".to_string()) }; // FIXME rust is new to me 
-        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+fn execute_update_voters(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    voters: Vec<String>,
+    allow: bool,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
 
-        assert_eq!(
-            res.attributes,
-            vec![attr("action", "instantiate_width_admin"), attr("admin", ADDR2)]
-        )
-        */
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != poll.creator && !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !poll.allowlist {
+        return Err(ContractError::NoAllowlist {});
     }
 
-    #[test]
-    fn test_execute_create_poll_valid() {
-        let mut deps = mock_dependencies();
-        let env = mock_env();
-        let info = mock_info(ADDR1, &vec![]);
-        // Instantiate the contract
-        let msg = InstantiateMsg { admin: None };
-        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    for voter in voters.iter() {
+        let key = (poll_id.clone(), deps.api.addr_validate(voter)?);
+        if allow {
+            ALLOWED_VOTERS.save(deps.storage, key, &Empty {})?;
+        } else {
+            ALLOWED_VOTERS.remove(deps.storage, key);
+        }
+    }
 
-        // New execute msg
-        let msg = ExecuteMsg::CreatePoll {
-            poll_id: "some_id".to_string(),
-            question: "What's your favourite Cosmos coin?".to_string(),
-            options: vec![
-                "Cosmos Hub".to_string(),
-                "Juno".to_string(),
-                "Osmosis".to_string(),
-            ],
-        };
+    Ok(Response::new()
+        .add_attribute("action", if allow { "add_voters" } else { "remove_voters" })
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voters", voters.len().to_string()))
+}
 
-        // Unwrap to assert success
-        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+fn execute_register_namespace(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    namespace: String,
+) -> Result<Response, ContractError> {
+    // Claiming ids takes the same rights as creating polls with them
+    let config = CONFIG.load(deps.storage)?;
+    if config.restricted_creation
+        && !has_role(deps.storage, &config, &info.sender, Role::PollCreator)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_not_banned(deps.storage, &info.sender)?;
+    if namespace.is_empty() || namespace.contains('/') {
+        return Err(ContractError::InvalidNamespace {});
+    }
+    if NAMESPACES.has(deps.storage, namespace.clone()) {
+        return Err(ContractError::NamespaceTaken { namespace });
+    }
+    // Polls someone else already created under the prefix would end up in a namespace they
+    // don't control
+    let prefix = format!("{}/", namespace);
+    let min = Some(Bound::inclusive(prefix.clone()));
+    let max = prefix_end(prefix.as_bytes()).map(Bound::ExclusiveRaw);
+    for item in polls().range(deps.storage, min, max, Order::Ascending) {
+        let (_, poll) = item?;
+        if poll.creator != info.sender {
+            return Err(ContractError::NamespaceTaken { namespace });
+        }
+    }
+    NAMESPACES.save(deps.storage, namespace.clone(), &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_namespace")
+        .add_attribute("namespace", namespace)
+        .add_attribute("owner", info.sender))
+}
+
+fn execute_update_namespace_creators(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    namespace: String,
+    creators: Vec<String>,
+    allow: bool,
+) -> Result<Response, ContractError> {
+    let owner = match NAMESPACES.may_load(deps.storage, namespace.clone())? {
+        Some(owner) => owner,
+        None => return Err(ContractError::NamespaceNotFound {}),
+    };
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for creator in creators.iter() {
+        let key = (namespace.clone(), deps.api.addr_validate(creator)?);
+        if allow {
+            NAMESPACE_CREATORS.save(deps.storage, key, &Empty {})?;
+        } else {
+            NAMESPACE_CREATORS.remove(deps.storage, key);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute(
+            "action",
+            if allow {
+                "add_namespace_creators"
+            } else {
+                "remove_namespace_creators"
+            },
+        )
+        .add_attribute("namespace", namespace)
+        .add_attribute("creators", creators.len().to_string()))
+}
+
+/// Fails unless the poll id is outside any claimed namespace or `creator` may create polls in it
+fn ensure_namespace_creator(
+    storage: &dyn Storage,
+    poll_id: &str,
+    creator: &Addr,
+) -> Result<(), ContractError> {
+    let namespace = match poll_id.split_once('/') {
+        Some((namespace, _)) => namespace.to_string(),
+        None => return Ok(()),
+    };
+    if let Some(owner) = NAMESPACES.may_load(storage, namespace.clone())? {
+        if owner != *creator
+            && !NAMESPACE_CREATORS.has(storage, (namespace.clone(), creator.clone()))
+        {
+            return Err(ContractError::NamespaceUnauthorized { namespace });
+        }
+    }
+    Ok(())
+}
+
+fn execute_commit_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    hash: String,
+    proof: Vec<String>,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    ensure_open(&poll, &env)?;
+    ensure_not_banned(deps.storage, &info.sender)?;
+    if !poll.commit_reveal {
+        return Err(ContractError::NotCommitReveal {});
+    }
+    // Only eligible voters get to reveal, so the proof is checked here
+    verify_merkle_proof(&poll, &info.sender, &proof)?;
+    // Anything but a sha256 digest could never be revealed
+    if !hex::decode(&hash).is_ok_and(|digest| digest.len() == 32) {
+        return Err(ContractError::InvalidCommitment {});
+    }
+
+    // Committing again replaces the earlier commitment
+    COMMITMENTS.save(
+        deps.storage,
+        (poll_id.clone(), info.sender.clone()),
+        &hash.to_lowercase(),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "commit_vote")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", info.sender))
+}
+
+fn execute_reveal_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    vote: String,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    if !poll.commit_reveal {
+        return Err(ContractError::NotCommitReveal {});
+    }
+    // Reveals run from the end of the poll until the reveal deadline
+    match poll.status {
+        PollStatus::Draft => return Err(ContractError::PollNotActive {}),
+        PollStatus::Active if !poll.is_expired(&env.block) => {
+            return Err(ContractError::RevealNotStarted {})
+        }
+        PollStatus::Active if !poll.voting_ended(&env.block) => {}
+        _ => return Err(ContractError::PollClosed {}),
+    }
+
+    let key = (poll_id.clone(), info.sender.clone());
+    let commitment = COMMITMENTS
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::CommitmentNotFound {})?;
+    if vote_commitment(&poll_id, info.sender.as_str(), &vote, &salt)? != commitment {
+        return Err(ContractError::CommitmentMismatch {});
+    }
+    COMMITMENTS.remove(deps.storage, key);
+
+    cast_vote(
+        deps,
+        &env,
+        poll_id,
+        poll,
+        info.sender,
+        vec![vote],
+        vec![],
+        "reveal_vote",
+    )
+}
+
+fn execute_delete(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let poll = polls().may_load(deps.storage, poll_id.clone())?;
+    let poll = match poll {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+
+    // Only the poll creator or a moderator may delete a poll
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != poll.creator
+        && !has_role(deps.storage, &config, &info.sender, Role::Moderator)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let (res, ballots_removed) = remove_poll(deps.storage, &env, &poll_id, &poll)?;
+
+    Ok(res
+        .add_attribute("action", "delete_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("sender", info.sender)
+        .add_attribute("ballots_removed", ballots_removed.to_string()))
+}
+
+fn execute_report_poll(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    ensure_not_banned(deps.storage, &info.sender)?;
+    if reason.is_empty() || reason.len() > MAX_REPORT_REASON_LENGTH {
+        return Err(ContractError::InvalidReportReason {
+            max: MAX_REPORT_REASON_LENGTH as u32,
+        });
+    }
+    let key = (poll_id.clone(), info.sender.clone());
+    if REPORTS.has(deps.storage, key.clone()) {
+        return Err(ContractError::AlreadyReported {});
+    }
+    REPORTS.save(deps.storage, key, &reason)?;
+    let reports = REPORT_COUNTS
+        .may_load(deps.storage, poll_id.clone())?
+        .unwrap_or(0)
+        + 1;
+    REPORT_COUNTS.save(deps.storage, poll_id.clone(), &reports)?;
+    // Hidden until a moderator unhides it, resolving the reports alone doesn't bring it back
+    let threshold = CONFIG.load(deps.storage)?.report_threshold;
+    let hide = !poll.hidden && threshold.is_some_and(|threshold| reports >= threshold);
+    if hide {
+        poll.hidden = true;
+        polls().save(deps.storage, poll_id.clone(), &poll)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "report_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("reporter", info.sender)
+        .add_attribute("reason", reason)
+        .add_attribute("reports", reports.to_string())
+        .add_attribute("hidden", poll.hidden.to_string()))
+}
+
+fn execute_resolve_report(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Moderator)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !REPORT_COUNTS.has(deps.storage, poll_id.clone()) {
+        return Err(ContractError::NoReports {});
+    }
+    let reports = clear_reports(deps.storage, &poll_id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "resolve_report")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("moderator", info.sender)
+        .add_attribute("reports", reports.to_string()))
+}
+
+fn execute_set_hidden(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    hidden: bool,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Moderator)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    poll.hidden = hidden;
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+
+    Ok(Response::new()
+        .add_attribute("action", if hidden { "hide_poll" } else { "unhide_poll" })
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("moderator", info.sender))
+}
+
+/// Removes a poll's reports and takes it out of the moderation queue, returning how many it had
+fn clear_reports(storage: &mut dyn Storage, poll_id: &str) -> StdResult<usize> {
+    let reporters = REPORTS
+        .prefix(poll_id.to_string())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for reporter in reporters.iter() {
+        REPORTS.remove(storage, (poll_id.to_string(), reporter.clone()));
+    }
+    REPORT_COUNTS.remove(storage, poll_id.to_string());
+    Ok(reporters.len())
+}
+
+fn execute_archive_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != poll.creator && !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if poll.status != PollStatus::Finalized {
+        return Err(ContractError::PollNotFinalized {});
+    }
+    if owes_voters(deps.storage, &poll_id, &poll)? {
+        return Err(ContractError::PollUnsettled {});
+    }
+
+    let (res, ballots_removed) = archive_poll(deps.storage, &env, &poll_id, poll, true)?;
+
+    Ok(res
+        .add_attribute("action", "archive_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("ballots_removed", ballots_removed.to_string()))
+}
+
+fn execute_clone_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    new_poll_id: String,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    let allowed_voters = if poll.allowlist {
+        let voters = ALLOWED_VOTERS
+            .prefix(poll_id.clone())
+            .keys(deps.storage, None, None, Order::Ascending)
+            .map(|voter| voter.map(String::from))
+            .collect::<StdResult<Vec<_>>>()?;
+        Some(voters)
+    } else {
+        None
+    };
+    // The voting window keeps its length, moved to start now. Times before the poll was created,
+    // like a start in the past, land on now.
+    let created = poll.created_at_time;
+    let shift = |time: Timestamp| {
+        env.block
+            .time
+            .plus_nanos(time.nanos().saturating_sub(created.nanos()))
+    };
+    let shift_height =
+        |height: u64| env.block.height + height.saturating_sub(poll.created_at_height);
+    let option_details = poll
+        .options
+        .iter()
+        .filter(|option| !option.description.is_empty() || option.image_uri.is_some())
+        .map(|option| {
+            let details = OptionDetails {
+                description: option.description.clone(),
+                image_uri: option.image_uri.clone(),
+            };
+            (option.label.clone(), details)
+        })
+        .collect();
+    let new_poll = NewPoll {
+        poll_id: new_poll_id,
+        question: poll.question,
+        description: poll.description,
+        metadata_uri: poll.metadata_uri,
+        tags: poll.tags,
+        options: poll
+            .options
+            .into_iter()
+            .map(|option| option.label)
+            .collect(),
+        option_details,
+        start_time: poll.start_time.map(shift),
+        end_height: poll.end_height.map(shift_height),
+        end_time: poll.end_time.map(shift),
+        revote_deadline: poll.revote_deadline.map(shift),
+        weight_denom: poll.weight_denom,
+        cw20_address: poll.cw20_address.map(String::from),
+        staking_weighted: poll.staking_weighted,
+        nft_collection: poll.nft_collection.map(String::from),
+        nft_weighted: poll.nft_weighted,
+        cw4_group: poll.cw4_group.map(String::from),
+        cw4_snapshot: poll.cw4_snapshot_height.is_some(),
+        snapshot: poll.snapshot_height.is_some(),
+        ibc_voting: poll.ibc_voting,
+        msgs: poll.msgs,
+        executor: poll.executor.map(String::from),
+        payload_per_option: poll.payload_per_option,
+        max_selections: Some(poll.max_selections),
+        tally_type: poll.tally_type,
+        credit_budget: poll.credit_budget,
+        gov_style: poll.gov_style,
+        veto_threshold: poll.veto_threshold,
+        quorum: poll.quorum,
+        threshold: poll.threshold,
+        eligible_power: poll.eligible_power,
+        commit_reveal: poll.commit_reveal,
+        reveal_end_height: poll.reveal_end_height.map(shift_height),
+        reveal_end_time: poll.reveal_end_time.map(shift),
+        private_ballots: poll.private_ballots,
+        allow_write_ins: poll.allow_write_ins,
+        locked_ballots: poll.locked_ballots,
+        allowed_voters,
+        merkle_root: poll.merkle_root,
+        min_reputation: poll.min_reputation,
+        vote_fee: poll.vote_fee,
+        // A reward has to be funded again, and drafts are opened like any other poll
+        reward: None,
+        draft: false,
+        factory: false,
+    };
+    let res = execute_create_poll(deps, env, info, new_poll)?;
+
+    Ok(res.add_attribute("cloned_from", poll_id))
+}
+
+fn execute_create_recurring_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    template_id: String,
+    template: NewPoll,
+    period: u64,
+) -> Result<Response, ContractError> {
+    // A stopped recurring poll's instances would clash with the new ones
+    let spawned = RECURRING_INSTANCES
+        .prefix(template_id.clone())
+        .keys_raw(deps.storage, None, None, Order::Ascending)
+        .next()
+        .is_some();
+    if spawned || RECURRING_POLLS.has(deps.storage, template_id.clone()) {
+        return Err(ContractError::RecurringPollExists { template_id });
+    }
+    // Ids and the voting window come from the schedule, and a reveal window couldn't follow it
+    if template_id.is_empty()
+        || period == 0
+        || !template.poll_id.is_empty()
+        || template.start_time.is_some()
+        || template.end_height.is_some()
+        || template.end_time.is_some()
+        || template.revote_deadline.is_some()
+        || template.commit_reveal
+    {
+        return Err(ContractError::InvalidRecurringPoll {});
+    }
+    let recurring = RecurringPoll {
+        creator: info.sender.clone(),
+        template,
+        period,
+        next_spawn: env.block.time,
+        instances: 0,
+    };
+    RECURRING_POLLS.save(deps.storage, template_id.clone(), &recurring)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_recurring_poll")
+        .add_attribute("template_id", template_id)
+        .add_attribute("creator", info.sender)
+        .add_attribute("period", period.to_string()))
+}
+
+fn execute_spawn_next_instance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    template_id: String,
+) -> Result<Response, ContractError> {
+    let mut recurring = match RECURRING_POLLS.may_load(deps.storage, template_id.clone())? {
+        Some(recurring) => recurring,
+        None => return Err(ContractError::RecurringPollNotFound {}),
+    };
+    if env.block.time < recurring.next_spawn {
+        return Err(ContractError::InstanceNotDue {
+            next: recurring.next_spawn,
+        });
+    }
+    // Periods nobody spawned are skipped, the instance runs until the current one ends
+    let missed = (env.block.time.seconds() - recurring.next_spawn.seconds()) / recurring.period;
+    let end_time = recurring
+        .next_spawn
+        .plus_seconds((missed + 1) * recurring.period);
+    recurring.next_spawn = end_time;
+    recurring.instances += 1;
+    let instance = recurring.instances;
+    let poll_id = format!("{}-{}", template_id, instance);
+    RECURRING_POLLS.save(deps.storage, template_id.clone(), &recurring)?;
+    RECURRING_INSTANCES.save(deps.storage, (template_id.clone(), instance), &poll_id)?;
+
+    let new_poll = NewPoll {
+        poll_id,
+        end_time: Some(end_time),
+        ..recurring.template
+    };
+    let info = MessageInfo {
+        sender: recurring.creator,
+        funds: info.funds,
+    };
+    let res = execute_create_poll(deps, env, info, new_poll)?;
+
+    Ok(res
+        .add_attribute("template_id", template_id)
+        .add_attribute("instance", instance.to_string()))
+}
+
+fn execute_stop_recurring_poll(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    template_id: String,
+) -> Result<Response, ContractError> {
+    let recurring = match RECURRING_POLLS.may_load(deps.storage, template_id.clone())? {
+        Some(recurring) => recurring,
+        None => return Err(ContractError::RecurringPollNotFound {}),
+    };
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != recurring.creator
+        && !has_role(deps.storage, &config, &info.sender, Role::Admin)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    // The instances stay listed, which keeps the id taken
+    RECURRING_POLLS.remove(deps.storage, template_id.clone());
+
+    Ok(Response::new()
+        .add_attribute("action", "stop_recurring_poll")
+        .add_attribute("template_id", template_id))
+}
+
+fn execute_prune_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+    keep_summaries: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    // Each poll takes its ballots with it, so batches stay small to fit in a block's gas
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut finalized = vec![];
+    for item in polls()
+        .idx
+        .status
+        .prefix(PollStatus::Finalized as u8)
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        let (poll_id, poll) = item?;
+        // Left for later, their voters still have something coming
+        if owes_voters(deps.storage, &poll_id, &poll)? {
+            continue;
+        }
+        finalized.push((poll_id, poll));
+        if finalized.len() == limit {
+            break;
+        }
+    }
+
+    let pruned = finalized.len();
+    let mut res = Response::new();
+    let mut ballots_removed = 0;
+    for (poll_id, poll) in finalized {
+        let (removed, ballots) = archive_poll(deps.storage, &env, &poll_id, poll, keep_summaries)?;
+        res = res
+            .add_submessages(removed.messages)
+            .add_attributes(removed.attributes)
+            .add_events(removed.events);
+        ballots_removed += ballots;
+    }
+
+    Ok(res
+        .add_attribute("action", "prune_expired")
+        .add_attribute("pruned", pruned.to_string())
+        .add_attribute("ballots_removed", ballots_removed.to_string()))
+}
+
+/// Whether a finalized poll still has a reward share left to claim or a raffle left to draw,
+/// which removing it would hand back to the creator
+fn owes_voters(storage: &dyn Storage, poll_id: &str, poll: &Poll) -> StdResult<bool> {
+    let undrawn = RAFFLES
+        .may_load(storage, poll_id.to_string())?
+        .is_some_and(|raffle| raffle.drawn.is_none());
+    if undrawn {
+        return Ok(true);
+    }
+    match &poll.reward {
+        // Rounding leaves dust in the pool, so only the ballots can tell it's all been claimed
+        Some(pool) if pool.claimed < pool.amount.amount => {
+            Ok(poll_ballots(storage, poll, poll_id)?
+                .iter()
+                .any(|ballot| !ballot.void && !ballot.reward_claimed))
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Removes a finalized poll, leaving a summary behind if asked to
+fn archive_poll(
+    storage: &mut dyn Storage,
+    env: &Env,
+    poll_id: &str,
+    poll: Poll,
+    keep_summary: bool,
+) -> Result<(Response, usize), ContractError> {
+    let tallies = poll.tallies(storage, poll_id)?;
+    let removed = remove_poll(storage, env, poll_id, &poll)?;
+    if keep_summary {
+        let summary = PollSummary {
+            tallies,
+            creator: poll.creator,
+            question: poll.question,
+            result: poll.result,
+            archived_at: env.block.time,
+        };
+        ARCHIVED_POLLS.save(storage, poll_id.to_string(), &summary)?;
+    }
+    Ok(removed)
+}
+
+/// Removes a poll along with everything stored for it, returning the response paying out what it
+/// still holds and how many ballots were removed
+fn remove_poll(
+    storage: &mut dyn Storage,
+    env: &Env,
+    poll_id: &str,
+    poll: &Poll,
+) -> Result<(Response, usize), ContractError> {
+    let poll_id = poll_id.to_string();
+    // Collect the voters first, we can't remove while iterating the same storage
+    let voters = POLL_VOTERS
+        .prefix(poll_id.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in voters.iter() {
+        BALLOTS.remove(storage, (voter.clone(), poll_id.clone()));
+        POLL_VOTERS.remove(storage, (poll_id.clone(), voter.clone()));
+    }
+    let hashed_voters = PRIVATE_BALLOTS
+        .prefix(poll_id.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in hashed_voters.iter() {
+        PRIVATE_BALLOTS.remove(storage, (poll_id.clone(), voter.clone()));
+    }
+    let represented = REPRESENTATIVES
+        .prefix(poll_id.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for delegator in represented {
+        REPRESENTATIVES.remove(storage, (poll_id.clone(), delegator));
+    }
+    let allowed = ALLOWED_VOTERS
+        .prefix(poll_id.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in allowed {
+        ALLOWED_VOTERS.remove(storage, (poll_id.clone(), voter));
+    }
+    let committers = COMMITMENTS
+        .prefix(poll_id.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for committer in committers {
+        COMMITMENTS.remove(storage, (poll_id.clone(), committer));
+    }
+
+    polls().remove(storage, poll_id.clone())?;
+    POLL_COUNT.update(storage, |count| -> StdResult<_> { Ok(count - 1) })?;
+    if let Some(number) = CREATION_NUMBERS.may_load(storage, poll_id.clone())? {
+        CREATION_ORDER.remove(storage, number);
+        CREATION_NUMBERS.remove(storage, poll_id.clone());
+    }
+    if matches!(poll.status, PollStatus::Draft | PollStatus::Active) {
+        release_active_poll(storage, &poll.creator)?;
+    }
+    for tag in &poll.tags {
+        TAG_POLLS.remove(storage, (tag.clone(), poll_id.clone()));
+    }
+    TALLY_ROUNDS.remove(storage, poll_id.clone());
+    KEEPER_BOUNTIES.remove(storage, poll_id.clone());
+    RESULT_ATTESTATIONS.remove(storage, poll_id.clone());
+    clear_reports(storage, &poll_id)?;
+    clear_tallies(storage, env.block.height, &poll_id, poll)?;
+    VOTE_COUNTS.remove(storage, &poll_id);
+    // An unsettled deposit is forfeited when the poll is deleted, unclaimed rewards go back
+    if let Some(deposit) = &poll.deposit {
+        add_to_treasury(storage, TreasurySource::SlashedDeposits, deposit)?;
+    }
+    let mut res = Response::new();
+    if let Some(pool) = &poll.reward {
+        let unclaimed = pool.amount.amount - pool.claimed;
+        if !unclaimed.is_zero() {
+            res = res.add_submessage(reward_payout(storage, pool, &poll.creator, unclaimed)?);
+        }
+    }
+    // So does the prize of a raffle that wasn't drawn yet
+    if let Some(raffle) = RAFFLES.may_load(storage, poll_id.clone())? {
+        RAFFLES.remove(storage, poll_id.clone());
+        if raffle.drawn.is_none() {
+            for prize in raffle.prize {
+                let refund = Payout {
+                    recipient: raffle.creator.clone(),
+                    amount: prize,
+                    cw20: false,
+                };
+                res = res.add_submessage(send_payout(storage, refund)?);
+            }
+        }
+    }
+    // And any vote fees not shared out yet
+    let res = res.add_submessages(refund_vote_fees(storage, &poll_id, poll)?);
+
+    Ok((res, voters.len() + hashed_voters.len()))
+}
+
+fn execute_revoke(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    vote: String,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    // Tallies are final once the poll has closed
+    ensure_open(&poll, &env)?;
+    let ballot = match load_ballot(deps.storage, &poll, &poll_id, &info.sender)? {
+        Some(ballot) => ballot,
+        None => return Err(ContractError::BallotNotFound {}),
+    };
+    ensure_revotable(&poll, &env)?;
+    // The voter has to name an option they are withdrawing from
+    if !ballot.options.contains(&vote) {
+        return Err(ContractError::VoteMismatch {});
+    }
+
+    retract_vote(deps.storage, env.block.height, &poll_id, &poll, &ballot)?;
+    release_represented(deps.storage, &poll_id, &ballot);
+    let action = VoteAction::Revoke;
+    log_vote(
+        deps.storage,
+        &env,
+        &poll_id,
+        &poll,
+        &info.sender,
+        action,
+        &ballot,
+    )?;
+
+    remove_ballot(deps.storage, &poll, &poll_id, &info.sender)?;
+    // Taking a ballot back isn't taking part, so the streak is left alone
+    if let Some(mut reputation) = reputations().may_load(deps.storage, info.sender.clone())? {
+        reputation.polls_voted = reputation.polls_voted.saturating_sub(1);
+        reputations().save(deps.storage, info.sender.clone(), &reputation)?;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "revoke_vote")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", info.sender);
+    if poll.private_ballots {
+        return Ok(res);
+    }
+    Ok(res.add_attribute("option", vote))
+}
+
+fn execute_delegate_vote(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    delegate: String,
+) -> Result<Response, ContractError> {
+    let delegate = deps.api.addr_validate(&delegate)?;
+
+    // Walk the delegate's chain, it must never lead back to the sender
+    let mut current = delegate.clone();
+    loop {
+        if current == info.sender {
+            return Err(ContractError::DelegationCycle {});
+        }
+        match DELEGATIONS.may_load(deps.storage, current)? {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+
+    // Delegating again moves the delegation
+    if let Some(previous) = DELEGATIONS.may_load(deps.storage, info.sender.clone())? {
+        DELEGATORS.remove(deps.storage, (previous, info.sender.clone()));
+    }
+    DELEGATIONS.save(deps.storage, info.sender.clone(), &delegate)?;
+    DELEGATORS.save(
+        deps.storage,
+        (delegate.clone(), info.sender.clone()),
+        &Empty {},
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "delegate_vote")
+        .add_attribute("delegator", info.sender)
+        .add_attribute("delegate", delegate))
+}
+
+fn execute_undelegate_vote(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let delegate = match DELEGATIONS.may_load(deps.storage, info.sender.clone())? {
+        Some(delegate) => delegate,
+        None => return Err(ContractError::NoDelegation {}),
+    };
+    DELEGATIONS.remove(deps.storage, info.sender.clone());
+    DELEGATORS.remove(deps.storage, (delegate.clone(), info.sender.clone()));
+
+    Ok(Response::new()
+        .add_attribute("action", "undelegate_vote")
+        .add_attribute("delegator", info.sender)
+        .add_attribute("delegate", delegate))
+}
+
+fn execute_checkpoint_power(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    address: Option<String>,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    let source = power_source(&poll).ok_or(ContractError::InvalidSnapshot {})?;
+    let address = match address {
+        Some(address) => deps.api.addr_validate(&address)?,
+        None => info.sender,
+    };
+
+    let power = match live_power(deps.as_ref(), &poll, &address) {
+        Err(ContractError::NoVotingPower {}) => Uint128::zero(),
+        power => power?,
+    };
+    POWER_CHECKPOINTS.save(deps.storage, (&source, &address), &power, env.block.height)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "checkpoint_power")
+        .add_attribute("source", source)
+        .add_attribute("address", address)
+        .add_attribute("power", power.to_string()))
+}
+
+fn execute_claim_reward(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    if poll.status != PollStatus::Finalized {
+        return Err(ContractError::PollNotFinalized {});
+    }
+    let mut ballot = match load_ballot(deps.storage, &poll, &poll_id, &info.sender)? {
+        Some(ballot) => ballot,
+        None => return Err(ContractError::BallotNotFound {}),
+    };
+    if ballot.reward_claimed {
+        return Err(ContractError::RewardClaimed {});
+    }
+    let pool = poll.reward.as_mut().ok_or(ContractError::NoReward {})?;
+
+    // Rounding down leaves any dust in the contract
+    let share = pool
+        .amount
+        .amount
+        .multiply_ratio(ballot.weight, pool.total_weight);
+    pool.claimed += share;
+    let amount = coin(share.u128(), &pool.amount.denom);
+    let payout = reward_payout(deps.storage, pool, &info.sender, share)?;
+    ballot.reward_claimed = true;
+    save_ballot(deps.storage, &poll, &poll_id, &info.sender, &ballot)?;
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+
+    Ok(Response::new()
+        .add_submessage(payout)
+        .add_attribute("action", "claim_reward")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", info.sender)
+        .add_attribute("amount", amount.to_string()))
+}
+
+fn execute_my_vote(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    // The sender can only ever read back their own ballot
+    let vote = load_ballot(deps.storage, &poll, &poll_id, &info.sender)?;
+
+    Ok(Response::new()
+        .set_data(to_binary(&VoteResponse { vote })?)
+        .add_attribute("action", "my_vote")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("voter", info.sender))
+}
+
+fn execute_set_viewing_key(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    key: String,
+) -> Result<Response, ContractError> {
+    if key.is_empty() {
+        VIEWING_KEYS.remove(deps.storage, info.sender.clone());
+    } else {
+        VIEWING_KEYS.save(deps.storage, info.sender.clone(), &viewing_key_hash(&key))?;
+    }
+
+    // The key stays out of the attributes, they're as public as the ballots
+    Ok(Response::new()
+        .add_attribute("action", "set_viewing_key")
+        .add_attribute("voter", info.sender)
+        .add_attribute("public", key.is_empty().to_string()))
+}
+
+fn execute_set_status(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    status: PollStatus,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != poll.creator && !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // An active poll whose voting has ended is as good as closed, so it can be finalized directly
+    let allowed = match (poll.status, status) {
+        (PollStatus::Draft, PollStatus::Active) => !poll.is_expired(&env.block),
+        (PollStatus::Active, PollStatus::Closed) => true,
+        (PollStatus::Active, PollStatus::Finalized) => poll.voting_ended(&env.block),
+        (PollStatus::Closed, PollStatus::Finalized) => true,
+        _ => false,
+    };
+    if !allowed {
+        return Err(ContractError::InvalidTransition {
+            from: poll.status,
+            to: status,
+        });
+    }
+
+    let mut res = Response::new();
+    if status == PollStatus::Finalized {
+        res = finalize_poll(deps.storage, &poll_id, &mut poll)?;
+    } else {
+        if status == PollStatus::Active && poll.snapshot_height.is_some() {
+            poll.snapshot_height = Some(env.block.height);
+        }
+        if status == PollStatus::Closed {
+            release_active_poll(deps.storage, &poll.creator)?;
+        }
+        poll.status = status;
+    }
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+
+    let action = match status {
+        PollStatus::Active => "open_poll",
+        PollStatus::Closed => "close_poll",
+        PollStatus::Finalized => "finalize_poll",
+        PollStatus::Draft | PollStatus::Cancelled => {
+            unreachable!("no transition leads back to draft or to cancelled")
+        }
+    };
+    Ok(res
+        .add_attribute("action", action)
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("sender", info.sender))
+}
+
+fn execute_end_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+
+    // Anyone may end a poll, but only once voting is over
+    ensure_ended(&poll, &env)?;
+
+    let bounty = pay_keeper_bounty(deps.storage, &env, &poll_id, &mut poll, &info.sender)?;
+    let res = finalize_poll(deps.storage, &poll_id, &mut poll)?;
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+    let res = res.add_submessages(bounty);
+
+    Ok(res
+        .add_attribute("action", "end_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("sender", info.sender))
+}
+
+fn execute_tick_expired(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // Everything that ended at or before the current block time or height, commit-reveal polls
+    // wait for their reveal to end as well
+    let end_time = Bound::exclusive((env.block.time.nanos() + 1, String::new()));
+    let by_time = polls()
+        .idx
+        .end_time
+        .sub_prefix(PollStatus::Active as u8)
+        .range(deps.storage, None, Some(end_time), Order::Ascending);
+    let end_height = Bound::exclusive((env.block.height + 1, String::new()));
+    let by_height = polls()
+        .idx
+        .end_height
+        .sub_prefix(PollStatus::Active as u8)
+        .range(deps.storage, None, Some(end_height), Order::Ascending);
+    // Polls still in their reveal are skipped, yet count towards the scan so it stays bounded
+    let mut expired: Vec<(String, Poll)> = vec![];
+    for item in by_time.take(limit).chain(by_height.take(limit)) {
+        if expired.len() == limit {
+            break;
+        }
+        let (poll_id, poll) = item?;
+        // A poll with both an end time and an end height is found twice
+        if poll.voting_ended(&env.block) && !expired.iter().any(|(id, _)| *id == poll_id) {
+            expired.push((poll_id, poll));
+        }
+    }
+
+    let mut res = Response::new();
+    let finalized = expired.len();
+    for (poll_id, mut poll) in expired {
+        let bounty = pay_keeper_bounty(deps.storage, &env, &poll_id, &mut poll, &info.sender)?;
+        let ended = finalize_poll(deps.storage, &poll_id, &mut poll)?;
+        polls().save(deps.storage, poll_id, &poll)?;
+        res = res
+            .add_submessages(ended.messages)
+            .add_submessages(bounty)
+            .add_events(ended.events);
+    }
+
+    Ok(res
+        .add_attribute("action", "tick_expired")
+        .add_attribute("sender", info.sender)
+        .add_attribute("finalized", finalized.to_string()))
+}
+
+/// Pays the configured bounty to the keeper ending an expired poll, once per poll. The creator's
+/// deposit pays for the finalization they left to others, the treasury otherwise, and there's no
+/// bounty when neither covers it.
+fn pay_keeper_bounty(
+    storage: &mut dyn Storage,
+    env: &Env,
+    poll_id: &str,
+    poll: &mut Poll,
+    keeper: &Addr,
+) -> Result<Option<SubMsg>, ContractError> {
+    let bounty = match CONFIG.load(storage)?.keeper_bounty {
+        Some(bounty) => bounty,
+        None => return Ok(None),
+    };
+    // Polls their creator closed don't need a keeper
+    if poll.status != PollStatus::Active
+        || !poll.is_expired(&env.block)
+        || KEEPER_BOUNTIES.has(storage, poll_id.to_string())
+    {
+        return Ok(None);
+    }
+    match poll.deposit.as_mut() {
+        Some(deposit) if deposit.denom == bounty.denom && deposit.amount >= bounty.amount => {
+            deposit.amount -= bounty.amount;
+            if deposit.amount.is_zero() {
+                poll.deposit = None;
+            }
+        }
+        _ => match take_from_treasury(storage, &bounty) {
+            Ok(()) => {}
+            Err(ContractError::InsufficientTreasury { .. }) => return Ok(None),
+            Err(err) => return Err(err),
+        },
+    }
+    KEEPER_BOUNTIES.save(storage, poll_id.to_string(), keeper)?;
+    let payout = Payout {
+        recipient: keeper.clone(),
+        amount: bounty,
+        cw20: false,
+    };
+    Ok(Some(send_payout(storage, payout)?))
+}
+
+fn execute_cancel_poll(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    if info.sender != poll.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    if poll.status != PollStatus::Active {
+        return Err(ContractError::InvalidTransition {
+            from: poll.status,
+            to: PollStatus::Cancelled,
+        });
+    }
+    // Once voting is over the result is known, and it's too late to call the poll off
+    if poll.is_expired(&env.block) {
+        return Err(ContractError::PollClosed {});
+    }
+
+    let voided = void_ballots(deps.storage, &poll_id, &poll)?;
+    let mut refunds = refund_creator(deps.storage, &poll_id, &mut poll)?;
+    refunds.extend(refund_vote_fees(deps.storage, &poll_id, &poll)?);
+    release_active_poll(deps.storage, &poll.creator)?;
+    poll.status = PollStatus::Cancelled;
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+    let hook = PollHookMsg::PollCancelled {
+        poll_id: poll_id.clone(),
+    };
+
+    Ok(Response::new()
+        .add_submessages(refunds)
+        .add_submessages(hook_msgs(deps.storage, hook)?)
+        .add_attribute("action", "cancel_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("sender", info.sender)
+        .add_attribute("ballots_voided", voided.to_string()))
+}
+
+/// Marks every ballot of a poll void, returning how many there were
+fn void_ballots(storage: &mut dyn Storage, poll_id: &str, poll: &Poll) -> StdResult<usize> {
+    let poll_id = poll_id.to_string();
+    if poll.private_ballots {
+        let ballots = PRIVATE_BALLOTS
+            .prefix(poll_id.clone())
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (voter, mut ballot) in ballots.iter().cloned() {
+            ballot.void = true;
+            PRIVATE_BALLOTS.save(storage, (poll_id.clone(), voter), &ballot)?;
+        }
+        return Ok(ballots.len());
+    }
+    let voters = POLL_VOTERS
+        .prefix(poll_id.clone())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for voter in voters.iter() {
+        let key = (voter.clone(), poll_id.clone());
+        let mut ballot = BALLOTS.load(storage, key.clone())?;
+        ballot.void = true;
+        BALLOTS.save(storage, key, &ballot)?;
+    }
+    Ok(voters.len())
+}
+
+/// Hands a poll's deposit, unclaimed reward and undrawn raffle prize back to its creator, for
+/// polls ending without a result
+fn refund_creator(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &mut Poll,
+) -> StdResult<Vec<SubMsg>> {
+    let mut msgs = vec![];
+    let mut refunds = vec![];
+    refunds.extend(poll.deposit.take());
+    // Voters have nothing to claim, the whole pool goes back
+    if let Some(pool) = poll.reward.take() {
+        let unclaimed = pool.amount.amount - pool.claimed;
+        if !unclaimed.is_zero() {
+            msgs.push(reward_payout(storage, &pool, &poll.creator, unclaimed)?);
+        }
+    }
+    if let Some(raffle) = RAFFLES.may_load(storage, poll_id.to_string())? {
+        RAFFLES.remove(storage, poll_id.to_string());
+        if raffle.drawn.is_none() {
+            refunds.extend(raffle.prize);
+        }
+    }
+    for amount in refunds {
+        let refund = Payout {
+            recipient: poll.creator.clone(),
+            amount,
+            cw20: false,
+        };
+        msgs.push(send_payout(storage, refund)?);
+    }
+    Ok(msgs)
+}
+
+/// Pays back the vote fees a poll is still holding, and forgets who paid them
+fn refund_vote_fees(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+) -> StdResult<Vec<SubMsg>> {
+    let payers = VOTE_FEES_PAID
+        .prefix(poll_id.to_string())
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    // Fees were already shared out if the poll was finalized
+    let refund = VOTE_FEES_COLLECTED.has(storage, poll_id);
+    VOTE_FEES_COLLECTED.remove(storage, poll_id);
+    let mut msgs = vec![];
+    for (voter, amount) in payers {
+        VOTE_FEES_PAID.remove(storage, (poll_id.to_string(), voter.clone()));
+        if let Some(fee) = poll.vote_fee.as_ref().filter(|_| refund) {
+            let payout = Payout {
+                recipient: voter,
+                amount: coin(amount.u128(), &fee.denom),
+                cw20: false,
+            };
+            msgs.push(send_payout(storage, payout)?);
+        }
+    }
+    Ok(msgs)
+}
+
+fn execute_force_finalize(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !matches!(poll.status, PollStatus::Active | PollStatus::Closed) {
+        return Err(ContractError::InvalidTransition {
+            from: poll.status,
+            to: PollStatus::Finalized,
+        });
+    }
+
+    // Counted as it stands, ballots still to be revealed are left out
+    let res = finalize_poll(deps.storage, &poll_id, &mut poll)?;
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+
+    Ok(res
+        .add_attribute("action", "force_finalize")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("sender", info.sender))
+}
+
+fn execute_invalidate_poll(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    // A finalized poll has already paid out and executed, a cancelled one refunded everything
+    if matches!(poll.status, PollStatus::Finalized | PollStatus::Cancelled) {
+        return Err(ContractError::PollAlreadyEnded {
+            status: poll.status,
+        });
+    }
+
+    let mut refunds = refund_creator(deps.storage, &poll_id, &mut poll)?;
+    refunds.extend(refund_vote_fees(deps.storage, &poll_id, &poll)?);
+    let result = PollResult {
+        winner: None,
+        tied: vec![],
+        outcome: PollOutcome::Invalidated,
+    };
+    let hook = PollHookMsg::PollFinalized {
+        poll_id: poll_id.clone(),
+        result: result.clone(),
+    };
+    if matches!(poll.status, PollStatus::Draft | PollStatus::Active) {
+        release_active_poll(deps.storage, &poll.creator)?;
+    }
+    poll.status = PollStatus::Finalized;
+    poll.result = Some(result);
+    poll.invalid_reason = Some(reason.clone());
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+    update_stats(deps.storage, |stats| stats.polls_finalized += 1)?;
+
+    Ok(Response::new()
+        .add_submessages(refunds)
+        .add_submessages(hook_msgs(deps.storage, hook)?)
+        .add_attribute("action", "invalidate_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("sender", info.sender)
+        .add_attribute("reason", reason))
+}
+
+// One parameter per field of the message, like the other handlers
+#[allow(clippy::too_many_arguments)]
+fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_fee: Option<Coin>,
+    poll_deposit: Option<Coin>,
+    accepted_token: Option<AcceptedToken>,
+    poll_code_id: Option<u64>,
+    limits: Option<PollLimits>,
+    restricted_creation: Option<bool>,
+    rate_limit: Option<RateLimit>,
+    max_active_polls: Option<u32>,
+    nois_proxy: Option<String>,
+    receipt_nft: Option<String>,
+    report_threshold: Option<u32>,
+    vote_fee_share: Option<Decimal>,
+    keeper_bounty: Option<Coin>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(poll_fee) = poll_fee {
+        // A zero fee turns the fee off
+        config.poll_fee = Some(poll_fee).filter(|fee| !fee.amount.is_zero());
+    }
+    if let Some(poll_deposit) = poll_deposit {
+        config.poll_deposit = Some(poll_deposit).filter(|deposit| !deposit.amount.is_zero());
+    }
+    if let Some(token) = accepted_token {
+        set_accepted_token(deps.as_ref(), &mut config, token)?;
+    }
+    if let Some(code_id) = poll_code_id {
+        config.poll_code_id = Some(code_id);
+    }
+    if let Some(limits) = limits {
+        set_limits(&mut config, limits)?;
+    }
+    if let Some(restricted_creation) = restricted_creation {
+        config.restricted_creation = restricted_creation;
+    }
+    if let Some(rate_limit) = rate_limit {
+        config.rate_limit =
+            Some(rate_limit).filter(|limit| limit.max_polls > 0 && limit.window > 0);
+    }
+    if let Some(max_active_polls) = max_active_polls {
+        config.max_active_polls = Some(max_active_polls).filter(|max| *max > 0);
+    }
+    if let Some(proxy) = nois_proxy {
+        config.nois_proxy = if proxy.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_validate(&proxy)?)
+        };
+    }
+    if let Some(nft) = receipt_nft {
+        config.receipt_nft = if nft.is_empty() {
+            None
+        } else {
+            Some(deps.api.addr_validate(&nft)?)
+        };
+    }
+    if let Some(report_threshold) = report_threshold {
+        config.report_threshold = Some(report_threshold).filter(|threshold| *threshold > 0);
+    }
+    if let Some(share) = vote_fee_share {
+        if share > Decimal::one() {
+            return Err(ContractError::InvalidVoteFeeShare {});
+        }
+        config.vote_fee_share = share;
+    }
+    if let Some(bounty) = keeper_bounty {
+        config.keeper_bounty = Some(bounty).filter(|bounty| !bounty.amount.is_zero());
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("admin", config.admin))
+}
+
+fn execute_set_paused(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("sender", info.sender))
+}
+
+/// Checks the sender may import, which only the admin can while the contract is bootstrapping
+fn ensure_importing(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    let config = CONFIG.load(storage)?;
+    if !has_role(storage, &config, sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !config.bootstrap {
+        return Err(ContractError::BootstrapEnded {});
+    }
+    Ok(())
+}
+
+fn execute_import_polls(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    imported: Vec<PollInfo>,
+) -> Result<Response, ContractError> {
+    ensure_importing(deps.storage, &info.sender)?;
+    let count = imported.len();
+    // The old deployment keeps hold of its coins, the ones owed here have to come with the polls
+    let mut held: Vec<Coin> = vec![];
+    for (i, PollInfo { poll_id, poll }) in imported.iter().enumerate() {
+        ensure_importable(deps.storage, &env, poll_id, poll)?;
+        if imported[..i]
+            .iter()
+            .any(|earlier| earlier.poll_id == *poll_id)
+        {
+            return Err(ContractError::PollAlreadyExists {
+                poll_id: poll_id.clone(),
+            });
+        }
+        let reward = poll.reward.as_ref().map(|pool| {
+            coin(
+                (pool.amount.amount - pool.claimed).u128(),
+                &pool.amount.denom,
+            )
+        });
+        for coin in poll.deposit.iter().chain(&reward) {
+            match held.iter_mut().find(|held| held.denom == coin.denom) {
+                Some(held) => held.amount += coin.amount,
+                None => held.push(coin.clone()),
+            }
+        }
+    }
+    held.retain(|coin| !coin.amount.is_zero());
+    let sent: Vec<_> = info
+        .funds
+        .iter()
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+    if sent.len() != held.len() || held.iter().any(|coin| !sent.contains(&coin)) {
+        return Err(ContractError::ImportFundsRequired { held });
+    }
+    for coin in &held {
+        reserve(deps.storage, coin)?;
+    }
+
+    for PollInfo { poll_id, poll } in imported {
+        polls().save(deps.storage, poll_id.clone(), &poll)?;
+        if matches!(poll.status, PollStatus::Draft | PollStatus::Active) {
+            ACTIVE_POLLS.update(
+                deps.storage,
+                poll.creator.clone(),
+                |count| -> StdResult<_> { Ok(count.unwrap_or(0) + 1) },
+            )?;
+        }
+        for tag in &poll.tags {
+            TAG_POLLS.save(deps.storage, (tag.clone(), poll_id.clone()), &Empty {})?;
+        }
+        POLL_COUNT.update(deps.storage, |count| -> StdResult<_> { Ok(count + 1) })?;
+        update_stats(deps.storage, |stats| stats.polls_created += 1)?;
+        let number = STATS.load(deps.storage)?.polls_created;
+        CREATION_ORDER.save(deps.storage, number, &poll_id)?;
+        CREATION_NUMBERS.save(deps.storage, poll_id, &number)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "import_polls")
+        .add_attribute("polls", count.to_string()))
+}
+
+/// Checks an exported poll could have been created and run here as it stands
+fn ensure_importable(
+    storage: &dyn Storage,
+    env: &Env,
+    poll_id: &str,
+    poll: &Poll,
+) -> Result<(), ContractError> {
+    if polls().has(storage, poll_id.to_string())
+        || ARCHIVED_POLLS.has(storage, poll_id.to_string())
+        || POLL_CONTRACTS.has(storage, poll_id.to_string())
+    {
+        return Err(ContractError::PollAlreadyExists {
+            poll_id: poll_id.to_string(),
+        });
+    }
+    let created_height = poll.created_at_height;
+    let created_time = poll.created_at_time;
+    let start = poll.start_time.unwrap_or(created_time);
+    let invalid = poll_id.is_empty()
+        || poll.options.len() < 2
+        || created_height > env.block.height
+        || created_time > env.block.time
+        // Only finalized polls have a result
+        || poll.result.is_some() != (poll.status == PollStatus::Finalized)
+        || poll.end_height.is_some_and(|height| height <= created_height)
+        || poll.end_time.is_some_and(|time| time <= start)
+        || poll.revote_deadline.is_some_and(|deadline| {
+            deadline <= start || poll.end_time.is_some_and(|end| deadline >= end)
+        })
+        || poll
+            .reveal_end_height
+            .is_some_and(|height| height <= poll.end_height.unwrap_or(created_height))
+        || poll
+            .reveal_end_time
+            .is_some_and(|time| time <= poll.end_time.unwrap_or(created_time))
+        // Finalizing divides by these, as CreatePoll makes sure it can
+        || [poll.veto_threshold, poll.quorum, poll.threshold]
+            .iter()
+            .flatten()
+            .any(|share| *share > Decimal::one())
+        || (poll.quorum.is_some() && poll.eligible_power.unwrap_or(0) == 0)
+        // cw20 rewards can't be sent along with the import, and claims divide by the total
+        // weight a finalized poll shares its reward by
+        || poll.reward.as_ref().is_some_and(|pool| {
+            pool.cw20
+                || pool.claimed > pool.amount.amount
+                || (poll.status == PollStatus::Finalized
+                    && pool.total_weight.is_zero()
+                    && pool.claimed < pool.amount.amount)
+        });
+    if invalid {
+        return Err(ContractError::InvalidImport {
+            poll_id: poll_id.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn execute_import_ballots(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ballots: Vec<ImportedBallot>,
+) -> Result<Response, ContractError> {
+    ensure_importing(deps.storage, &info.sender)?;
+    let count = ballots.len();
+    for ImportedBallot {
+        poll_id,
+        voter,
+        ballot,
+    } in ballots
+    {
+        let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+            Some(poll) => poll,
+            None => return Err(ContractError::PollNotFound {}),
+        };
+        // Only a cancelled poll's ballots are void, and every other ballot carries some weight
+        if ballot.void != (poll.status == PollStatus::Cancelled)
+            || (!ballot.void && ballot.weight.is_zero())
+        {
+            return Err(ContractError::InvalidImport { poll_id });
+        }
+        let mut counts = VOTE_COUNTS
+            .may_load(deps.storage, &poll_id)?
+            .unwrap_or_default();
+        if !ballot.void {
+            counts.total_votes += ballot.weight;
+            counts.unique_voters += 1;
+        }
+        // A finalized poll's shares were fixed by the weight counted then, more would overdraw it
+        if poll.status == PollStatus::Finalized
+            && poll
+                .reward
+                .as_ref()
+                .is_some_and(|pool| counts.total_votes > pool.total_weight)
+        {
+            return Err(ContractError::InvalidImport { poll_id });
+        }
+
+        // Private polls were exported by voter hash, which is kept as is
+        if poll.private_ballots {
+            let key = (poll_id.clone(), voter);
+            if PRIVATE_BALLOTS.has(deps.storage, key.clone()) {
+                return Err(ContractError::BallotAlreadyExists {});
+            }
+            PRIVATE_BALLOTS.save(deps.storage, key, &ballot)?;
+        } else {
+            let voter = deps.api.addr_validate(&voter)?;
+            if BALLOTS.has(deps.storage, (voter.clone(), poll_id.clone())) {
+                return Err(ContractError::BallotAlreadyExists {});
+            }
+            BALLOTS.save(deps.storage, (voter.clone(), poll_id.clone()), &ballot)?;
+            POLL_VOTERS.save(deps.storage, (poll_id.clone(), voter), &Empty {})?;
+        }
+        if ballot.void {
+            continue;
+        }
+
+        VOTE_COUNTS.save(deps.storage, &poll_id, &counts)?;
+        for (option, votes) in ballot_votes(&poll, &ballot) {
+            let position = option_position(&poll, &option)? as u32;
+            let tally = TALLIES
+                .may_load(deps.storage, (&poll_id, position))?
+                .unwrap_or_default()
+                .checked_add(votes)
+                .map_err(|_| ContractError::TallyOverflow { option })?;
+            TALLIES.save(deps.storage, (&poll_id, position), &tally, env.block.height)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "import_ballots")
+        .add_attribute("ballots", count.to_string()))
+}
+
+fn execute_end_bootstrap(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_importing(deps.storage, &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    config.bootstrap = false;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "end_bootstrap")
+        .add_attribute("sender", info.sender))
+}
+
+fn execute_set_role(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    role: Option<Role>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    let res = match role {
+        Some(role) => {
+            ROLES.save(deps.storage, address.clone(), &role)?;
+            Response::new()
+                .add_attribute("action", "grant_role")
+                .add_attribute("role", format!("{:?}", role).to_lowercase())
+        }
+        None => {
+            ROLES.remove(deps.storage, address.clone());
+            Response::new().add_attribute("action", "revoke_role")
+        }
+    };
+    Ok(res.add_attribute("address", address))
+}
+
+fn execute_set_banned(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address: String,
+    banned: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    let action = if banned {
+        BANNED.save(deps.storage, address.clone(), &Empty {})?;
+        "ban_address"
+    } else {
+        BANNED.remove(deps.storage, address.clone());
+        "unban_address"
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("address", address))
+}
+
+fn execute_update_banned_words(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    words: Vec<String>,
+    banned: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    for word in words.iter() {
+        // An empty word would be found in every text
+        let word = word.trim().to_lowercase();
+        if word.is_empty() {
+            return Err(ContractError::InvalidBannedWord {});
+        }
+        if banned {
+            BANNED_WORDS.save(deps.storage, word, &Empty {})?;
+        } else {
+            BANNED_WORDS.remove(deps.storage, word);
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute(
+            "action",
+            if banned {
+                "add_banned_words"
+            } else {
+                "remove_banned_words"
+            },
+        )
+        .add_attribute("words", words.len().to_string()))
+}
+
+/// Fails if any of the texts contains a banned word, ignoring case
+pub(crate) fn ensure_no_banned_words<'a>(
+    storage: &dyn Storage,
+    texts: impl IntoIterator<Item = &'a String>,
+) -> Result<(), ContractError> {
+    let words = BANNED_WORDS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if words.is_empty() {
+        return Ok(());
+    }
+    for text in texts {
+        let text = text.to_lowercase();
+        if let Some(word) = words.iter().find(|word| text.contains(word.as_str())) {
+            return Err(ContractError::BannedWord { word: word.clone() });
+        }
+    }
+    Ok(())
+}
+
+/// Counts a creation against the creator's current window, opening a new one once it ran out
+fn count_creation(
+    storage: &mut dyn Storage,
+    env: &Env,
+    creator: &Addr,
+    rate_limit: RateLimit,
+) -> Result<(), ContractError> {
+    let now = env.block.time;
+    let mut window = CREATION_WINDOWS
+        .may_load(storage, creator.clone())?
+        .filter(|window| now < window.start.plus_seconds(rate_limit.window))
+        .unwrap_or(CreationWindow {
+            start: now,
+            count: 0,
+        });
+    if window.count >= rate_limit.max_polls {
+        return Err(ContractError::RateLimited {
+            retry_after: window.start.plus_seconds(rate_limit.window),
+        });
+    }
+    window.count += 1;
+    CREATION_WINDOWS.save(storage, creator.clone(), &window)?;
+    Ok(())
+}
+
+pub(crate) fn ensure_not_banned(
+    storage: &dyn Storage,
+    address: &Addr,
+) -> Result<(), ContractError> {
+    if BANNED.has(storage, address.clone()) {
+        return Err(ContractError::Banned {});
+    }
+    Ok(())
+}
+
+/// Whether an address holds `role` or one above it
+pub(crate) fn has_role(
+    storage: &dyn Storage,
+    config: &Config,
+    address: &Addr,
+    role: Role,
+) -> StdResult<bool> {
+    if *address == config.admin {
+        return Ok(true);
+    }
+    let granted = ROLES.may_load(storage, address.clone())?;
+    Ok(granted.is_some_and(|granted| granted >= role))
+}
+
+fn execute_propose_new_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    deadline: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    let deadline = deadline
+        .unwrap_or_else(|| Expiration::AtTime(env.block.time.plus_seconds(ADMIN_CLAIM_PERIOD)));
+    if deadline.is_expired(&env.block) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    let pending = PendingAdmin {
+        address: address.clone(),
+        deadline,
+    };
+    PENDING_ADMIN.save(deps.storage, &pending)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_admin")
+        .add_attribute("pending_admin", address)
+        .add_attribute("deadline", deadline.to_string()))
+}
+
+fn execute_accept_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = match PENDING_ADMIN.may_load(deps.storage)? {
+        Some(pending) => pending,
+        None => return Err(ContractError::NoPendingAdmin {}),
+    };
+    if info.sender != pending.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending.deadline.is_expired(&env.block) {
+        return Err(ContractError::AdminClaimExpired {});
+    }
+    PENDING_ADMIN.remove(deps.storage);
+    let mut config = CONFIG.load(deps.storage)?;
+    config.admin = pending.address;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("admin", config.admin))
+}
+
+fn set_accepted_token(
+    deps: Deps,
+    config: &mut Config,
+    token: AcceptedToken,
+) -> Result<(), ContractError> {
+    config.accepted_token = Some(deps.api.addr_validate(&token.address)?);
+    config.cw20_poll_fee = token.poll_fee.filter(|fee| !fee.is_zero());
+    Ok(())
+}
+
+fn apply_settings(config: &mut Config, settings: Settings) -> Result<(), ContractError> {
+    if let Some(max_options) = settings.max_options {
+        set_limits(
+            config,
+            PollLimits {
+                max_options,
+                ..config.limits
+            },
+        )?;
+    }
+    if settings.default_poll_duration == Some(0) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    if settings
+        .vote_fee_share
+        .is_some_and(|share| share > Decimal::one())
+    {
+        return Err(ContractError::InvalidVoteFeeShare {});
+    }
+    config.default_poll_duration = settings.default_poll_duration;
+    config.vote_fee_share = settings.vote_fee_share.unwrap_or_default();
+    config.keeper_bounty = settings
+        .keeper_bounty
+        .filter(|bounty| !bounty.amount.is_zero());
+    config.restricted_creation = settings.restricted_creation;
+    Ok(())
+}
+
+fn set_limits(config: &mut Config, limits: PollLimits) -> Result<(), ContractError> {
+    if limits.max_question_len == 0 || limits.max_option_len == 0 || limits.max_options < 2 {
+        return Err(ContractError::InvalidLimits {});
+    }
+    config.limits = limits;
+    Ok(())
+}
+
+fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // The message comes from the token contract, it vouches for the sender and amount
+    let config = CONFIG.load(deps.storage)?;
+    if config.accepted_token.as_ref() != Some(&info.sender) {
+        return Err(ContractError::UnacceptedToken {});
+    }
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    let token = info.sender;
+
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::FundPoll { poll_id } => {
+            execute_fund_poll(deps, env, sender, token, wrapper.amount, poll_id)
+        }
+        ReceiveMsg::PayPollFee {} => {
+            let fee = config.cw20_poll_fee.ok_or(ContractError::NoCw20Fee {})?;
+            if wrapper.amount < fee {
+                return Err(ContractError::InsufficientFee {
+                    fee: coin(fee.u128(), token),
+                });
+            }
+            // Whole fees buy a credit each, whatever is left over goes back
+            let bought = u32::try_from((wrapper.amount / fee).u128())
+                .map_err(|_| StdError::generic_err("too many fee credits"))?;
+            let paid = fee * Uint128::from(bought);
+            CW20_TREASURY.update(deps.storage, token.clone(), |balance| -> StdResult<_> {
+                Ok(balance.unwrap_or_default().checked_add(paid)?)
+            })?;
+            let credits = FEE_CREDITS.update(deps.storage, sender.clone(), |credits| {
+                credits
+                    .unwrap_or(0)
+                    .checked_add(bought)
+                    .ok_or_else(|| StdError::generic_err("too many fee credits"))
+            })?;
+            let mut res = Response::new();
+            let change = wrapper.amount - paid;
+            if !change.is_zero() {
+                let refund = Payout {
+                    recipient: sender.clone(),
+                    amount: coin(change.u128(), token),
+                    cw20: true,
+                };
+                res = res.add_submessage(send_payout(deps.storage, refund)?);
+            }
+
+            Ok(res
+                .add_attribute("action", "pay_poll_fee")
+                .add_attribute("sender", sender)
+                .add_attribute("credits", credits.to_string()))
+        }
+    }
+}
+
+fn execute_fund_poll(
+    deps: DepsMut,
+    _env: Env,
+    sender: Addr,
+    token: Addr,
+    amount: Uint128,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let mut poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    // Shares are fixed at finalization, and nobody could ever claim from a cancelled poll
+    if !matches!(poll.status, PollStatus::Draft | PollStatus::Active) {
+        return Err(ContractError::PollClosed {});
+    }
+
+    match poll.reward.as_mut() {
+        Some(pool) if pool.cw20 && pool.amount.denom == token.as_str() => {
+            pool.amount.amount += amount;
+        }
+        Some(_) => return Err(ContractError::RewardDenomMismatch {}),
+        None => {
+            poll.reward = Some(RewardPool {
+                amount: coin(amount.u128(), token),
+                total_weight: Uint128::zero(),
+                claimed: Uint128::zero(),
+                cw20: true,
+            })
+        }
+    }
+    polls().save(deps.storage, poll_id.clone(), &poll)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("sender", sender)
+        .add_attribute("amount", amount))
+}
+
+fn execute_update_hooks(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addr: String,
+    add: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let hook = deps.api.addr_validate(&addr)?;
+    let registered = HOOKS.has(deps.storage, hook.clone());
+
+    let action = if add {
+        if registered {
+            return Err(ContractError::HookAlreadyRegistered { addr });
+        }
+        HOOKS.save(deps.storage, hook.clone(), &Empty {})?;
+        "add_hook"
+    } else {
+        if !registered {
+            return Err(ContractError::HookNotRegistered { addr });
+        }
+        HOOKS.remove(deps.storage, hook.clone());
+        "remove_hook"
+    };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("hook", hook))
+}
+
+fn execute_sweep(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender,
+    };
+
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    let held = HELD
+        .may_load(deps.storage, denom.clone())?
+        .unwrap_or_default();
+    let stranded = balance.saturating_sub(held);
+    if stranded.is_zero() {
+        return Err(ContractError::NothingToSweep { denom });
+    }
+    // Owed to the recipient from now on, like anything else paid out
+    let payout = Payout {
+        recipient: recipient.clone(),
+        amount: coin(stranded.u128(), &denom),
+        cw20: false,
+    };
+    reserve(deps.storage, &payout.amount)?;
+
+    Ok(Response::new()
+        .add_submessage(send_payout(deps.storage, payout)?)
+        .add_attribute("action", "sweep")
+        .add_attribute("denom", denom)
+        .add_attribute("amount", stranded)
+        .add_attribute("recipient", recipient))
+}
+
+fn execute_retry_payouts(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let payouts = FAILED_PAYOUTS
+        .may_load(deps.storage, info.sender.clone())?
+        .unwrap_or_default();
+    if payouts.is_empty() {
+        return Err(ContractError::NoFailedPayouts {});
+    }
+    FAILED_PAYOUTS.remove(deps.storage, info.sender.clone());
+
+    let count = payouts.len();
+    let mut res = Response::new();
+    for payout in payouts {
+        res = res.add_submessage(send_payout(deps.storage, payout)?);
+    }
+    Ok(res
+        .add_attribute("action", "retry_payouts")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("payouts", count.to_string()))
+}
+
+fn execute_withdraw_fees(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender,
+    };
+
+    let amount = TREASURY
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| coin(amount.u128(), denom)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let tokens = CW20_TREASURY
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if amount.is_empty() && tokens.is_empty() {
+        return Err(ContractError::NoFees {});
+    }
+    for fee in amount.iter() {
+        TREASURY.remove(deps.storage, fee.denom.clone());
+        for source in TreasurySource::ALL {
+            TREASURY_SOURCES.remove(deps.storage, (source.key(), fee.denom.clone()));
+        }
+    }
+
+    let mut res = Response::new();
+    for fee in amount {
+        let payout = Payout {
+            recipient: recipient.clone(),
+            amount: fee,
+            cw20: false,
+        };
+        res = res.add_submessage(send_payout(deps.storage, payout)?);
+    }
+    for (token, amount) in tokens {
+        CW20_TREASURY.remove(deps.storage, token.clone());
+        let payout = Payout {
+            recipient: recipient.clone(),
+            amount: coin(amount.u128(), token),
+            cw20: true,
+        };
+        res = res.add_submessage(send_payout(deps.storage, payout)?);
+    }
+
+    Ok(res
+        .add_attribute("action", "withdraw_fees")
+        .add_attribute("recipient", recipient))
+}
+
+fn execute_spend(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    to: String,
+    amount: Coin,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &info.sender, Role::Admin)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let to = deps.api.addr_validate(&to)?;
+    if amount.amount.is_zero() {
+        return Err(ContractError::InsufficientTreasury {
+            available: coin(0, &amount.denom),
+        });
+    }
+    take_from_treasury(deps.storage, &amount)?;
+
+    let payout = Payout {
+        recipient: to.clone(),
+        amount: amount.clone(),
+        cw20: false,
+    };
+    Ok(Response::new()
+        .add_submessage(send_payout(deps.storage, payout)?)
+        .add_attribute("action", "spend")
+        .add_attribute("to", to)
+        .add_attribute("amount", amount.to_string()))
+}
+
+fn take_from_treasury(storage: &mut dyn Storage, amount: &Coin) -> Result<(), ContractError> {
+    let balance = TREASURY
+        .may_load(storage, amount.denom.clone())?
+        .unwrap_or_default();
+    if amount.amount > balance {
+        return Err(ContractError::InsufficientTreasury {
+            available: coin(balance.u128(), &amount.denom),
+        });
+    }
+    TREASURY.save(storage, amount.denom.clone(), &(balance - amount.amount))?;
+    // The sources are drawn down one after the other
+    let mut left = amount.amount;
+    for source in TreasurySource::ALL {
+        let key = (source.key(), amount.denom.clone());
+        let held = TREASURY_SOURCES
+            .may_load(storage, key.clone())?
+            .unwrap_or_default();
+        let spent = held.min(left);
+        left -= spent;
+        if held == spent {
+            TREASURY_SOURCES.remove(storage, key);
+        } else {
+            TREASURY_SOURCES.save(storage, key, &(held - spent))?;
+        }
+    }
+    Ok(())
+}
+
+fn execute_claim_creator_fees(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let fees = CREATOR_FEES
+        .prefix(info.sender.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if fees.is_empty() {
+        return Err(ContractError::NoFees {});
+    }
+
+    let mut res = Response::new();
+    for (denom, amount) in fees {
+        CREATOR_FEES.remove(deps.storage, (info.sender.clone(), denom.clone()));
+        let payout = Payout {
+            recipient: info.sender.clone(),
+            amount: coin(amount.u128(), denom),
+            cw20: false,
+        };
+        res = res.add_submessage(send_payout(deps.storage, payout)?);
+    }
+
+    Ok(res
+        .add_attribute("action", "claim_creator_fees")
+        .add_attribute("creator", info.sender))
+}
+
+/// The result a poll would end with as things stand, along with the instant-runoff rounds of a
+/// ranked choice poll and the total weight of its ballots when that's needed
+pub(crate) fn poll_result(
+    storage: &dyn Storage,
+    poll_id: &str,
+    poll: &Poll,
+) -> StdResult<(PollResult, Option<Vec<TallyRound>>, u128)> {
+    // The tallies the winner came out of, the final round for ranked choice
+    let (mut result, tallies, rounds) = match poll.tally_type {
+        _ if poll.gov_style => {
+            let tallies = poll.tallies(storage, poll_id)?;
+            (gov_result(poll, &tallies), tallies, None)
+        }
+        TallyType::Plurality | TallyType::Quadratic => {
+            let tallies = poll.tallies(storage, poll_id)?;
+            (tally_result(&tallies), tallies, None)
+        }
+        TallyType::RankedChoice => {
+            let ballots = poll_ballots(storage, poll, poll_id)?;
+            let labels: Vec<String> = poll
+                .options
+                .iter()
+                .map(|option| option.label.clone())
+                .collect();
+            let (result, rounds) = instant_runoff(&labels, &ballots);
+            let tallies = rounds
+                .last()
+                .map(|round| round.tallies.clone())
+                .unwrap_or_default();
+            (result, tallies, Some(rounds))
+        }
+    };
+    let mut turnout = 0;
+    if poll.quorum.is_some() || poll.threshold.is_some() || poll.reward.is_some() {
+        let counts = VOTE_COUNTS.may_load(storage, poll_id)?.unwrap_or_default();
+        turnout = counts.total_votes.u128();
+        result.outcome = participation_outcome(poll, &result, turnout, &tallies);
+    }
+    Ok((result, rounds, turnout))
+}
+
+/// Records the result on the poll, marks it finalized, settles the deposit and describes the
+/// outcome as an event
+pub(crate) fn finalize_poll(
+    storage: &mut dyn Storage,
+    poll_id: &str,
+    poll: &mut Poll,
+) -> StdResult<Response> {
+    let (result, rounds, turnout) = poll_result(storage, poll_id, poll)?;
+    if let Some(rounds) = rounds {
+        TALLY_ROUNDS.save(storage, poll_id.to_string(), &rounds)?;
+    }
+    if let Some(pool) = poll.reward.as_mut() {
+        pool.total_weight = Uint128::from(turnout);
+    }
+    let mut event = Event::new("poll_ended")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("winner", result.winner.clone().unwrap_or_default())
+        .add_attribute("tie", (!result.tied.is_empty()).to_string())
+        .add_attribute("outcome", format!("{:?}", result.outcome).to_lowercase());
+    let attestation = attest_result(storage, poll_id, poll)?;
+    RESULT_ATTESTATIONS.save(storage, poll_id.to_string(), &attestation)?;
+    event = event.add_attribute("attestation", attestation);
+
+    // The deposit comes back unless the poll missed its quorum or was vetoed
+    let mut res = Response::new();
+    if let Some(deposit) = poll.deposit.take() {
+        if matches!(
+            result.outcome,
+            PollOutcome::QuorumNotMet | PollOutcome::Vetoed
+        ) {
+            add_to_treasury(storage, TreasurySource::SlashedDeposits, &deposit)?;
+            event = event.add_attribute("deposit", "slashed");
+        } else {
+            let refund = Payout {
+                recipient: poll.creator.clone(),
+                amount: deposit,
+                cw20: false,
+            };
+            res = res.add_submessage(send_payout(storage, refund)?);
+            event = event.add_attribute("deposit", "refunded");
+        }
+    }
+    if let Some(fee) = &poll.vote_fee {
+        let collected = VOTE_FEES_COLLECTED
+            .may_load(storage, poll_id)?
+            .unwrap_or_default();
+        VOTE_FEES_COLLECTED.remove(storage, poll_id);
+        let kept = collected * CONFIG.load(storage)?.vote_fee_share;
+        let kept = coin(kept.u128(), &fee.denom);
+        add_to_treasury(storage, TreasurySource::VoteFees, &kept)?;
+        CREATOR_FEES.update(
+            storage,
+            (poll.creator.clone(), fee.denom.clone()),
+            |fees| -> StdResult<_> { Ok(fees.unwrap_or_default() + collected - kept.amount) },
+        )?;
+    }
+    // Nobody to share a reward with, so it goes back to the creator
+    if let Some(pool) = poll.reward.as_mut() {
+        if pool.total_weight.is_zero() {
+            pool.claimed = pool.amount.amount;
+            let amount = pool.amount.amount;
+            res = res.add_submessage(reward_payout(storage, pool, &poll.creator, amount)?);
+        }
+    }
+
+    // A passed proposal executes along with the finalization, which fails while they can't
+    if result.outcome == PollOutcome::Passed {
+        let winner = result.winner.as_ref();
+        if let Some((_, msgs)) = poll.msgs.iter().find(|(option, _)| Some(option) == winner) {
+            res = res.add_messages(msgs.clone());
+            event = event.add_attribute("executed", msgs.len().to_string());
+        }
+        let payload = winner
+            .and_then(|winner| {
+                poll.options
+                    .iter()
+                    .position(|option| option.label == *winner)
+            })
+            .and_then(|position| poll.payload_per_option.get(position));
+        if let (Some(executor), Some(payload)) = (&poll.executor, payload) {
+            res = res.add_message(WasmMsg::Execute {
+                contract_addr: executor.to_string(),
+                msg: payload.clone(),
+                funds: vec![],
+            });
+            event = event.add_attribute("executor", executor);
+        }
+    }
+
+    let hook = PollHookMsg::PollFinalized {
+        poll_id: poll_id.to_string(),
+        result: result.clone(),
+    };
+    if poll.status == PollStatus::Active {
+        release_active_poll(storage, &poll.creator)?;
+    }
+    poll.status = PollStatus::Finalized;
+    poll.result = Some(result);
+    update_stats(storage, |stats| stats.polls_finalized += 1)?;
+    Ok(res
+        .add_submessages(hook_msgs(storage, hook)?)
+        .add_event(event))
+}
+
+/// The hash of a poll's final tallies and voters, for anyone to recompute from AttestedResult
+fn attest_result(storage: &dyn Storage, poll_id: &str, poll: &Poll) -> StdResult<String> {
+    let mut tallies = poll.tallies(storage, poll_id)?;
+    tallies.sort();
+    // Both come out of storage in ascending order already
+    let voters = if poll.private_ballots {
+        PRIVATE_BALLOTS
+            .prefix(poll_id.to_string())
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?
+    } else {
+        POLL_VOTERS
+            .prefix(poll_id.to_string())
+            .keys(storage, None, None, Order::Ascending)
+            .map(|voter| voter.map(String::from))
+            .collect::<StdResult<Vec<_>>>()?
+    };
+    let payload = AttestedResult {
+        poll_id: poll_id.to_string(),
+        tallies,
+        voters,
+    };
+    Ok(hex::encode(Sha256::digest(to_vec(&payload)?)))
+}
+
+/// Frees a slot of the creator's active poll cap, once a poll stops accepting votes
+fn release_active_poll(storage: &mut dyn Storage, creator: &Addr) -> StdResult<()> {
+    ACTIVE_POLLS.update(storage, creator.clone(), |count| -> StdResult<_> {
+        Ok(count.unwrap_or(0).saturating_sub(1))
+    })?;
+    Ok(())
+}
+
+/// Applies a change to an address's reputation, counting the current period towards its streak
+fn update_reputation(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    env: &Env,
+    update: impl FnOnce(&mut Reputation),
+) -> StdResult<()> {
+    let mut reputation = reputations()
+        .may_load(storage, address.clone())?
+        .unwrap_or_default();
+    let period = env.block.time.seconds() / REPUTATION_PERIOD;
+    if reputation.streak == 0 || period > reputation.last_active_period + 1 {
+        reputation.streak = 1;
+    } else if period == reputation.last_active_period + 1 {
+        reputation.streak += 1;
+    }
+    reputation.last_active_period = period;
+    update(&mut reputation);
+    reputations().save(storage, address.clone(), &reputation)
+}
+
+/// Applies a change to the contract-wide counters
+fn update_stats(storage: &mut dyn Storage, update: impl FnOnce(&mut Stats)) -> StdResult<()> {
+    let mut stats = STATS.load(storage)?;
+    update(&mut stats);
+    STATS.save(storage, &stats)
+}
+
+/// Notifies every registered hook, a failing hook is only reported in the reply
+fn hook_msgs(storage: &dyn Storage, hook: PollHookMsg) -> StdResult<Vec<SubMsg>> {
+    let msg = to_binary(&HookExecuteMsg::PollHook(hook))?;
+    HOOKS
+        .keys(storage, None, None, Order::Ascending)
+        .map(|addr| {
+            let execute = WasmMsg::Execute {
+                contract_addr: addr?.into_string(),
+                msg: msg.clone(),
+                funds: vec![],
+            };
+            Ok(SubMsg::reply_on_error(execute, HOOK_REPLY_ID))
+        })
+        .collect()
+}
+
+/// Pays part of a reward pool out, through the token contract for cw20 pools
+fn reward_payout(
+    storage: &mut dyn Storage,
+    pool: &RewardPool,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<SubMsg> {
+    let payout = Payout {
+        recipient: recipient.clone(),
+        amount: coin(amount.u128(), &pool.amount.denom),
+        cw20: pool.cw20,
+    };
+    send_payout(storage, payout)
+}
+
+/// Sends a payout as a submessage, a failed transfer is kept for the recipient to retry instead
+/// of reverting the whole transaction
+pub(crate) fn send_payout(storage: &mut dyn Storage, payout: Payout) -> StdResult<SubMsg> {
+    let msg: CosmosMsg = if payout.cw20 {
+        WasmMsg::Execute {
+            contract_addr: payout.amount.denom.clone(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: payout.recipient.to_string(),
+                amount: payout.amount.amount,
+            })?,
+            funds: vec![],
+        }
+        .into()
+    } else {
+        BankMsg::Send {
+            to_address: payout.recipient.to_string(),
+            amount: vec![payout.amount.clone()],
+        }
+        .into()
+    };
+    if !payout.cw20 {
+        release(storage, &payout.amount)?;
+    }
+    // Messages run in between, like hooks or a poll's executor, can send payouts of their own
+    // before this one replies, so each payout is found by its reply id
+    let id = NEXT_PAYOUT_REPLY_ID
+        .may_load(storage)?
+        .unwrap_or(FIRST_PAYOUT_REPLY_ID);
+    NEXT_PAYOUT_REPLY_ID.save(storage, &(id + 1))?;
+    PENDING_PAYOUTS.save(storage, id, &payout)?;
+    Ok(SubMsg::reply_always(msg, id))
+}
+
+/// Counts native coins the contract took in for someone towards what it holds
+pub(crate) fn reserve(storage: &mut dyn Storage, amount: &Coin) -> StdResult<()> {
+    HELD.update(storage, amount.denom.clone(), |held| -> StdResult<_> {
+        Ok(held.unwrap_or_default() + amount.amount)
+    })?;
+    Ok(())
+}
+
+/// Takes native coins leaving the contract back out of what it holds
+fn release(storage: &mut dyn Storage, amount: &Coin) -> StdResult<()> {
+    let held = HELD
+        .may_load(storage, amount.denom.clone())?
+        .unwrap_or_default()
+        .saturating_sub(amount.amount);
+    if held.is_zero() {
+        HELD.remove(storage, amount.denom.clone());
+    } else {
+        HELD.save(storage, amount.denom.clone(), &held)?;
+    }
+    Ok(())
+}
+
+/// Takes `amount` out of `funds`, returning false when they don't cover it
+fn deduct(funds: &mut [Coin], amount: &Coin) -> bool {
+    match funds.iter_mut().find(|paid| paid.denom == amount.denom) {
+        Some(paid) if paid.amount >= amount.amount => {
+            paid.amount -= amount.amount;
+            true
+        }
+        _ => false,
+    }
+}
+
+fn add_to_treasury(
+    storage: &mut dyn Storage,
+    source: TreasurySource,
+    amount: &Coin,
+) -> StdResult<()> {
+    TREASURY.update(storage, amount.denom.clone(), |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_add(amount.amount)?)
+    })?;
+    TREASURY_SOURCES.update(
+        storage,
+        (source.key(), amount.denom.clone()),
+        |balance| -> StdResult<_> { Ok(balance.unwrap_or_default().checked_add(amount.amount)?) },
+    )?;
+    Ok(())
+}
+
+/// Applies the poll's quorum and pass threshold on top of the tally result
+fn participation_outcome(
+    poll: &Poll,
+    result: &PollResult,
+    turnout: u128,
+    tallies: &[(String, Uint128)],
+) -> PollOutcome {
+    if let (Some(quorum), Some(eligible)) = (poll.quorum, poll.eligible_power) {
+        if Decimal::from_ratio(turnout, eligible) < quorum {
+            return PollOutcome::QuorumNotMet;
+        }
+    }
+    if let (PollOutcome::Passed, Some(threshold), Some(winner)) =
+        (result.outcome, poll.threshold, &result.winner)
+    {
+        // Abstaining counts towards the quorum but not the threshold
+        let counted: u128 = tallies
+            .iter()
+            .filter(|option| !(poll.gov_style && option.0 == ABSTAIN_OPTION))
+            .map(|option| option.1.u128())
+            .sum();
+        let votes = tallies
+            .iter()
+            .find(|option| &option.0 == winner)
+            .map_or(Uint128::zero(), |option| option.1);
+        if Decimal::from_ratio(votes, counted) < threshold {
+            return PollOutcome::Rejected;
+        }
+    }
+    result.outcome
+}
+
+/// Every ballot cast in a poll
+fn poll_ballots(storage: &dyn Storage, poll: &Poll, poll_id: &str) -> StdResult<Vec<Ballot>> {
+    if poll.private_ballots {
+        return PRIVATE_BALLOTS
+            .prefix(poll_id.to_string())
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, ballot)| ballot))
+            .collect();
+    }
+    POLL_VOTERS
+        .prefix(poll_id.to_string())
+        .keys(storage, None, None, Order::Ascending)
+        .map(|voter| BALLOTS.load(storage, (voter?, poll_id.to_string())))
+        .collect()
+}
+
+/// A voter's ballot, looked up under the hashed key for private polls
+fn load_ballot(
+    storage: &dyn Storage,
+    poll: &Poll,
+    poll_id: &str,
+    voter: &Addr,
+) -> StdResult<Option<Ballot>> {
+    if poll.private_ballots {
+        let key = (poll_id.to_string(), voter_hash(poll_id, voter.as_str())?);
+        return PRIVATE_BALLOTS.may_load(storage, key);
+    }
+    BALLOTS.may_load(storage, (voter.clone(), poll_id.to_string()))
+}
+
+/// Stores a voter's ballot, private ballots stay out of the voter index
+fn save_ballot(
+    storage: &mut dyn Storage,
+    poll: &Poll,
+    poll_id: &str,
+    voter: &Addr,
+    ballot: &Ballot,
+) -> StdResult<()> {
+    if poll.private_ballots {
+        let key = (poll_id.to_string(), voter_hash(poll_id, voter.as_str())?);
+        return PRIVATE_BALLOTS.save(storage, key, ballot);
+    }
+    BALLOTS.save(storage, (voter.clone(), poll_id.to_string()), ballot)?;
+    POLL_VOTERS.save(storage, (poll_id.to_string(), voter.clone()), &Empty {})
+}
+
+fn remove_ballot(
+    storage: &mut dyn Storage,
+    poll: &Poll,
+    poll_id: &str,
+    voter: &Addr,
+) -> StdResult<()> {
+    if poll.private_ballots {
+        let key = (poll_id.to_string(), voter_hash(poll_id, voter.as_str())?);
+        PRIVATE_BALLOTS.remove(storage, key);
+    } else {
+        BALLOTS.remove(storage, (voter.clone(), poll_id.to_string()));
+        POLL_VOTERS.remove(storage, (poll_id.to_string(), voter.clone()));
+    }
+    Ok(())
+}
+
+/// Runs instant-runoff elimination over ranked ballots, returning the result and every round.
+///
+/// Each round a ballot counts for its highest ranked option still in the race. An option holding
+/// a strict majority of the counted weight wins, otherwise the lowest options are eliminated. When
+/// every remaining option is tied for lowest they are reported as a tie.
+fn instant_runoff(options: &[String], ballots: &[Ballot]) -> (PollResult, Vec<TallyRound>) {
+    let mut continuing = options.to_vec();
+    let mut rounds = vec![];
+    loop {
+        let mut tallies: Vec<(String, Uint128)> = continuing
+            .iter()
+            .map(|option| (option.clone(), Uint128::zero()))
+            .collect();
+        let mut counted = Uint128::zero();
+        for ballot in ballots {
+            let choice = ballot
+                .options
+                .iter()
+                .find(|option| continuing.contains(option));
+            let tally = choice.and_then(|choice| tallies.iter_mut().find(|t| &t.0 == choice));
+            if let Some(tally) = tally {
+                // Each tally is at most the sum of the first preferences that fit in the poll
+                tally.1 = tally.1.saturating_add(ballot.weight);
+                counted = counted.saturating_add(ballot.weight);
+            }
+        }
+
+        let highest = tallies
+            .iter()
+            .map(|tally| tally.1)
+            .max()
+            .unwrap_or_default();
+        if highest.is_zero() {
+            rounds.push(TallyRound {
+                tallies,
+                eliminated: vec![],
+            });
+            let result = PollResult {
+                winner: None,
+                tied: vec![],
+                outcome: PollOutcome::Rejected,
+            };
+            return (result, rounds);
+        }
+        // A strict majority, without doubling the tally past the largest weight
+        if highest > counted - highest {
+            let winner = tallies
+                .iter()
+                .find(|tally| tally.1 == highest)
+                .map(|tally| tally.0.clone());
+            rounds.push(TallyRound {
+                tallies,
+                eliminated: vec![],
+            });
+            let result = PollResult {
+                winner,
+                tied: vec![],
+                outcome: PollOutcome::Passed,
+            };
+            return (result, rounds);
+        }
+
+        let lowest = tallies
+            .iter()
+            .map(|tally| tally.1)
+            .min()
+            .unwrap_or_default();
+        let eliminated: Vec<String> = tallies
+            .iter()
+            .filter(|tally| tally.1 == lowest)
+            .map(|tally| tally.0.clone())
+            .collect();
+        if eliminated.len() == tallies.len() {
+            rounds.push(TallyRound {
+                tallies,
+                eliminated: vec![],
+            });
+            let result = PollResult {
+                winner: None,
+                tied: eliminated,
+                outcome: PollOutcome::Rejected,
+            };
+            return (result, rounds);
+        }
+
+        continuing.retain(|option| !eliminated.contains(option));
+        rounds.push(TallyRound {
+            tallies,
+            eliminated,
+        });
+    }
+}
+
+/// Picks the option with the highest tally, reporting every option sharing it on a tie
+fn tally_result(options: &[(String, Uint128)]) -> PollResult {
+    let highest = options
+        .iter()
+        .map(|option| option.1)
+        .max()
+        .unwrap_or_default();
+    if highest.is_zero() {
+        return PollResult {
+            winner: None,
+            tied: vec![],
+            outcome: PollOutcome::Rejected,
+        };
+    }
+
+    let leaders: Vec<String> = options
+        .iter()
+        .filter(|option| option.1 == highest)
+        .map(|option| option.0.clone())
+        .collect();
+    if leaders.len() == 1 {
+        PollResult {
+            winner: leaders.into_iter().next(),
+            tied: vec![],
+            outcome: PollOutcome::Passed,
+        }
+    } else {
+        PollResult {
+            winner: None,
+            tied: leaders,
+            outcome: PollOutcome::Rejected,
+        }
+    }
+}
+
+/// Cosmos gov rules: too much NoWithVeto vetoes the poll, Abstain can't win and a winning
+/// NoWithVeto rejects it
+fn gov_result(poll: &Poll, tallies: &[(String, Uint128)]) -> PollResult {
+    let total: u128 = tallies.iter().map(|(_, votes)| votes.u128()).sum();
+    let veto = tallies
+        .iter()
+        .find(|(option, _)| option == VETO_OPTION)
+        .map_or(Uint128::zero(), |(_, votes)| *votes);
+    let threshold = poll.veto_threshold.unwrap_or(DEFAULT_VETO_THRESHOLD);
+    if total > 0 && Decimal::from_ratio(veto, total) > threshold {
+        return PollResult {
+            winner: None,
+            tied: vec![],
+            outcome: PollOutcome::Vetoed,
+        };
+    }
+
+    let contenders: Vec<(String, Uint128)> = tallies
+        .iter()
+        .filter(|option| option.0 != ABSTAIN_OPTION)
+        .cloned()
+        .collect();
+    let mut result = tally_result(&contenders);
+    if result.winner.as_deref() == Some(VETO_OPTION) {
+        result.outcome = PollOutcome::Rejected;
+    }
+    result
+}
+
+/// Finds an option by its label
+fn option_position(poll: &Poll, option: &str) -> Result<usize, ContractError> {
+    poll.options
+        .iter()
+        .position(|existing| existing.label == option)
+        .ok_or_else(|| ContractError::OptionNotFound {
+            option: option.to_string(),
+        })
+}
+
+/// What a ballot adds to each option's tally.
+///
+/// A ranking only counts towards its first choice, the full ranking is used at finalization.
+fn ballot_votes(poll: &Poll, ballot: &Ballot) -> Vec<(String, Uint128)> {
+    match poll.tally_type {
+        TallyType::Plurality => ballot
+            .options
+            .iter()
+            .map(|option| (option.clone(), ballot.weight))
+            .collect(),
+        TallyType::RankedChoice => ballot
+            .options
+            .iter()
+            .take(1)
+            .map(|option| (option.clone(), ballot.weight))
+            .collect(),
+        TallyType::Quadratic => ballot
+            .options
+            .iter()
+            .zip(ballot.credits.iter())
+            .map(|(option, credits)| (option.clone(), isqrt(Uint128::from(*credits))))
+            .collect(),
+    }
+}
+
+/// Integer square root, rounded down, by Newton's method
+fn isqrt(n: Uint128) -> Uint128 {
+    if n < Uint128::new(2) {
+        return n;
+    }
+    let two = Uint128::new(2);
+    let mut x = n;
+    let mut y = (x + Uint128::one()) / two;
+    while y < x {
+        x = y;
+        y = (x + n / x) / two;
+    }
+    x
+}
+
+fn clear_tallies(
+    storage: &mut dyn Storage,
+    height: u64,
+    poll_id: &str,
+    poll: &Poll,
+) -> StdResult<()> {
+    for position in 0..poll.options.len() as u32 {
+        TALLIES.remove(storage, (poll_id, position), height)?;
+    }
+    Ok(())
+}
+
+/// Takes a ballot's votes back out of the poll tally without risking an underflow panic
+fn retract_vote(
+    storage: &mut dyn Storage,
+    height: u64,
+    poll_id: &str,
+    poll: &Poll,
+    ballot: &Ballot,
+) -> Result<(), ContractError> {
+    let mut counts = VOTE_COUNTS.may_load(storage, poll_id)?.unwrap_or_default();
+    counts.total_votes = counts.total_votes.saturating_sub(ballot.weight);
+    counts.unique_voters = counts.unique_voters.saturating_sub(1);
+    VOTE_COUNTS.save(storage, poll_id, &counts)?;
+    for (option, votes) in ballot_votes(poll, ballot) {
+        let position = option_position(poll, &option)? as u32;
+        let tally = TALLIES
+            .may_load(storage, (poll_id, position))?
+            .unwrap_or_default()
+            .checked_sub(votes)
+            .map_err(|_| ContractError::TallyUnderflow { option })?;
+        TALLIES.save(storage, (poll_id, position), &tally, height)?;
+    }
+    Ok(())
+}
+
+/// How much a vote from `voter` counts for in this poll
+fn voting_power(deps: Deps, poll: &Poll, voter: &Addr) -> Result<Uint128, ContractError> {
+    let power = live_power(deps, poll, voter)?;
+    let (Some(height), Some(source)) = (poll.snapshot_height, power_source(poll)) else {
+        return Ok(power);
+    };
+    // Power bought or borrowed after the poll started doesn't count, nor does power given away
+    let recorded = POWER_CHECKPOINTS
+        .may_load_at_height(deps.storage, (&source, voter), height)?
+        .unwrap_or_default();
+    let power = power.min(recorded);
+    if power.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+    Ok(power)
+}
+
+/// Identifies where a weighted poll reads voting power from, polls sharing a source share
+/// checkpoints
+fn power_source(poll: &Poll) -> Option<String> {
+    if let Some(denom) = &poll.weight_denom {
+        Some(format!("denom/{}", denom))
+    } else if let Some(token) = &poll.cw20_address {
+        Some(format!("cw20/{}", token))
+    } else if poll.staking_weighted {
+        Some("staking".to_string())
+    } else if poll.nft_weighted {
+        poll.nft_collection
+            .as_ref()
+            .map(|collection| format!("nft/{}", collection))
+    } else {
+        None
+    }
+}
+
+/// The voting power `voter` holds right now
+fn live_power(deps: Deps, poll: &Poll, voter: &Addr) -> Result<Uint128, ContractError> {
+    if let Some(collection) = &poll.nft_collection {
+        // Owning a single NFT is enough to pass the gate, only weighting needs the full count
+        let held = nft_count(deps, collection, voter, !poll.nft_weighted)?;
+        if held == 0 {
+            return Err(ContractError::NoVotingPower {});
+        }
+        if poll.nft_weighted {
+            return Ok(Uint128::from(held));
+        }
+    }
+
+    let balance = if let Some(denom) = &poll.weight_denom {
+        deps.querier.query_balance(voter, denom)?.amount
+    } else if let Some(token) = &poll.cw20_address {
+        let msg = Cw20QueryMsg::Balance {
+            address: voter.to_string(),
+        };
+        let res: Cw20BalanceResponse = deps.querier.query_wasm_smart(token, &msg)?;
+        res.balance
+    } else if let Some(group) = &poll.cw4_group {
+        let msg = Cw4QueryMsg::Member {
+            addr: voter.to_string(),
+            at_height: poll.cw4_snapshot_height,
+        };
+        let res: Cw4MemberResponse = deps.querier.query_wasm_smart(group, &msg)?;
+        Uint128::from(res.weight.unwrap_or(0))
+    } else if poll.staking_weighted {
+        // Every delegation is in the bonded denom, so the amounts can simply be added up
+        let delegations = deps.querier.query_all_delegations(voter)?;
+        delegations
+            .iter()
+            .try_fold(Uint128::zero(), |total, delegation| {
+                total.checked_add(delegation.amount.amount)
+            })
+            .map_err(StdError::from)?
+    } else {
+        return Ok(Uint128::one());
+    };
+
+    if balance.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+    Ok(balance)
+}
+
+/// Counts the NFTs `owner` holds in a cw721 collection, page by page, stopping at the first one
+/// if `any` is set
+fn nft_count(deps: Deps, collection: &Addr, owner: &Addr, any: bool) -> StdResult<u64> {
+    const PAGE: u32 = 30;
+    let mut count = 0;
+    let mut start_after = None;
+    loop {
+        let msg = Cw721QueryMsg::Tokens {
+            owner: owner.to_string(),
+            start_after,
+            limit: Some(if any { 1 } else { PAGE }),
+        };
+        let res: Cw721TokensResponse = deps.querier.query_wasm_smart(collection, &msg)?;
+        count += res.tokens.len() as u64;
+        if any || res.tokens.len() < PAGE as usize {
+            return Ok(count);
+        }
+        start_after = res.tokens.last().cloned();
+    }
+}
+
+/// The voter's own power plus that of everyone delegating to them, directly or down a chain, who
+/// hasn't voted in the poll themselves. Returns the total along with the delegators it covers.
+fn ballot_power(
+    deps: Deps,
+    poll: &Poll,
+    poll_id: &str,
+    voter: &Addr,
+) -> Result<(Uint128, Vec<Addr>), ContractError> {
+    let key = (poll_id.to_string(), voter.clone());
+    if let Some(representative) = REPRESENTATIVES.may_load(deps.storage, key)? {
+        return Err(ContractError::AlreadyRepresented {
+            representative: representative.into_string(),
+        });
+    }
+
+    let mut total = power_or_zero(deps, poll, voter)?;
+    let mut represented = vec![];
+    let mut pending = vec![voter.clone()];
+    while let Some(delegate) = pending.pop() {
+        let delegators = DELEGATORS
+            .prefix(delegate)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for delegator in delegators {
+            // Voting directly takes a delegator, and everyone behind them, out of the delegation
+            if load_ballot(deps.storage, poll, poll_id, &delegator)?.is_some() {
+                continue;
+            }
+            // Power that isn't allowed in the poll can't be delegated into it either, and a merkle
+            // proof only ever covers the voter sending it
+            if poll.merkle_root.is_some() || !is_eligible(deps.storage, poll, poll_id, &delegator) {
+                pending.push(delegator);
+                continue;
+            }
+            let key = (poll_id.to_string(), delegator.clone());
+            let representative = REPRESENTATIVES.may_load(deps.storage, key)?;
+            if representative.is_some_and(|representative| &representative != voter) {
+                continue;
+            }
+            total = total
+                .checked_add(power_or_zero(deps, poll, &delegator)?)
+                .map_err(|_| ContractError::VotingPowerTooLarge {})?;
+            represented.push(delegator.clone());
+            pending.push(delegator);
+        }
+    }
+
+    if total.is_zero() {
+        return Err(ContractError::NoVotingPower {});
+    }
+    Ok((total, represented))
+}
+
+fn is_eligible(storage: &dyn Storage, poll: &Poll, poll_id: &str, voter: &Addr) -> bool {
+    let reputable = poll.min_reputation.is_none_or(|min| {
+        let reputation = reputations()
+            .may_load(storage, voter.clone())
+            .ok()
+            .flatten();
+        reputation.unwrap_or_default().polls_voted >= min
+    });
+    reputable
+        && (!poll.allowlist || ALLOWED_VOTERS.has(storage, (poll_id.to_string(), voter.clone())))
+}
+
+/// Checks the voter is a leaf of the poll's merkle tree, if it has one
+fn verify_merkle_proof(poll: &Poll, voter: &Addr, proof: &[String]) -> Result<(), ContractError> {
+    let root = match &poll.merkle_root {
+        Some(root) => decode_hash(root)?,
+        None => return Ok(()),
+    };
+    let mut node: [u8; 32] = Sha256::digest(voter.as_bytes()).into();
+    for sibling in proof {
+        let sibling = decode_hash(sibling)?;
+        // Sorted pairs mean proofs don't have to say which side each sibling is on
+        let (left, right) = if node <= sibling {
+            (node, sibling)
+        } else {
+            (sibling, node)
+        };
+        node = Sha256::digest([left, right].concat()).into();
+    }
+    if node != root {
+        return Err(ContractError::NotEligible {});
+    }
+    Ok(())
+}
+
+fn decode_hash(hash: &str) -> Result<[u8; 32], ContractError> {
+    hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ContractError::InvalidMerkleHash {})
+}
+
+fn power_or_zero(deps: Deps, poll: &Poll, voter: &Addr) -> Result<Uint128, ContractError> {
+    match voting_power(deps, poll, voter) {
+        Err(ContractError::NoVotingPower {}) => Ok(Uint128::zero()),
+        power => power,
+    }
+}
+
+/// Hands the delegators of a withdrawn ballot their votes back
+fn release_represented(storage: &mut dyn Storage, poll_id: &str, ballot: &Ballot) {
+    for delegator in &ballot.represented {
+        REPRESENTATIVES.remove(storage, (poll_id.to_string(), delegator.clone()));
+    }
+}
+
+/// Errors unless the poll is currently accepting votes
+pub(crate) fn ensure_open(poll: &Poll, env: &Env) -> Result<(), ContractError> {
+    match poll.status {
+        PollStatus::Draft => Err(ContractError::PollNotActive {}),
+        PollStatus::Active if poll.start_time.is_some_and(|start| env.block.time < start) => {
+            Err(ContractError::PollNotStarted {})
+        }
+        PollStatus::Active if !poll.is_expired(&env.block) => Ok(()),
+        _ => Err(ContractError::PollClosed {}),
+    }
+}
+
+/// Checks a ballot already cast in an open poll may still be changed or revoked
+fn ensure_revotable(poll: &Poll, env: &Env) -> Result<(), ContractError> {
+    if poll.locked_ballots {
+        return Err(ContractError::AlreadyVoted {});
+    }
+    if poll
+        .revote_deadline
+        .is_some_and(|deadline| env.block.time >= deadline)
+    {
+        return Err(ContractError::RevoteClosed {});
+    }
+    Ok(())
+}
+
+/// Checks a poll can be finalized, it has been closed or its voting is over
+pub(crate) fn ensure_ended(poll: &Poll, env: &Env) -> Result<(), ContractError> {
+    match poll.status {
+        PollStatus::Closed => Ok(()),
+        PollStatus::Active if poll.voting_ended(&env.block) => Ok(()),
+        PollStatus::Active => Err(ContractError::PollNotExpired {}),
+        from => Err(ContractError::InvalidTransition {
+            from,
+            to: PollStatus::Finalized,
+        }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        HOOK_REPLY_ID => {
+            // Only failures reply, and a broken hook mustn't undo the poll change it was told about
+            let error = msg.result.into_result().err().unwrap_or_default();
+            Ok(Response::new()
+                .add_attribute("action", "hook_failed")
+                .add_attribute("error", error))
+        }
+        POLL_CONTRACT_REPLY_ID => reply_poll_contract(deps, env, msg),
+        RECEIPT_REPLY_ID => {
+            // Like hooks, a receipt that couldn't be minted mustn't undo the vote
+            let error = msg.result.into_result().err().unwrap_or_default();
+            Ok(Response::new()
+                .add_attribute("action", "receipt_failed")
+                .add_attribute("error", error))
+        }
+        id if id >= FIRST_PAYOUT_REPLY_ID => reply_payout(deps, env, id, msg.result),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+/// Records the contract instantiated for a factory mode poll and creates the poll in it
+fn reply_poll_contract(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let res = parse_reply_instantiate_data(msg)?;
+    let address = deps.api.addr_validate(&res.contract_address)?;
+    let new_poll = PENDING_POLL_CONTRACT.load(deps.storage)?;
+    PENDING_POLL_CONTRACT.remove(deps.storage);
+    POLL_CONTRACTS.save(deps.storage, new_poll.poll_id.clone(), &address)?;
+
+    let poll_id = new_poll.poll_id.clone();
+    let create = WasmMsg::Execute {
+        contract_addr: address.to_string(),
+        msg: to_binary(&ExecuteMsg::CreatePoll(new_poll))?,
+        funds: vec![],
+    };
+    Ok(Response::new()
+        .add_message(create)
+        .add_attribute("action", "poll_contract_instantiated")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("address", address))
+}
+
+fn reply_payout(
+    deps: DepsMut,
+    _env: Env,
+    id: u64,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    let payout = match PENDING_PAYOUTS.may_load(deps.storage, id)? {
+        Some(payout) => payout,
+        None => return Err(StdError::generic_err("no payout pending a reply").into()),
+    };
+    PENDING_PAYOUTS.remove(deps.storage, id);
+
+    let error = match result {
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+        SubMsgResult::Err(error) => error,
+    };
+    // The coins never left, they're owed to the recipient until they retry
+    if !payout.cw20 {
+        reserve(deps.storage, &payout.amount)?;
+    }
+    let mut failed = FAILED_PAYOUTS
+        .may_load(deps.storage, payout.recipient.clone())?
+        .unwrap_or_default();
+    failed.push(payout.clone());
+    FAILED_PAYOUTS.save(deps.storage, payout.recipient.clone(), &failed)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "payout_failed")
+        .add_attribute("recipient", payout.recipient)
+        .add_attribute("amount", payout.amount.to_string())
+        .add_attribute("error", error))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::FreezeContract {} => sudo_set_frozen(deps, env, true),
+        SudoMsg::UnfreezeContract {} => sudo_set_frozen(deps, env, false),
+        SudoMsg::RemovePoll { poll_id } => sudo_remove_poll(deps, env, poll_id),
+        SudoMsg::SetAdmin { admin } => sudo_set_admin(deps, env, admin),
+    }
+}
+
+fn sudo_set_frozen(deps: DepsMut, _env: Env, frozen: bool) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    config.frozen = frozen;
+    CONFIG.save(deps.storage, &config)?;
+
+    let action = if frozen {
+        "freeze_contract"
+    } else {
+        "unfreeze_contract"
+    };
+    Ok(Response::new().add_attribute("action", action))
+}
+
+fn sudo_remove_poll(deps: DepsMut, env: Env, poll_id: String) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    let (res, ballots_removed) = remove_poll(deps.storage, &env, &poll_id, &poll)?;
+
+    Ok(res
+        .add_attribute("action", "sudo_remove_poll")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("ballots_removed", ballots_removed.to_string()))
+}
+
+fn sudo_set_admin(deps: DepsMut, _env: Env, admin: String) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    config.admin = deps.api.addr_validate(&admin)?;
+    CONFIG.save(deps.storage, &config)?;
+    // A handover the old admin proposed shouldn't outlive governance replacing them
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "sudo_set_admin")
+        .add_attribute("admin", config.admin))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            contract: stored.contract,
+        });
+    }
+    let from = parse_version(&stored.version)?;
+    let to = parse_version(CONTRACT_VERSION)?;
+    if from > to {
+        return Err(ContractError::CannotMigrateVersion {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    // Every step the stored version hasn't been through yet, oldest first
+    if from < Version::new(0, 2, 0) {
+        migrations::v0_1_to_v0_2::migrate(deps.storage, &env.block)?;
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version).map_err(|err| ContractError::InvalidVersion(err.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::AllPolls {
+            start_after,
+            limit,
+            order,
+        } => query_all_polls(deps, env, start_after, limit, order),
+        QueryMsg::RecentPolls { limit } => query_recent_polls(deps, env, limit),
+        QueryMsg::Poll { poll_id } => query_poll(deps, env, poll_id),
+        QueryMsg::PollCount {} => query_poll_count(deps, env),
+        QueryMsg::SigNonce { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            let nonce = SIG_NONCES
+                .may_load(deps.storage, address)?
+                .unwrap_or_default();
+            to_binary(&SigNonceResponse { nonce })
+        }
+        QueryMsg::PollExists { poll_id } => query_poll_exists(deps, env, poll_id),
+        QueryMsg::Vote {
+            address,
+            poll_id,
+            viewing_key,
+        } => query_vote(deps, env, address, poll_id, viewing_key),
+        QueryMsg::Config {} => query_config(deps, env),
+        QueryMsg::ExportState {
+            address,
+            viewing_key,
+            start_after,
+            limit,
+        } => query_export_state(deps, env, address, viewing_key, start_after, limit),
+        QueryMsg::ExportBallots {
+            address,
+            viewing_key,
+            poll_id,
+            start_after,
+            limit,
+        } => query_export_ballots(deps, env, address, viewing_key, poll_id, start_after, limit),
+        QueryMsg::PendingAdmin {} => to_binary(&PENDING_ADMIN.may_load(deps.storage)?),
+        QueryMsg::Role { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            to_binary(&ROLES.may_load(deps.storage, address)?)
+        }
+        QueryMsg::Stats {} => query_stats(deps, env),
+        QueryMsg::Reputation { address } => query_reputation(deps, env, address),
+        QueryMsg::ReportedPolls { start_after, limit } => {
+            query_reported_polls(deps, env, start_after, limit)
+        }
+        QueryMsg::Namespace {
+            namespace,
+            start_after,
+            limit,
+        } => query_namespace(deps, env, namespace, start_after, limit),
+        QueryMsg::Leaderboard { kind, limit } => query_leaderboard(deps, env, kind, limit),
+        QueryMsg::Delegations { address } => query_delegations(deps, env, address),
+        QueryMsg::TallyRounds { poll_id } => query_tally_rounds(deps, env, poll_id),
+        QueryMsg::VoteLog {
+            poll_id,
+            start_after,
+            limit,
+        } => query_vote_log(deps, env, poll_id, start_after, limit),
+        QueryMsg::ResultAttestation { poll_id } => query_result_attestation(deps, env, poll_id),
+        QueryMsg::TallyAtHeight { poll_id, height } => {
+            query_tally_at_height(deps, env, poll_id, height)
+        }
+        QueryMsg::FailedPayouts { address } => query_failed_payouts(deps, env, address),
+        QueryMsg::Treasury {} => query_treasury(deps, env),
+        QueryMsg::CreatorFees { address } => query_creator_fees(deps, env, address),
+        QueryMsg::Hooks {} => query_hooks(deps, env),
+        QueryMsg::BannedAddresses { start_after, limit } => {
+            query_banned_addresses(deps, env, start_after, limit)
+        }
+        QueryMsg::BannedWords { start_after, limit } => {
+            query_banned_words(deps, env, start_after, limit)
+        }
+        QueryMsg::PollVoters {
+            poll_id,
+            start_after,
+            limit,
+        } => query_poll_voters(deps, env, poll_id, start_after, limit),
+        QueryMsg::VotesByVoter {
+            address,
+            viewing_key,
+            start_after,
+            limit,
+        } => query_votes_by_voter(deps, env, address, viewing_key, start_after, limit),
+        QueryMsg::PollsByCreator {
+            creator,
+            start_after,
+            limit,
+        } => query_polls_by_creator(deps, env, creator, start_after, limit),
+        QueryMsg::PollsByIdPrefix { prefix, limit } => {
+            query_polls_by_id_prefix(deps, env, prefix, limit)
+        }
+        QueryMsg::PollsByStatus {
+            status,
+            start_after,
+            limit,
+        } => query_polls_by_status(deps, env, status, start_after, limit),
+        QueryMsg::EndingPolls { start_after, limit } => {
+            query_ending_polls(deps, env, start_after, limit)
+        }
+        QueryMsg::PollsByTag {
+            tag,
+            start_after,
+            limit,
+        } => query_polls_by_tag(deps, env, tag, start_after, limit),
+        QueryMsg::PollResults { poll_id } => query_poll_results(deps, env, poll_id),
+        QueryMsg::ArchivedPoll { poll_id } => {
+            to_binary(&ARCHIVED_POLLS.may_load(deps.storage, poll_id)?)
+        }
+        QueryMsg::Raffle { poll_id } => to_binary(&RAFFLES.may_load(deps.storage, poll_id)?),
+        QueryMsg::Survey { survey_id } => survey::query_survey(deps, env, survey_id),
+        QueryMsg::SurveyAnswers { survey_id, address } => {
+            survey::query_survey_answers(deps, env, survey_id, address)
+        }
+        QueryMsg::SurveyRespondents {
+            survey_id,
+            start_after,
+            limit,
+        } => survey::query_survey_respondents(deps, env, survey_id, start_after, limit),
+        QueryMsg::RecurringPoll { template_id } => {
+            to_binary(&RECURRING_POLLS.may_load(deps.storage, template_id)?)
+        }
+        QueryMsg::RecurringInstances {
+            template_id,
+            start_after,
+            limit,
+        } => query_recurring_instances(deps, env, template_id, start_after, limit),
+        QueryMsg::PollContract { poll_id } => query_poll_contract(deps, env, poll_id),
+        QueryMsg::Proposal { proposal_id } => cw3::query_proposal(deps, env, proposal_id),
+        QueryMsg::ListProposals { start_after, limit } => {
+            cw3::query_list_proposals(deps, env, start_after, limit, Order::Ascending)
+        }
+        QueryMsg::ReverseProposals {
+            start_before,
+            limit,
+        } => cw3::query_list_proposals(deps, env, start_before, limit, Order::Descending),
+        QueryMsg::ListVotes {
+            proposal_id,
+            start_after,
+            limit,
+        } => cw3::query_list_votes(deps, env, proposal_id, start_after, limit),
+    }
+}
+
+fn query_all_polls(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+    order: Option<SortOrder>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let order = order.map_or(Order::Ascending, Order::from);
+    let start = start_after.map(Bound::exclusive);
+    let (min, max) = match order {
+        Order::Ascending => (start, None),
+        Order::Descending => (None, start),
+    };
+    let polls = polls()
+        .range(deps.storage, min, max, order)
+        .filter(|p| !matches!(p, Ok((_, poll)) if poll.hidden))
+        .take(limit)
+        .map(|p| {
+            let (poll_id, poll) = p?;
+            Ok(PollInfo { poll_id, poll })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&AllPollsResponse { polls })
+}
+
+fn query_recent_polls(deps: Deps, _env: Env, limit: Option<u32>) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let polls = CREATION_ORDER
+        .range(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            let (_, poll_id) = item?;
+            let poll = polls().load(deps.storage, poll_id.clone())?;
+            Ok(PollInfo { poll_id, poll })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&AllPollsResponse { polls })
+}
+
+fn query_poll_voters(
+    deps: Deps,
+    _env: Env,
+    poll_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let poll = polls().load(deps.storage, poll_id.clone())?;
+    if poll.private_ballots {
+        return Err(StdError::generic_err("Ballots of this poll are private"));
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // Voters relayed over IBC aren't local addresses, so the bound isn't validated
+    let start = start_after.map(|voter| Bound::exclusive(Addr::unchecked(voter)));
+    let voters = POLL_VOTERS
+        .prefix(poll_id.clone())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .filter(|voter| {
+            voter
+                .as_ref()
+                .map_or(true, |voter| !VIEWING_KEYS.has(deps.storage, voter.clone()))
+        })
+        .take(limit)
+        .map(|voter| {
+            let voter = voter?;
+            let ballot = BALLOTS.load(deps.storage, (voter.clone(), poll_id.clone()))?;
+            Ok(VoterInfo { voter, ballot })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&PollVotersResponse { voters })
+}
+
+fn query_votes_by_voter(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    viewing_key: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let voter = deps.api.addr_validate(&address)?;
+    check_viewing_key(deps.storage, &voter, viewing_key)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    // Private ballots are kept under hashed keys, so they never show up here
+    let votes = BALLOTS
+        .prefix(voter)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (poll_id, ballot) = item?;
+            Ok(VoteInfo { poll_id, ballot })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&VotesByVoterResponse { votes })
+}
+
+fn query_polls_by_creator(
+    deps: Deps,
+    _env: Env,
+    creator: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let polls = polls()
+        .idx
+        .creator
+        .prefix(creator)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(poll_id, poll)| PollInfo { poll_id, poll }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&AllPollsResponse { polls })
+}
+
+fn query_recurring_instances(
+    deps: Deps,
+    _env: Env,
+    template_id: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let instances = RECURRING_INSTANCES
+        .prefix(template_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&RecurringInstancesResponse { instances })
+}
+
+fn query_polls_by_id_prefix(
+    deps: Deps,
+    _env: Env,
+    prefix: String,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // A String primary key is stored as its raw UTF-8 bytes with nothing appended, and keys are
+    // ordered bytewise, so the ids starting with the prefix are exactly those from the prefix up
+    // to the first byte string after every extension of it. `prefix_range` doesn't help here, it
+    // only bounds the leading elements of composite keys and a poll id is a single element.
+    let min = Some(Bound::inclusive(prefix.clone()));
+    let max = prefix_end(prefix.as_bytes()).map(Bound::ExclusiveRaw);
+    let polls = polls()
+        .range(deps.storage, min, max, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(poll_id, poll)| PollInfo { poll_id, poll }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&AllPollsResponse { polls })
+}
+
+/// The smallest key sorting after everything starting with `prefix`, None if there's no such key
+fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+fn query_polls_by_status(
+    deps: Deps,
+    _env: Env,
+    status: PollStatus,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let polls = polls()
+        .idx
+        .status
+        .prefix(status as u8)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(poll_id, poll)| PollInfo { poll_id, poll }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&AllPollsResponse { polls })
+}
+
+fn query_ending_polls(
+    deps: Deps,
+    env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(poll_id) => {
+            let poll = polls().load(deps.storage, poll_id.clone())?;
+            Bound::exclusive((poll.end_key(), poll_id))
+        }
+        None => Bound::inclusive((env.block.time.nanos(), String::new())),
+    };
+    let polls = polls()
+        .idx
+        .end_time
+        .sub_prefix(PollStatus::Active as u8)
+        .range(deps.storage, Some(start), None, Order::Ascending)
+        .take_while(|item| !matches!(item, Ok((_, poll)) if poll.end_time.is_none()))
+        .take(limit)
+        .map(|item| item.map(|(poll_id, poll)| PollInfo { poll_id, poll }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&AllPollsResponse { polls })
+}
+
+fn query_polls_by_tag(
+    deps: Deps,
+    _env: Env,
+    tag: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let polls = TAG_POLLS
+        .prefix(tag)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|poll_id| {
+            let poll_id = poll_id?;
+            let poll = polls().load(deps.storage, poll_id.clone())?;
+            Ok(PollInfo { poll_id, poll })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&AllPollsResponse { polls })
+}
+
+fn query_poll(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
+    let poll = polls().may_load(deps.storage, poll_id.clone())?;
+    let tallies = match &poll {
+        Some(poll) => poll.tallies(deps.storage, &poll_id)?,
+        None => vec![],
+    };
+    let counts = VOTE_COUNTS
+        .may_load(deps.storage, &poll_id)?
+        .unwrap_or_default();
+    to_binary(&PollResponse {
+        poll,
+        tallies,
+        total_votes: counts.total_votes,
+        unique_voters: counts.unique_voters,
+    })
+}
+
+fn query_poll_count(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let count = POLL_COUNT.load(deps.storage)?;
+    to_binary(&PollCountResponse { count })
+}
+
+fn query_poll_exists(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
+    let exists = polls().has(deps.storage, poll_id);
+    to_binary(&PollExistsResponse { exists })
+}
+
+fn query_poll_results(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
+    let poll = polls().load(deps.storage, poll_id.clone())?;
+    let tallies = poll.tallies(deps.storage, &poll_id)?;
+    let total: u128 = tallies.iter().map(|(_, votes)| votes.u128()).sum();
+    let options = tallies
+        .iter()
+        .map(|(option, votes)| OptionResult {
+            option: option.clone(),
+            votes: *votes,
+            percentage: if total == 0 {
+                Decimal::zero()
+            } else {
+                Decimal::from_ratio(*votes, total)
+            },
+        })
+        .collect();
+    // Plain tallies, the rules that only apply once the poll is finalized are left out
+    let current = tally_result(&tallies);
+
+    to_binary(&PollResultsResponse {
+        total_votes: Uint128::from(total),
+        options,
+        leader: current.winner,
+        tied: current.tied,
+    })
+}
+
+fn query_vote(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    poll_id: String,
+    viewing_key: Option<String>,
+) -> StdResult<Binary> {
+    let validated_address = deps.api.addr_validate(&address)?;
+    let keyed = check_viewing_key(deps.storage, &validated_address, viewing_key)?;
+    if let Some(poll) = polls().may_load(deps.storage, poll_id.clone())? {
+        // Queries have no sender, so private ballots need the voter's viewing key
+        if poll.private_ballots {
+            if !keyed {
+                return Err(StdError::generic_err("Ballots of this poll are private"));
+            }
+            let vote = load_ballot(deps.storage, &poll, &poll_id, &validated_address)?;
+            return to_binary(&VoteResponse { vote });
+        }
+    }
+    let vote = BALLOTS.may_load(deps.storage, (validated_address, poll_id))?;
+
+    to_binary(&VoteResponse { vote })
+}
+
+fn viewing_key_hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Whether the voter's ballots are being read with their viewing key, failing when they've set
+/// one and it wasn't given
+fn check_viewing_key(
+    storage: &dyn Storage,
+    voter: &Addr,
+    viewing_key: Option<String>,
+) -> StdResult<bool> {
+    let hash = match VIEWING_KEYS.may_load(storage, voter.clone())? {
+        Some(hash) => hash,
+        None => return Ok(false),
+    };
+    if viewing_key.map(|key| viewing_key_hash(&key)) != Some(hash) {
+        return Err(StdError::generic_err("Wrong or missing viewing key"));
+    }
+    Ok(true)
+}
+
+fn query_export_state(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    viewing_key: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    ensure_exporter(deps, address, viewing_key)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let polls = polls()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(poll_id, poll)| PollInfo { poll_id, poll }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&ExportStateResponse { polls })
+}
+
+fn query_export_ballots(
+    deps: Deps,
+    _env: Env,
+    address: String,
+    viewing_key: String,
+    poll_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    ensure_exporter(deps, address, viewing_key)?;
+    let poll = polls().load(deps.storage, poll_id.clone())?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let ballots = if poll.private_ballots {
+        let start = start_after.map(Bound::exclusive);
+        PRIVATE_BALLOTS
+            .prefix(poll_id.clone())
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?
+    } else {
+        let start = start_after.map(|voter| Bound::exclusive(Addr::unchecked(voter)));
+        POLL_VOTERS
+            .prefix(poll_id.clone())
+            .keys(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|voter| {
+                let voter = voter?;
+                let ballot = BALLOTS.load(deps.storage, (voter.clone(), poll_id.clone()))?;
+                Ok((voter.into_string(), ballot))
+            })
+            .collect::<StdResult<Vec<_>>>()?
+    };
+    let ballots = ballots
+        .into_iter()
+        .map(|(voter, ballot)| ImportedBallot {
+            poll_id: poll_id.clone(),
+            voter,
+            ballot,
+        })
+        .collect();
+
+    to_binary(&ExportBallotsResponse { ballots })
+}
+
+/// Checks the address is an admin who gave their viewing key, exports include private ballots
+fn ensure_exporter(deps: Deps, address: String, viewing_key: String) -> StdResult<()> {
+    let address = deps.api.addr_validate(&address)?;
+    let config = CONFIG.load(deps.storage)?;
+    if !has_role(deps.storage, &config, &address, Role::Admin)?
+        || !check_viewing_key(deps.storage, &address, Some(viewing_key))?
+    {
+        return Err(StdError::generic_err("Only an admin can export the state"));
+    }
+    Ok(())
+}
+
+fn query_config(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    to_binary(&config)
+}
+
+fn query_stats(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let stats = STATS.load(deps.storage)?;
+    to_binary(&stats)
+}
+
+fn query_reputation(deps: Deps, env: Env, address: String) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    let mut reputation = reputations()
+        .may_load(deps.storage, address)?
+        .unwrap_or_default();
+    // A streak is over once a whole period went by without any activity
+    let period = env.block.time.seconds() / REPUTATION_PERIOD;
+    if period > reputation.last_active_period + 1 {
+        reputation.streak = 0;
+    }
+    to_binary(&reputation)
+}
+
+fn query_leaderboard(
+    deps: Deps,
+    _env: Env,
+    kind: LeaderboardKind,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let reputations = reputations();
+    let index = match kind {
+        LeaderboardKind::PollsCreated => &reputations.idx.polls_created,
+        LeaderboardKind::PollsVoted => &reputations.idx.polls_voted,
+    };
+    let count = |reputation: &Reputation| match kind {
+        LeaderboardKind::PollsCreated => reputation.polls_created,
+        LeaderboardKind::PollsVoted => reputation.polls_voted,
+    };
+    // Addresses that never did the thing counted sort last, so the listing stops at them
+    let entries = index
+        .range(deps.storage, None, None, Order::Descending)
+        .map(|item| item.map(|(address, reputation)| (address, count(&reputation))))
+        .take_while(|item| !matches!(item, Ok((_, 0))))
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&LeaderboardResponse { entries })
+}
+
+fn query_namespace(
+    deps: Deps,
+    _env: Env,
+    namespace: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let owner = NAMESPACES.may_load(deps.storage, namespace.clone())?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?
+        .map(Bound::exclusive);
+    let creators = NAMESPACE_CREATORS
+        .prefix(namespace)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&NamespaceResponse { owner, creators })
+}
+
+fn query_reported_polls(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let reported = REPORT_COUNTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (poll_id, reports) = item?;
+            let hidden = polls().load(deps.storage, poll_id.clone())?.hidden;
+            Ok(ReportedPoll {
+                poll_id,
+                reports,
+                hidden,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&ReportedPollsResponse { polls: reported })
+}
+
+fn query_delegations(deps: Deps, _env: Env, address: String) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    let delegate = DELEGATIONS.may_load(deps.storage, address.clone())?;
+    let delegators = DELEGATORS
+        .prefix(address)
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&DelegationsResponse {
+        delegate,
+        delegators,
+    })
+}
+
+fn query_tally_rounds(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
+    let rounds = TALLY_ROUNDS
+        .may_load(deps.storage, poll_id)?
+        .unwrap_or_default();
+    to_binary(&TallyRoundsResponse { rounds })
+}
+
+fn query_result_attestation(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
+    let attestation = RESULT_ATTESTATIONS.may_load(deps.storage, poll_id)?;
+    to_binary(&ResultAttestationResponse { attestation })
+}
+
+fn query_vote_log(
+    deps: Deps,
+    _env: Env,
+    poll_id: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let entries = match poll_id {
+        Some(poll_id) => {
+            vote_log()
+                .idx
+                .poll
+                .prefix(poll_id)
+                .range(deps.storage, start, None, Order::Ascending)
+        }
+        None => vote_log().range(deps.storage, start, None, Order::Ascending),
+    }
+    .take(limit)
+    .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&VoteLogResponse { entries })
+}
+
+fn query_tally_at_height(deps: Deps, _env: Env, poll_id: String, height: u64) -> StdResult<Binary> {
+    let poll = polls().load(deps.storage, poll_id.clone())?;
+    let tallies = (0u32..)
+        .zip(&poll.options)
+        .map(|(position, option)| {
+            let votes = TALLIES
+                .may_load_at_height(deps.storage, (&poll_id, position), height)?
+                .unwrap_or_default();
+            Ok((option.label.clone(), votes))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&TallyAtHeightResponse { height, tallies })
+}
+
+fn query_poll_contract(deps: Deps, _env: Env, poll_id: String) -> StdResult<Binary> {
+    let address = POLL_CONTRACTS.may_load(deps.storage, poll_id)?;
+    to_binary(&PollContractResponse { address })
+}
+
+fn query_hooks(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let hooks = HOOKS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&HooksResponse { hooks })
+}
+
+fn query_banned_addresses(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?
+        .map(Bound::exclusive);
+    let addresses = BANNED
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&BannedAddressesResponse { addresses })
+}
+
+fn query_banned_words(
+    deps: Deps,
+    _env: Env,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let words = BANNED_WORDS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&BannedWordsResponse { words })
+}
+
+fn query_failed_payouts(deps: Deps, _env: Env, address: String) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    let payouts = FAILED_PAYOUTS
+        .may_load(deps.storage, address)?
+        .unwrap_or_default();
+    to_binary(&FailedPayoutsResponse { payouts })
+}
+
+fn query_treasury(deps: Deps, _env: Env) -> StdResult<Binary> {
+    let balance = TREASURY
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| coin(amount.u128(), denom)))
+        .collect::<StdResult<Vec<_>>>()?;
+    let sources = TreasurySource::ALL
+        .into_iter()
+        .map(|source| {
+            let coins = TREASURY_SOURCES
+                .prefix(source.key())
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| item.map(|(denom, amount)| coin(amount.u128(), denom)))
+                .collect::<StdResult<Vec<_>>>()?;
+            Ok((source, coins))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let cw20_poll_fees = CW20_TREASURY
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&TreasuryResponse {
+        balance,
+        sources,
+        cw20_poll_fees,
+    })
+}
+
+fn query_creator_fees(deps: Deps, _env: Env, address: String) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    let fees = CREATOR_FEES
+        .prefix(address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| coin(amount.u128(), denom)))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_binary(&CreatorFeesResponse { fees })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{
+        execute, instantiate, migrate, query, reply, sudo, FIRST_PAYOUT_REPLY_ID, RECEIPT_REPLY_ID,
+    };
+    use crate::contract::{
+        pubkey_address, send_payout, ADMIN_CLAIM_PERIOD, CONTRACT_NAME, CONTRACT_VERSION,
+        MAX_DESCRIPTION_LENGTH, REPUTATION_PERIOD,
+    };
+    use crate::external::{Cw20ExecuteMsg, Cw20ReceiveMsg, Cw721ExecuteMsg};
+    use crate::helpers::{vote_commitment, voter_hash};
+    use crate::msg::{
+        AcceptedToken, AllPollsResponse, AttestedResult, BannedAddressesResponse,
+        BannedWordsResponse, CreatePollResponse, CreatorFeesResponse, DelegationsResponse,
+        ExecuteMsg, ExportBallotsResponse, ExportStateResponse, FailedPayoutsResponse,
+        InstantiateMsg, LeaderboardKind, LeaderboardResponse, MigrateMsg, NamespaceResponse,
+        NewPoll, OptionDetails, PollCountResponse, PollExistsResponse, PollResponse,
+        PollResultsResponse, PollVotersResponse, QueryMsg, ReceiptExtension, ReceiveMsg,
+        RecurringInstancesResponse, ReportedPoll, ReportedPollsResponse, ResultAttestationResponse,
+        Settings, SigNonceResponse, SignedVote, SortOrder, SudoMsg, TallyAtHeightResponse,
+        TallyRoundsResponse, TreasuryResponse, VoteLogResponse, VoteResponse, VotesByVoterResponse,
+    };
+    use crate::state::{
+        polls, Config, Payout, PendingAdmin, Poll, PollLimits, PollOutcome, PollResult, PollStatus,
+        PollSummary, RateLimit, Reputation, RewardPool, Role, Stats, TallyType, TreasurySource,
+        VoteAction, BALLOTS, HELD, KEEPER_BOUNTIES, POLL_COUNT, POLL_VOTERS, PRIVATE_BALLOTS,
+        TALLIES, TREASURY, TREASURY_SOURCES,
+    };
+    use crate::ContractError;
+    use cosmwasm_std::attr; // helper to construct an attribute e.g. ("action", "instantiate")
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info}; // mock functions to mock an environment, message info, dependencies
+    use cosmwasm_std::testing::{mock_dependencies_with_balances, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        coin, from_binary, to_binary, to_vec, Addr, Api, BankMsg, Binary, CanonicalAddr, CosmosMsg,
+        Decimal, Deps, DepsMut, Empty, Env, FullDelegation, OwnedDeps, RecoverPubkeyError, Reply,
+        StdResult, SubMsg, SubMsgResponse, SubMsgResult, Uint128, Validator, VerificationError,
+        WasmMsg,
+    };
+    use cw_utils::{Expiration, PaymentError};
+    use k256::ecdsa::signature::hazmat::PrehashSigner;
+    use k256::ecdsa::{Signature, SigningKey};
+    use sha2::{Digest, Sha256};
+    use std::marker::PhantomData;
+
+    // Two fake addresses we will use to mock_info
+    pub const ADDR1: &str = "addr1";
+    pub const ADDR2: &str = "addr2";
+
+    // A single choice vote
+    fn vote_msg(poll_id: &str, vote: &str) -> ExecuteMsg {
+        ExecuteMsg::Vote {
+            poll_id: poll_id.to_string(),
+            proposal_id: None,
+            vote: Some(vote.to_string()),
+            option_index: None,
+            votes: vec![],
+            credits: vec![],
+            proof: vec![],
+        }
+    }
+
+    // The poll most tests work with, override fields with `..new_poll(id)`
+    fn new_poll(poll_id: &str) -> NewPoll {
+        NewPoll {
+            poll_id: poll_id.to_string(),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_instantiate() {
+        // Mock the dependencies, must be mutable so we can pass it as a mutable, empty vector means our contract has no balance
+        let mut deps = mock_dependencies();
+        // Mock the contract environment, contains the block info, contract address, etc.
+        let env = mock_env();
+        // Mock the message info, ADDR1 will be the sender, the empty vec means we sent no funds.
+        let info = mock_info(ADDR1, &[]);
+
+        // Create a message where we (the sender) will be an admin
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        // Call instantiate, unwrap to assert success
+        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "instantiate"), attr("admin", ADDR1)]
+        )
+    }
+
+    #[test]
+    fn test_update_config() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract, ADDR1 is the admin
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // ADDR2 is not the admin so can't hand itself the role
+        let msg = ExecuteMsg::ProposeNewAdmin {
+            address: ADDR2.to_string(),
+            deadline: None,
+        };
+        let other = mock_info(ADDR2, &[]);
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            other.clone(),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::NoPendingAdmin {}));
+
+        // The admin offers the role, nothing changes until it is accepted
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let bin = query(deps.as_ref(), env.clone(), QueryMsg::PendingAdmin {}).unwrap();
+        let pending: Option<PendingAdmin> = from_binary(&bin).unwrap();
+        assert_eq!(
+            pending,
+            Some(PendingAdmin {
+                address: Addr::unchecked(ADDR2),
+                deadline: Expiration::AtTime(env.block.time.plus_seconds(ADMIN_CLAIM_PERIOD)),
+            })
+        );
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // A proposal left past its deadline can't be accepted
+        let mut late = env.clone();
+        late.block.time = late.block.time.plus_seconds(ADMIN_CLAIM_PERIOD);
+        let err = execute(
+            deps.as_mut(),
+            late,
+            other.clone(),
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::AdminClaimExpired {}));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            other,
+            ExecuteMsg::AcceptAdmin {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "accept_admin"), attr("admin", ADDR2)]
+        );
+        let bin = query(deps.as_ref(), env.clone(), QueryMsg::PendingAdmin {}).unwrap();
+        let pending: Option<PendingAdmin> = from_binary(&bin).unwrap();
+        assert_eq!(pending, None);
+
+        let bin = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
+        let config: Config = from_binary(&bin).unwrap();
+        assert_eq!(config.admin, Addr::unchecked(ADDR2));
+    }
+
+    #[test]
+    fn test_roles() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let creator = mock_info(ADDR2, &[]);
+        let moderator = mock_info("moderator", &[]);
+        let grant = |address: &str, role| ExecuteMsg::GrantRole {
+            address: address.to_string(),
+            role,
+        };
+
+        // Only admins hand out roles
+        let msg = grant(ADDR2, Role::Admin);
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let msg = grant("moderator", Role::Moderator);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "grant_role"),
+                attr("role", "moderator"),
+                attr("address", "moderator"),
+            ]
+        );
+        let query_msg = QueryMsg::Role {
+            address: "moderator".to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let role: Option<Role> = from_binary(&bin).unwrap();
+        assert_eq!(role, Some(Role::Moderator));
+
+        // Moderators delete anyone's polls but can't configure the contract
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::Delete {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), moderator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: Some(true),
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), moderator.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // With creation restricted, only poll creators and those above them create polls
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("other_id"));
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), moderator, msg.clone()).unwrap();
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            grant(ADDR2, Role::PollCreator),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("third_id"));
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg.clone()).unwrap();
+
+        let msg = ExecuteMsg::RevokeRole {
+            address: ADDR2.to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("fourth_id"));
+        let err = execute(deps.as_mut(), env, creator, msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_ban_address() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let other = mock_info(ADDR2, &[]);
+        let ban = ExecuteMsg::BanAddress {
+            address: ADDR2.to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), ban.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), ban).unwrap();
+
+        // Banned addresses can neither vote nor create polls
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Banned {}));
+        let msg = ExecuteMsg::CreatePoll(new_poll("other_id"));
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Banned {}));
+
+        let query_msg = QueryMsg::BannedAddresses {
+            start_after: None,
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), query_msg.clone()).unwrap();
+        let res: BannedAddressesResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.addresses, vec![Addr::unchecked(ADDR2)]);
+
+        let unban = ExecuteMsg::UnbanAddress {
+            address: ADDR2.to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, unban).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), other, msg).unwrap();
+        let bin = query(deps.as_ref(), env, query_msg).unwrap();
+        let res: BannedAddressesResponse = from_binary(&bin).unwrap();
+        assert!(res.addresses.is_empty());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: Some(RateLimit {
+                max_polls: 2,
+                window: 86_400,
+            }),
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Two polls a day per address
+        let other = mock_info(ADDR2, &[]);
+        for poll_id in ["first", "second"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap();
+        }
+        let mut later = env.clone();
+        later.block.time = later.block.time.plus_seconds(3_600);
+        let msg = ExecuteMsg::CreatePoll(new_poll("third"));
+        let err = execute(deps.as_mut(), later, other.clone(), msg.clone()).unwrap_err();
+        let next_window = env.block.time.plus_seconds(86_400);
+        assert!(matches!(
+            err,
+            ContractError::RateLimited { retry_after } if retry_after == next_window
+        ));
+        // Others have their own allowance
+        let admin_msg = ExecuteMsg::CreatePoll(new_poll("admin_poll"));
+        let _res = execute(deps.as_mut(), env.clone(), info, admin_msg).unwrap();
+
+        // A new window opens once the day is over
+        let mut next_day = env.clone();
+        next_day.block.time = next_day.block.time.plus_seconds(86_400);
+        let _res = execute(deps.as_mut(), next_day, other, msg).unwrap();
+    }
+
+    #[test]
+    fn test_max_active_polls() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: Some(1),
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(new_poll("first"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("second"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyActivePolls { max: 1 }));
+
+        // Closing a poll frees its slot, and so does deleting one
+        let close = ExecuteMsg::ClosePoll {
+            poll_id: "first".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), close).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let delete = ExecuteMsg::Delete {
+            poll_id: "second".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), delete).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("third"));
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_archive_and_prune() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for poll_id in ["first", "second", "third"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            reward: Some(coin(100, "uosmo")),
+            ..new_poll("rewarded")
+        });
+        let funded = mock_info(ADDR1, &[coin(100, "uosmo")]);
+        let _res = execute(deps.as_mut(), env.clone(), funded, msg).unwrap();
+        for voter in [ADDR1, ADDR2] {
+            let msg = vote_msg("rewarded", "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        for poll_id in ["first", "second", "rewarded"] {
+            for msg in [
+                ExecuteMsg::ClosePoll {
+                    poll_id: poll_id.to_string(),
+                },
+                ExecuteMsg::FinalizePoll {
+                    poll_id: poll_id.to_string(),
+                },
+            ] {
+                let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            }
+        }
+
+        // Only finalized polls are archived, by their creator or an admin
+        let archive = |poll_id: &str| ExecuteMsg::ArchivePoll {
+            poll_id: poll_id.to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            archive("first"),
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), archive("third"));
+        assert!(matches!(
+            err.unwrap_err(),
+            ContractError::PollNotFinalized {}
+        ));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), archive("first")).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "archive_poll"),
+                attr("poll_id", "first"),
+                attr("ballots_removed", "1"),
+            ]
+        );
+        assert!(!polls().has(&deps.storage, "first".to_string()));
+        assert!(!BALLOTS.has(&deps.storage, (Addr::unchecked(ADDR1), "first".to_string())));
+        let query_msg = QueryMsg::ArchivedPoll {
+            poll_id: "first".to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let summary: Option<PollSummary> = from_binary(&bin).unwrap();
+        let summary = summary.unwrap();
+        assert!(summary
+            .tallies
+            .contains(&("Juno".to_string(), Uint128::new(1))));
+        assert_eq!(summary.result.unwrap().winner, Some("Juno".to_string()));
+        // The id of an archived poll stays taken
+        let msg = ExecuteMsg::CreatePoll(new_poll("first"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollAlreadyExists { .. }));
+
+        // Pruning skips polls still running
+        let msg = ExecuteMsg::PruneExpired {
+            limit: None,
+            keep_summaries: false,
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            msg.clone(),
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "prune_expired"),
+                attr("pruned", "1"),
+                attr("ballots_removed", "1"),
+            ]
+        );
+        assert!(!polls().has(&deps.storage, "second".to_string()));
+        assert!(polls().has(&deps.storage, "third".to_string()));
+        let query_msg = QueryMsg::ArchivedPoll {
+            poll_id: "second".to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let summary: Option<PollSummary> = from_binary(&bin).unwrap();
+        assert_eq!(summary, None);
+
+        // Nor are polls whose voters haven't all claimed their share of the reward
+        assert!(polls().has(&deps.storage, "rewarded".to_string()));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            archive("rewarded"),
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::PollUnsettled {}));
+        let claim = ExecuteMsg::ClaimReward {
+            poll_id: "rewarded".to_string(),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            claim.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR2.to_string(),
+                amount: vec![coin(50, "uosmo")],
+            })
+        );
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), claim).unwrap();
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.attributes[1], attr("pruned", "1"));
+        assert!(!polls().has(&deps.storage, "rewarded".to_string()));
+    }
+
+    #[test]
+    fn test_poll_limits() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: Some(PollLimits {
+                max_question_len: 40,
+                max_option_len: 8,
+                max_options: 3,
+            }),
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            question: "What's your favourite Cosmos coin, and why is it Juno?".to_string(),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::QuestionTooLong { max: 40 }));
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionTooLong { max: 8 }));
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            options: vec!["Juno", "Osmosis", "Stars", "Atom"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyOptions { max: 3 }));
+
+        // Limits have to leave room for a poll
+        let update = |limits| ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: Some(limits),
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let msg = update(PollLimits {
+            max_options: 1,
+            ..PollLimits::default()
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidLimits {}));
+        let msg = update(PollLimits::default());
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_instantiate_with_admin() {
+        /*
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR2, &[]);
+
+        let msg = InstantiateMsg {
+            admin: Some("Boss".to_string()),
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        }; // FIXME rust is new to me
+        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "instantiate_width_admin"), attr("admin", ADDR2)]
+        )
+        */
+    }
+
+    #[test]
+    fn test_execute_create_poll_valid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // New execute msg
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            poll_id: "some_id".to_string(),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            description: String::new(),
+            metadata_uri: None,
+            tags: vec![],
+            options: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            option_details: vec![(
+                "Juno".to_string(),
+                OptionDetails {
+                    description: "The smart contract hub".to_string(),
+                    image_uri: Some("ipfs://juno".to_string()),
+                },
+            )],
+            start_time: None,
+            end_height: None,
+            end_time: None,
+            revote_deadline: None,
+            weight_denom: None,
+            cw20_address: None,
+            staking_weighted: false,
+            nft_collection: None,
+            nft_weighted: false,
+            cw4_group: None,
+            cw4_snapshot: false,
+            snapshot: false,
+            ibc_voting: false,
+            msgs: vec![],
+            executor: None,
+            payload_per_option: vec![],
+            max_selections: None,
+            tally_type: TallyType::Plurality,
+            credit_budget: None,
+            gov_style: false,
+            veto_threshold: None,
+            quorum: None,
+            threshold: None,
+            eligible_power: None,
+            commit_reveal: false,
+            reveal_end_height: None,
+            reveal_end_time: None,
+            private_ballots: false,
+            allow_write_ins: false,
+            locked_ballots: false,
+            allowed_voters: None,
+            merkle_root: None,
+            min_reputation: None,
+            vote_fee: None,
+            reward: None,
+            draft: false,
+            factory: false,
+        });
+
+        // Unwrap to assert success
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "create_poll"),
+                attr("poll_id", "some_id"),
+                attr("creator", ADDR1),
+            ]
+        );
+        assert_eq!(res.events[0].ty, "poll_created");
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(poll.creator, ADDR1);
+        assert_eq!(poll.created_at_height, env.block.height);
+        assert_eq!(poll.created_at_time, env.block.time);
+        assert_eq!(poll.options[1].description, "The smart contract hub");
+        assert_eq!(poll.options[1].image_uri.as_deref(), Some("ipfs://juno"));
+        assert_eq!(poll.options[2].description, "");
+    }
+
+    #[test]
+    fn test_execute_create_poll_duplicate() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create the poll and vote on it
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Creating it again, even from another address, must not reset the tallies
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            question: "Something else entirely?".to_string(),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollAlreadyExists { .. }));
+
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(poll.creator, Addr::unchecked(ADDR1));
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(1))
+        );
+    }
+
+    #[test]
+    fn test_execute_create_poll_invalid_options() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let create = |deps: DepsMut, options: &[&str]| {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                options: options.iter().map(|option| option.to_string()).collect(),
+                ..new_poll("some_id")
+            });
+            execute(deps, mock_env(), mock_info(ADDR1, &[]), msg).unwrap_err()
+        };
+        let err = create(deps.as_mut(), &["Juno"]);
+        assert!(matches!(err, ContractError::TooFewOptions {}));
+        let err = create(deps.as_mut(), &["Juno", " "]);
+        assert!(matches!(err, ContractError::EmptyOption {}));
+        let err = create(deps.as_mut(), &["Juno", "Osmosis", "juno "]);
+        assert!(matches!(err, ContractError::DuplicateOption { option } if option == "juno "));
+        let err = create(
+            deps.as_mut(),
+            &["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"],
+        );
+        assert!(matches!(err, ContractError::TooManyOptions { max: 10 }));
+
+        // Updates are held to the same rules
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdatePoll {
+            poll_id: "some_id".to_string(),
+            question: None,
+            options: Some(vec!["Juno".to_string(), "JUNO".to_string()]),
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateOption { .. }));
+    }
+
+    #[test]
+    fn test_execute_vote_valid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // First vote
+        let msg = vote_msg("some_id", "Juno");
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "vote"),
+                attr("poll_id", "some_id"),
+                attr("voter", ADDR1),
+                attr("option", "Juno"),
+                attr("tally", "1"),
+            ]
+        );
+
+        // Changing the vote reports the option it moved away from
+        let msg = vote_msg("some_id", "Osmosis");
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.events[0].ty, "vote_cast");
+        assert_eq!(
+            res.events[0].attributes.last(),
+            Some(&attr("previous_option", "Juno"))
+        );
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(0))
+        );
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[2],
+            ("Osmosis".to_string(), Uint128::new(1))
+        );
+    }
+
+    #[test]
+    fn test_locked_ballots() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            locked_ballots: true,
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // The ballot can neither be changed nor taken back
+        let msg = vote_msg("some_id", "Osmosis");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyVoted {}));
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Juno".to_string(),
+        };
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyVoted {}));
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(1))
+        );
+    }
+
+    #[test]
+    fn test_revote_deadline() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let deadline = env.block.time.plus_seconds(3600);
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            end_time: Some(deadline),
+            revote_deadline: Some(deadline),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRevoteDeadline {}));
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            end_time: Some(deadline.plus_seconds(3600)),
+            revote_deadline: Some(deadline),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Osmosis");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let ballot = BALLOTS
+            .load(
+                &deps.storage,
+                (Addr::unchecked(ADDR1), "some_id".to_string()),
+            )
+            .unwrap();
+        assert_eq!(ballot.cast_at_time, Some(env.block.time));
+        let msg = QueryMsg::Vote {
+            address: ADDR1.to_string(),
+            poll_id: "some_id".to_string(),
+            viewing_key: None,
+        };
+        let res: VoteResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        let vote = res.vote.unwrap();
+        assert_eq!(vote.cast_at_height, Some(env.block.height));
+        assert_eq!(vote.cast_at_time, Some(env.block.time));
+        // A malformed address is an error, not a panic
+        let msg = QueryMsg::Vote {
+            address: String::new(),
+            poll_id: "some_id".to_string(),
+            viewing_key: None,
+        };
+        query(deps.as_ref(), env.clone(), msg).unwrap_err();
+
+        // Past the deadline new voters are still counted, but ballots stay as they are
+        env.block.time = deadline;
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::RevoteClosed {}));
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Osmosis".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::RevoteClosed {}));
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), msg).unwrap();
+    }
+
+    /// MockApi can't humanize 20 byte canonical addresses, signers show up as hex instead
+    #[derive(Clone, Copy, Default)]
+    struct SignerApi(MockApi);
+
+    impl Api for SignerApi {
+        fn addr_validate(&self, human: &str) -> StdResult<Addr> {
+            self.0.addr_validate(human)
+        }
+
+        fn addr_canonicalize(&self, human: &str) -> StdResult<CanonicalAddr> {
+            self.0.addr_canonicalize(human)
+        }
+
+        fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+            if canonical.len() == 20 {
+                return Ok(Addr::unchecked(format!(
+                    "signer{}",
+                    hex::encode(canonical.as_slice())
+                )));
+            }
+            self.0.addr_humanize(canonical)
+        }
+
+        fn secp256k1_verify(
+            &self,
+            message_hash: &[u8],
+            signature: &[u8],
+            public_key: &[u8],
+        ) -> Result<bool, VerificationError> {
+            self.0.secp256k1_verify(message_hash, signature, public_key)
+        }
+
+        fn secp256k1_recover_pubkey(
+            &self,
+            message_hash: &[u8],
+            signature: &[u8],
+            recovery_param: u8,
+        ) -> Result<Vec<u8>, RecoverPubkeyError> {
+            self.0
+                .secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+        }
+
+        fn ed25519_verify(
+            &self,
+            message: &[u8],
+            signature: &[u8],
+            public_key: &[u8],
+        ) -> Result<bool, VerificationError> {
+            self.0.ed25519_verify(message, signature, public_key)
+        }
+
+        fn ed25519_batch_verify(
+            &self,
+            messages: &[&[u8]],
+            signatures: &[&[u8]],
+            public_keys: &[&[u8]],
+        ) -> Result<bool, VerificationError> {
+            self.0
+                .ed25519_batch_verify(messages, signatures, public_keys)
+        }
+
+        fn debug(&self, message: &str) {
+            self.0.debug(message)
+        }
+    }
+
+    // A VoteBySig for `vote`, signed by `key` and valid for an hour
+    fn signed_vote_msg(
+        key: &SigningKey,
+        env: &Env,
+        poll_id: &str,
+        vote: &str,
+        nonce: u64,
+    ) -> ExecuteMsg {
+        let expires = env.block.time.plus_seconds(3600);
+        let payload = SignedVote {
+            chain_id: env.block.chain_id.clone(),
+            contract: env.contract.address.to_string(),
+            poll_id: poll_id.to_string(),
+            vote: vote.to_string(),
+            nonce,
+            expires,
+        };
+        let hash = Sha256::digest(to_vec(&payload).unwrap());
+        let signature: Signature = key.sign_prehash(&hash).unwrap();
+        ExecuteMsg::VoteBySig {
+            poll_id: poll_id.to_string(),
+            vote: vote.to_string(),
+            voter_pubkey: Binary::from(key.verifying_key().to_bytes().as_slice()),
+            signature: Binary::from(signature.as_ref()),
+            nonce,
+            expires,
+        }
+    }
+
+    #[test]
+    fn test_vote_by_sig() {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: SignerApi::default(),
+            querier: MockQuerier::default(),
+            custom_query_type: PhantomData::<Empty>,
+        };
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // The relayer pays for the vote, the signer casts it
+        let signing_key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let pubkey = signing_key.verifying_key().to_bytes();
+        let signer = pubkey_address(&deps.api, &pubkey).unwrap();
+        let relayer = mock_info("relayer", &[]);
+        let msg = signed_vote_msg(&signing_key, &env, "some_id", "Juno", 0);
+        let res = execute(deps.as_mut(), env.clone(), relayer.clone(), msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "vote_by_sig"));
+        let ballot = BALLOTS
+            .load(&deps.storage, (signer, "some_id".to_string()))
+            .unwrap();
+        assert_eq!(ballot.options, vec!["Juno".to_string()]);
+        let key = (Addr::unchecked("relayer"), "some_id".to_string());
+        assert!(!BALLOTS.has(&deps.storage, key));
+
+        // The signature only covers the vote it was made for
+        let msg = match signed_vote_msg(&signing_key, &env, "some_id", "Juno", 1) {
+            ExecuteMsg::VoteBySig {
+                poll_id,
+                voter_pubkey,
+                signature,
+                nonce,
+                expires,
+                ..
+            } => ExecuteMsg::VoteBySig {
+                poll_id,
+                vote: "Osmosis".to_string(),
+                voter_pubkey,
+                signature,
+                nonce,
+                expires,
+            },
+            _ => unreachable!(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), relayer.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+
+        // Signatures are bound to the chain they were made for
+        let mut other_chain = env.clone();
+        other_chain.block.chain_id = "other-chain".to_string();
+        let msg = signed_vote_msg(&signing_key, &other_chain, "some_id", "Juno", 1);
+        let err = execute(deps.as_mut(), env, relayer, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+    }
+
+    #[test]
+    fn test_sig_nonces() {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: SignerApi::default(),
+            querier: MockQuerier::default(),
+            custom_query_type: PhantomData::<Empty>,
+        };
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let signing_key = SigningKey::from_bytes(&[7; 32]).unwrap();
+        let pubkey = signing_key.verifying_key().to_bytes();
+        let signer = pubkey_address(&deps.api, &pubkey).unwrap();
+        let relayer = mock_info("relayer", &[]);
+
+        // Nonces have to be used in order, starting from 0
+        let msg = signed_vote_msg(&signing_key, &env, "some_id", "Juno", 1);
+        let err = execute(deps.as_mut(), env.clone(), relayer.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidNonce { expected: 0 }));
+        let first = signed_vote_msg(&signing_key, &env, "some_id", "Juno", 0);
+        let _res = execute(deps.as_mut(), env.clone(), relayer.clone(), first.clone()).unwrap();
+        let msg = QueryMsg::SigNonce {
+            address: signer.to_string(),
+        };
+        let res: SigNonceResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.nonce, 1);
+
+        // A vote that was already submitted can't be replayed
+        let err = execute(deps.as_mut(), env.clone(), relayer.clone(), first).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidNonce { expected: 1 }));
+
+        // Nor can one be submitted after it expired
+        let msg = signed_vote_msg(&signing_key, &env, "some_id", "Osmosis", 1);
+        env.block.time = env.block.time.plus_seconds(3600);
+        let err = execute(deps.as_mut(), env, relayer, msg).unwrap_err();
+        assert!(matches!(err, ContractError::SignatureExpired {}));
+        let ballot = BALLOTS
+            .load(&deps.storage, (signer, "some_id".to_string()))
+            .unwrap();
+        assert_eq!(ballot.options, vec!["Juno".to_string()]);
+    }
+
+    #[test]
+    fn test_write_ins() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("closed"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            allow_write_ins: true,
+            ..new_poll("open")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = vote_msg("closed", "Stargaze");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound { .. }));
+
+        // Write-ins follow the same rules as the options a poll is created with
+        let msg = vote_msg("open", "cosmos hub");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateOption { .. }));
+        let msg = vote_msg("open", &"a".repeat(200));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionTooLong { .. }));
+
+        let msg = vote_msg("open", "Stargaze");
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = vote_msg("open", "Stargaze");
+        let _res = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), msg).unwrap();
+        let poll = polls().load(&deps.storage, "open".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "open").unwrap()[3],
+            ("Stargaze".to_string(), Uint128::new(2))
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_by_index() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let vote = |index| ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            proposal_id: None,
+            vote: None,
+            option_index: Some(index),
+            votes: vec![],
+            credits: vec![],
+            proof: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), vote(3)).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::OptionIndexOutOfRange { index: 3 }
+        ));
+        let res = execute(deps.as_mut(), env, info, vote(1)).unwrap();
+        assert_eq!(res.attributes[3], attr("option", "Juno"));
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(1))
+        );
+    }
+
+    #[test]
+    fn test_scheduled_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let start_time = env.block.time.plus_seconds(3600);
+
+        // The voting window can't be empty
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            start_time: Some(start_time),
+            end_time: Some(start_time),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidStartTime {}));
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            start_time: Some(start_time),
+            end_time: Some(start_time.plus_seconds(3600)),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotStarted {}));
+
+        env.block.time = start_time;
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_vote_counts() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+
+        let counts = |deps: Deps| {
+            let msg = QueryMsg::Poll {
+                poll_id: "some_id".to_string(),
+            };
+            let res: PollResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            (res.total_votes.u128(), res.unique_voters)
+        };
+        assert_eq!(counts(deps.as_ref()), (0, 0));
+        for (voter, vote) in [(ADDR1, "Juno"), (ADDR2, "Juno"), (ADDR1, "Osmosis")] {
+            let msg = vote_msg("some_id", vote);
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        // Changing a vote doesn't count the voter twice
+        assert_eq!(counts(deps.as_ref()), (2, 2));
+
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Juno".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), msg).unwrap();
+        assert_eq!(counts(deps.as_ref()), (1, 1));
+    }
+
+    #[test]
+    fn test_execute_vote_invalid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create the vote, some_id poll is not created yet.
+        let msg = vote_msg("some_id", "Juno");
+        // Unwrap to assert error
+        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+
+        // Create the poll
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Vote on a now existing poll but the option "DVPN" does not exist
+        let msg = vote_msg("some_id", "DVPN");
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound { .. }));
+    }
+
+    #[test]
+    fn test_execute_delete_valid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract, ADDR1 is the admin
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // ADDR2 creates the poll and votes on it
+        let info = mock_info(ADDR2, &[]);
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // The creator deletes the poll
+        let msg = ExecuteMsg::Delete {
+            poll_id: "some_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "delete_poll"),
+                attr("poll_id", "some_id"),
+                attr("sender", ADDR2),
+                attr("ballots_removed", "1"),
+            ]
+        );
+
+        // Both the poll and its ballots are gone
+        let key = (Addr::unchecked(ADDR2), "some_id".to_string());
+        assert!(!polls().has(&deps.storage, "some_id".to_string()));
+        assert!(!BALLOTS.has(&deps.storage, key));
+        let key = ("some_id".to_string(), Addr::unchecked(ADDR2));
+        assert!(!POLL_VOTERS.has(&deps.storage, key));
+    }
+
+    #[test]
+    fn test_execute_delete_invalid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract, ADDR1 is the admin
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Deleting a poll that doesn't exist errors
+        let msg = ExecuteMsg::Delete {
+            poll_id: "some_id".to_string(),
+        };
+        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+
+        // The admin creates a poll
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // ADDR2 is neither the creator nor the admin so it can't delete the poll
+        let info = mock_info(ADDR2, &[]);
+        let msg = ExecuteMsg::Delete {
+            poll_id: "some_id".to_string(),
+        };
+        let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(polls().has(&deps.storage, "some_id".to_string()));
+    }
+
+    #[test]
+    fn test_execute_revoke_valid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create the poll and vote on it
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Withdraw the ballot
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Juno".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "revoke_vote"),
+                attr("poll_id", "some_id"),
+                attr("voter", ADDR1),
+                attr("option", "Juno"),
+            ]
+        );
+
+        // The tally went back to 0 and the ballot is gone
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(0))
+        );
+        let key = (Addr::unchecked(ADDR1), "some_id".to_string());
+        assert!(!BALLOTS.has(&deps.storage, key));
+    }
+
+    #[test]
+    fn test_execute_revoke_invalid() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Nothing to revoke yet
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Juno".to_string(),
+        };
+        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+
+        // Revoking an option other than the one voted for errors
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Osmosis".to_string(),
+        };
+        let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_execute_vote_expired() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A poll that ended before it was created is rejected
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            end_height: Some(env.block.height),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidExpiration {}));
+
+        // Create a poll ending in 10 blocks and vote while it's open
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            end_height: Some(env.block.height + 10),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+        // Once the end height is reached no more votes are accepted
+        env.block.height += 10;
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+    }
+
+    #[test]
+    fn test_execute_poll_lifecycle() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Create a draft poll, it doesn't accept votes yet
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            draft: true,
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let vote = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotActive {}));
+
+        // Only the creator or admin can open it
+        let msg = ExecuteMsg::OpenPoll {
+            poll_id: "some_id".to_string(),
+        };
+        let other = mock_info(ADDR2, &[]);
+        let _err = execute(deps.as_mut(), env.clone(), other, msg.clone()).unwrap_err();
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap();
+
+        // Finalizing an open poll that hasn't expired is not allowed
+        let finalize = ExecuteMsg::FinalizePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _err = execute(deps.as_mut(), env.clone(), info.clone(), finalize.clone()).unwrap_err();
+
+        // Close it, votes are now rejected
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), vote).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+
+        // Finalize it, and it can't be finalized twice
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), finalize.clone()).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "finalize_poll"));
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(poll.status, PollStatus::Finalized);
+        let _err = execute(deps.as_mut(), env, info, finalize).unwrap_err();
+    }
+
+    #[test]
+    fn test_execute_end_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Two polls ending in 10 blocks, one with a clear winner and one tied
+        for poll_id in ["winner", "tied"] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                end_height: Some(env.block.height + 10),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        for (voter, poll_id, vote) in [
+            (ADDR1, "winner", "Juno"),
+            (ADDR2, "winner", "Juno"),
+            (ADDR1, "tied", "Juno"),
+            (ADDR2, "tied", "Osmosis"),
+        ] {
+            let msg = vote_msg(poll_id, vote);
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        // Too early to end the poll
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "winner".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotExpired {}));
+
+        // Once expired anyone can end it
+        env.block.height += 10;
+        let other = mock_info(ADDR2, &[]);
+        let res = execute(deps.as_mut(), env.clone(), other, msg.clone()).unwrap();
+        assert_eq!(res.events[0].ty, "poll_ended");
+        assert_eq!(
+            res.events[0].attributes[..4],
+            vec![
+                attr("poll_id", "winner"),
+                attr("winner", "Juno"),
+                attr("tie", "false"),
+                attr("outcome", "passed"),
+            ]
+        );
+        let poll = polls().load(&deps.storage, "winner".to_string()).unwrap();
+        assert_eq!(poll.status, PollStatus::Finalized);
+        assert_eq!(poll.result.unwrap().winner, Some("Juno".to_string()));
+
+        // It can only be ended once
+        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+
+        // A tie has no winner but records the tied options
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "tied".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let poll = polls().load(&deps.storage, "tied".to_string()).unwrap();
+        assert_eq!(
+            poll.result,
+            Some(PollResult {
+                winner: None,
+                tied: vec!["Juno".to_string(), "Osmosis".to_string()],
+                outcome: PollOutcome::Rejected,
+            })
+        );
+    }
+
+    #[test]
+    fn test_create_poll_auto_id() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Ids picked by hand are skipped over
+        let msg = ExecuteMsg::CreatePoll(new_poll("2"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for expected in ["1", "3"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(""));
+            let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let data: CreatePollResponse = from_binary(&res.data.unwrap()).unwrap();
+            assert_eq!(data.poll_id, expected);
+            assert!(res.attributes.contains(&attr("poll_id", expected)));
+            assert!(polls().has(&deps.storage, expected.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_update_poll() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            option_details: vec![(
+                "Juno".to_string(),
+                OptionDetails {
+                    description: "The smart contract hub".to_string(),
+                    image_uri: None,
+                },
+            )],
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Only the creator may fix their poll
+        let update = ExecuteMsg::UpdatePoll {
+            poll_id: "some_id".to_string(),
+            question: Some("What's your favourite Cosmos chain?".to_string()),
+            options: Some(vec!["Juno".to_string(), "Stargaze".to_string()]),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            update.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), update.clone()).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(poll.question, "What's your favourite Cosmos chain?");
+        let labels: Vec<_> = poll
+            .options
+            .iter()
+            .map(|option| option.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["Juno", "Stargaze"]);
+        assert_eq!(poll.options[0].description, "The smart contract hub");
+
+        // Not anymore once someone voted
+        let msg = vote_msg("some_id", "Stargaze");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), env, info, update).unwrap_err();
+        assert!(matches!(err, ContractError::PollHasVotes {}));
+    }
+
+    #[test]
+    fn test_query_all_polls_paginated() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        for poll_id in ["poll_1", "poll_2", "poll_3"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // First page
+        let msg = QueryMsg::AllPolls {
+            start_after: None,
+            limit: Some(2),
+            order: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["poll_1", "poll_2"]);
+
+        // Continue after the last id of the previous page
+        let msg = QueryMsg::AllPolls {
+            start_after: Some("poll_2".to_string()),
+            limit: Some(2),
+            order: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["poll_3"]);
+
+        // Backwards from the other end
+        let msg = QueryMsg::AllPolls {
+            start_after: Some("poll_3".to_string()),
+            limit: None,
+            order: Some(SortOrder::Descending),
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["poll_2", "poll_1"]);
+    }
+
+    #[test]
+    fn test_query_poll_count_and_exists() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for poll_id in ["poll_1", "poll_2"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::Delete {
+            poll_id: "poll_1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let bin = query(deps.as_ref(), env.clone(), QueryMsg::PollCount {}).unwrap();
+        let res: PollCountResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.count, 1);
+        for (poll_id, exists) in [("poll_1", false), ("poll_2", true)] {
+            let msg = QueryMsg::PollExists {
+                poll_id: poll_id.to_string(),
+            };
+            let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+            let res: PollExistsResponse = from_binary(&bin).unwrap();
+            assert_eq!(res.exists, exists);
+        }
+    }
+
+    #[test]
+    fn test_query_stats() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for poll_id in ["poll_1", "poll_2"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        // Changing a vote casts another one, but it's still the same voter
+        for vote in ["Juno", "Osmosis"] {
+            let msg = vote_msg("poll_1", vote);
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        }
+        for msg in [
+            ExecuteMsg::ClosePoll {
+                poll_id: "poll_1".to_string(),
+            },
+            ExecuteMsg::FinalizePoll {
+                poll_id: "poll_1".to_string(),
+            },
+        ] {
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let bin = query(deps.as_ref(), env, QueryMsg::Stats {}).unwrap();
+        let stats: Stats = from_binary(&bin).unwrap();
+        assert_eq!(
+            stats,
+            Stats {
+                polls_created: 2,
+                votes_cast: 4,
+                unique_voters: 2,
+                polls_finalized: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_poll_voters() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, private_ballots) in [("public", false), ("private", true)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                private_ballots,
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            for (voter, vote) in [(ADDR1, "Juno"), (ADDR2, "Osmosis")] {
+                let msg = vote_msg(poll_id, vote);
+                let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+            }
+        }
+
+        let msg = QueryMsg::PollVoters {
+            poll_id: "public".to_string(),
+            start_after: None,
+            limit: Some(1),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: PollVotersResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.voters.len(), 1);
+        assert_eq!(res.voters[0].voter, Addr::unchecked(ADDR1));
+        assert_eq!(res.voters[0].ballot.options, vec!["Juno".to_string()]);
+        let msg = QueryMsg::PollVoters {
+            poll_id: "public".to_string(),
+            start_after: Some(ADDR1.to_string()),
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: PollVotersResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.voters.len(), 1);
+        assert_eq!(res.voters[0].voter, Addr::unchecked(ADDR2));
+
+        // Who voted for what in a private poll stays hidden
+        let msg = QueryMsg::PollVoters {
+            poll_id: "private".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        assert!(query(deps.as_ref(), env, msg).is_err());
+    }
+
+    #[test]
+    fn test_query_votes_by_voter() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, private_ballots) in [("poll_1", false), ("poll_2", true), ("poll_3", false)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                private_ballots,
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let votes_of = |deps: Deps, start_after: Option<&str>| {
+            let msg = QueryMsg::VotesByVoter {
+                address: ADDR1.to_string(),
+                viewing_key: None,
+                start_after: start_after.map(|poll_id| poll_id.to_string()),
+                limit: None,
+            };
+            let bin = query(deps, mock_env(), msg).unwrap();
+            let res: VotesByVoterResponse = from_binary(&bin).unwrap();
+            res.votes
+        };
+        // The private ballot stays hidden
+        let votes = votes_of(deps.as_ref(), None);
+        let polls: Vec<_> = votes.iter().map(|vote| vote.poll_id.as_str()).collect();
+        assert_eq!(polls, vec!["poll_1", "poll_3"]);
+        assert_eq!(votes[0].ballot.options, vec!["Juno".to_string()]);
+        let votes = votes_of(deps.as_ref(), Some("poll_1"));
+        assert_eq!(votes.len(), 1);
+        assert_eq!(votes[0].poll_id, "poll_3");
+    }
+
+    #[test]
+    fn test_query_polls_by_creator() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        for (creator, poll_id) in [(ADDR1, "poll_1"), (ADDR2, "poll_2"), (ADDR1, "poll_3")] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(creator, &[]), msg).unwrap();
+        }
+
+        let polls_by = |deps: Deps, start_after: Option<&str>| {
+            let msg = QueryMsg::PollsByCreator {
+                creator: ADDR1.to_string(),
+                start_after: start_after.map(|poll_id| poll_id.to_string()),
+                limit: None,
+            };
+            let bin = query(deps, mock_env(), msg).unwrap();
+            let res: AllPollsResponse = from_binary(&bin).unwrap();
+            res.polls.into_iter().map(|p| p.poll_id).collect::<Vec<_>>()
+        };
+        assert_eq!(polls_by(deps.as_ref(), None), vec!["poll_1", "poll_3"]);
+        assert_eq!(polls_by(deps.as_ref(), Some("poll_1")), vec!["poll_3"]);
+
+        // Deleted polls leave the index
+        let msg = ExecuteMsg::Delete {
+            poll_id: "poll_3".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, mock_info(ADDR1, &[]), msg).unwrap();
+        assert_eq!(polls_by(deps.as_ref(), None), vec!["poll_1"]);
+    }
+
+    #[test]
+    fn test_clone_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        let details = OptionDetails {
+            description: "The smart contract hub".to_string(),
+            image_uri: None,
+        };
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            tags: vec!["coins".to_string()],
+            option_details: vec![("Juno".to_string(), details.clone())],
+            end_time: Some(env.block.time.plus_seconds(3600)),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(7200);
+
+        let clone = |poll_id: &str| ExecuteMsg::ClonePoll {
+            poll_id: poll_id.to_string(),
+            new_poll_id: "again".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            clone("missing"),
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::PollNotFound {}));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            clone("some_id"),
+        );
+        assert!(res
+            .unwrap()
+            .attributes
+            .contains(&attr("cloned_from", "some_id")));
+
+        // Same question and options, a new owner, a fresh hour and no votes
+        let original = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        let cloned = polls().load(&deps.storage, "again".to_string()).unwrap();
+        assert_eq!(cloned.creator, ADDR2);
+        assert_eq!(cloned.question, original.question);
+        assert_eq!(cloned.options, original.options);
+        assert_eq!(cloned.options[1].description, details.description);
+        assert_eq!(cloned.tags, original.tags);
+        assert_eq!(cloned.end_time, Some(env.block.time.plus_seconds(3600)));
+        let tallies = cloned.tallies(&deps.storage, "again").unwrap();
+        assert!(tallies.iter().all(|(_, votes)| votes.is_zero()));
+
+        // A start before the original was created moves to now rather than underflowing
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            start_time: Some(env.block.time.minus_seconds(600)),
+            end_time: Some(env.block.time.plus_seconds(3600)),
+            ..new_poll("started")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        env.block.time = env.block.time.plus_seconds(60);
+        let msg = ExecuteMsg::ClonePoll {
+            poll_id: "started".to_string(),
+            new_poll_id: "restarted".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        let cloned = polls()
+            .load(&deps.storage, "restarted".to_string())
+            .unwrap();
+        assert_eq!(cloned.start_time, Some(env.block.time));
+        assert_eq!(cloned.end_time, Some(env.block.time.plus_seconds(3600)));
+    }
+
+    #[test]
+    fn test_recurring_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        const WEEK: u64 = 7 * 24 * 3600;
+
+        // The schedule decides when instances end
+        let create = |poll: NewPoll| ExecuteMsg::CreateRecurringPoll {
+            template_id: "weekly".to_string(),
+            poll,
+            period: WEEK,
+        };
+        let msg = create(NewPoll {
+            end_time: Some(env.block.time.plus_seconds(WEEK)),
+            ..new_poll("")
+        });
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRecurringPoll {}));
+        let msg = create(new_poll(""));
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+
+        // Anyone can spawn an instance, which belongs to the creator and runs for the period
+        let spawn = || ExecuteMsg::SpawnNextInstance {
+            template_id: "weekly".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), spawn()).unwrap();
+        let poll = polls().load(&deps.storage, "weekly-1".to_string()).unwrap();
+        assert_eq!(poll.creator, ADDR1);
+        assert_eq!(poll.end_time, Some(env.block.time.plus_seconds(WEEK)));
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), spawn()).unwrap_err();
+        assert!(matches!(err, ContractError::InstanceNotDue { .. }));
+
+        // A missed period is skipped
+        let start = env.block.time;
+        env.block.time = start.plus_seconds(2 * WEEK + 60);
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), spawn()).unwrap();
+        let poll = polls().load(&deps.storage, "weekly-2".to_string()).unwrap();
+        assert_eq!(poll.end_time, Some(start.plus_seconds(3 * WEEK)));
+
+        let msg = QueryMsg::RecurringInstances {
+            template_id: "weekly".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: RecurringInstancesResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res.instances,
+            vec![(1, "weekly-1".to_string()), (2, "weekly-2".to_string())]
+        );
+
+        // Only the creator or the admin can stop it, and the id stays taken
+        let msg = ExecuteMsg::StopRecurringPoll {
+            template_id: "weekly".to_string(),
+        };
+        let info = mock_info(ADDR2, &[]);
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), spawn()).unwrap_err();
+        assert!(matches!(err, ContractError::RecurringPollNotFound {}));
+        let msg = create(new_poll(""));
+        let err = execute(deps.as_mut(), env, mock_info(ADDR1, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::RecurringPollExists { .. }));
+    }
+
+    #[test]
+    fn test_query_polls_by_status() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for poll_id in ["poll_1", "poll_2", "poll_3"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll_2".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let polls_in = |deps: Deps, status: PollStatus, start_after: Option<&str>| {
+            let msg = QueryMsg::PollsByStatus {
+                status,
+                start_after: start_after.map(|poll_id| poll_id.to_string()),
+                limit: None,
+            };
+            let bin = query(deps, mock_env(), msg).unwrap();
+            let res: AllPollsResponse = from_binary(&bin).unwrap();
+            res.polls.into_iter().map(|p| p.poll_id).collect::<Vec<_>>()
+        };
+        let active = polls_in(deps.as_ref(), PollStatus::Active, None);
+        assert_eq!(active, vec!["poll_1", "poll_3"]);
+        let active = polls_in(deps.as_ref(), PollStatus::Active, Some("poll_1"));
+        assert_eq!(active, vec!["poll_3"]);
+        let closed = polls_in(deps.as_ref(), PollStatus::Closed, None);
+        assert_eq!(closed, vec!["poll_2"]);
+        assert!(polls_in(deps.as_ref(), PollStatus::Draft, None).is_empty());
+    }
+
+    #[test]
+    fn test_query_ending_polls() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, hours) in [("poll_1", Some(3)), ("poll_2", None), ("poll_3", Some(1))] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                end_time: hours.map(|hours| env.block.time.plus_seconds(hours * 3600)),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let ending = |deps: Deps, start_after: Option<&str>| {
+            let msg = QueryMsg::EndingPolls {
+                start_after: start_after.map(|poll_id| poll_id.to_string()),
+                limit: None,
+            };
+            let bin = query(deps, mock_env(), msg).unwrap();
+            let res: AllPollsResponse = from_binary(&bin).unwrap();
+            res.polls.into_iter().map(|p| p.poll_id).collect::<Vec<_>>()
+        };
+        // Polls without an end time never end
+        assert_eq!(ending(deps.as_ref(), None), vec!["poll_3", "poll_1"]);
+        assert_eq!(ending(deps.as_ref(), Some("poll_3")), vec!["poll_1"]);
+
+        // Closing a poll moves it out of the active part of the index
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "poll_3".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(ending(deps.as_ref(), None), vec!["poll_1"]);
+    }
+
+    #[test]
+    fn test_query_polls_by_tag() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Tags must be distinct
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            tags: vec!["defi".to_string(), "defi".to_string()],
+            ..new_poll("poll_1")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTags { .. }));
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            description: "x".repeat(MAX_DESCRIPTION_LENGTH + 1),
+            ..new_poll("poll_1")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::MetadataTooLong {}));
+
+        for (poll_id, tags) in [
+            ("poll_1", vec!["defi", "juno"]),
+            ("poll_2", vec!["juno"]),
+            ("poll_3", vec!["defi"]),
+        ] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                description: "Pick one".to_string(),
+                metadata_uri: Some("ipfs://poll".to_string()),
+                tags: tags.into_iter().map(String::from).collect(),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        let polls_by = |deps: Deps, tag: &str| {
+            let msg = QueryMsg::PollsByTag {
+                tag: tag.to_string(),
+                start_after: None,
+                limit: None,
+            };
+            let bin = query(deps, mock_env(), msg).unwrap();
+            let res: AllPollsResponse = from_binary(&bin).unwrap();
+            res.polls.into_iter().map(|p| p.poll_id).collect::<Vec<_>>()
+        };
+        assert_eq!(polls_by(deps.as_ref(), "defi"), vec!["poll_1", "poll_3"]);
+        assert_eq!(polls_by(deps.as_ref(), "juno"), vec!["poll_1", "poll_2"]);
+
+        // Deleted polls leave the index
+        let msg = ExecuteMsg::Delete {
+            poll_id: "poll_1".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(polls_by(deps.as_ref(), "defi"), vec!["poll_3"]);
+    }
+
+    #[test]
+    fn test_query_poll_results() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let results = |deps: Deps| {
+            let msg = QueryMsg::PollResults {
+                poll_id: "some_id".to_string(),
+            };
+            let bin = query(deps, mock_env(), msg).unwrap();
+            from_binary::<PollResultsResponse>(&bin).unwrap()
+        };
+        let res = results(deps.as_ref());
+        assert_eq!(res.total_votes, Uint128::zero());
+        assert_eq!(res.options[0].percentage, Decimal::zero());
+        assert_eq!(res.leader, None);
+
+        for (voter, vote) in [(ADDR1, "Juno"), (ADDR2, "Osmosis")] {
+            let msg = vote_msg("some_id", vote);
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        let res = results(deps.as_ref());
+        assert_eq!(res.leader, None);
+        assert_eq!(res.tied, vec!["Juno".to_string(), "Osmosis".to_string()]);
+        assert_eq!(res.options[1].percentage, Decimal::percent(50));
+
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env, mock_info("addr3", &[]), msg).unwrap();
+        let res = results(deps.as_ref());
+        assert_eq!(res.total_votes, Uint128::new(3));
+        assert_eq!(res.leader, Some("Juno".to_string()));
+        assert!(res.tied.is_empty());
+        assert_eq!(res.options[1].votes, Uint128::new(2));
+        assert_eq!(res.options[1].percentage, Decimal::from_ratio(2u128, 3u128));
+
+        let msg = QueryMsg::PollResults {
+            poll_id: "other_id".to_string(),
+        };
+        assert!(query(deps.as_ref(), mock_env(), msg).is_err());
+    }
+
+    #[test]
+    fn test_query_recent_polls() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for poll_id in ["b", "c", "a"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::Delete {
+            poll_id: "c".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let msg = QueryMsg::RecentPolls { limit: None };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: AllPollsResponse = from_binary(&bin).unwrap();
+        let ids: Vec<_> = res.polls.iter().map(|p| p.poll_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_execute_vote_inconsistent_state() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let vote = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap();
+
+        // Corrupt the tally so the old ballot can't be taken back out
+        TALLIES
+            .save(
+                &mut deps.storage,
+                ("some_id", 1),
+                &Uint128::zero(),
+                env.block.height,
+            )
+            .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), vote.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TallyUnderflow { .. }));
+
+        // Remove the option the ballot points at entirely
+        let mut poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        poll.options.remove(1);
+        polls()
+            .save(&mut deps.storage, "some_id".to_string(), &poll)
+            .unwrap();
+        let msg = vote_msg("some_id", "Osmosis");
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound { .. }));
+    }
+
+    #[test]
+    fn test_execute_vote_weighted() {
+        // ADDR1 holds 100ujuno, ADDR2 holds none
+        let mut deps = mock_dependencies_with_balances(&[(ADDR1, &[coin(100, "ujuno")])]);
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            weight_denom: Some("ujuno".to_string()),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // The vote counts for the whole balance
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(100))
+        );
+
+        // Without a balance there is nothing to vote with
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingPower {}));
+
+        // Balances in micro-denoms can go well past what a u64 holds
+        let whale = u64::MAX as u128 * 1_000;
+        deps.querier
+            .update_balance(ADDR2, vec![coin(whale, "ujuno")]);
+        let msg = vote_msg("some_id", "Osmosis");
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[2].1,
+            Uint128::new(whale)
+        );
+
+        // Revoking takes back exactly what was added
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Juno".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(0))
+        );
+    }
+
+    #[test]
+    fn test_power_snapshot() {
+        let mut deps = mock_dependencies_with_balances(&[(ADDR1, &[coin(100, "ujuno")])]);
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Snapshots need a weight source to read from
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            snapshot: true,
+            ..new_poll("unweighted")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSnapshot {}));
+
+        // ADDR1 records their balance on the ujuno source through an ordinary weighted poll
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            weight_denom: Some("ujuno".to_string()),
+            ..new_poll("live")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CheckpointPower {
+            poll_id: "live".to_string(),
+            address: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        env.block.height += 1;
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            weight_denom: Some("ujuno".to_string()),
+            snapshot: true,
+            ..new_poll("snapshot")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Tokens arriving after the poll started don't count
+        env.block.height += 1;
+        deps.querier.update_balance(ADDR1, vec![coin(150, "ujuno")]);
+        deps.querier.update_balance(ADDR2, vec![coin(50, "ujuno")]);
+        let msg = ExecuteMsg::CheckpointPower {
+            poll_id: "snapshot".to_string(),
+            address: Some(ADDR2.to_string()),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("snapshot", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingPower {}));
+        let poll = polls().load(&deps.storage, "snapshot".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "snapshot").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(100))
+        );
+
+        // Nor do tokens given away since
+        deps.querier.update_balance(ADDR1, vec![coin(30, "ujuno")]);
+        let msg = vote_msg("snapshot", "Osmosis");
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let poll = polls().load(&deps.storage, "snapshot".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "snapshot").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(0))
+        );
+        assert_eq!(
+            poll.tallies(&deps.storage, "snapshot").unwrap()[2],
+            ("Osmosis".to_string(), Uint128::new(30))
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_staking_weighted() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+
+        // ADDR1 delegates to two validators, ADDR2 has nothing bonded
+        let validators: Vec<_> = ["val1", "val2"]
+            .iter()
+            .map(|address| Validator {
+                address: address.to_string(),
+                commission: Decimal::percent(5),
+                max_commission: Decimal::percent(10),
+                max_change_rate: Decimal::percent(1),
+            })
+            .collect();
+        let delegations: Vec<_> = [("val1", 30), ("val2", 12)]
+            .iter()
+            .map(|(validator, amount)| FullDelegation {
+                delegator: Addr::unchecked(ADDR1),
+                validator: validator.to_string(),
+                amount: coin(*amount, "ustake"),
+                can_redelegate: coin(0, "ustake"),
+                accumulated_rewards: vec![],
+            })
+            .collect();
+        deps.querier
+            .update_staking("ustake", &validators, &delegations);
+
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A poll can't be weighted by stake and a token at the same time
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            staking_weighted: true,
+            weight_denom: Some("ujuno".to_string()),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::MultipleWeightSources {}));
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            staking_weighted: true,
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // The vote counts for the total stake
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(42))
+        );
+
+        let err = execute(deps.as_mut(), env, mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoVotingPower {}));
+    }
+
+    #[test]
+    fn test_execute_vote_multiple_choice() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // More selections than options is not a valid poll
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            max_selections: Some(4),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMaxSelections {}));
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            max_selections: Some(2),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Pick two options
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            proposal_id: None,
+            vote: None,
+            option_index: None,
+            votes: vec!["Juno".to_string(), "Osmosis".to_string()],
+            credits: vec![],
+            proof: vec![],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Three is too many, and the same option can't be picked twice
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            proposal_id: None,
+            vote: None,
+            option_index: None,
+            votes: vec![
+                "Cosmos Hub".to_string(),
+                "Juno".to_string(),
+                "Osmosis".to_string(),
+            ],
+            credits: vec![],
+            proof: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TooManySelections { max: 2 }));
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            proposal_id: None,
+            vote: None,
+            option_index: None,
+            votes: vec!["Juno".to_string(), "Juno".to_string()],
+            credits: vec![],
+            proof: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateSelection { .. }));
+
+        // Revoting takes back both previous selections
+        let _res = execute(deps.as_mut(), env, info, vote_msg("some_id", "Cosmos Hub")).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap(),
+            vec![
+                ("Cosmos Hub".to_string(), Uint128::new(1)),
+                ("Juno".to_string(), Uint128::new(0)),
+                ("Osmosis".to_string(), Uint128::new(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranked_choice_runoff() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            tally_type: TallyType::RankedChoice,
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Juno leads on first preferences but Osmosis picks up the Cosmos Hub voter
+        let rankings = [
+            ("voter1", vec!["Juno", "Cosmos Hub"]),
+            ("voter2", vec!["Juno"]),
+            ("voter3", vec!["Osmosis", "Juno"]),
+            ("voter4", vec!["Osmosis"]),
+            ("voter5", vec!["Cosmos Hub", "Osmosis", "Juno"]),
+        ];
+        for (voter, ranking) in rankings {
+            let msg = ExecuteMsg::Vote {
+                poll_id: "some_id".to_string(),
+                proposal_id: None,
+                vote: None,
+                option_index: None,
+                votes: ranking.iter().map(|option| option.to_string()).collect(),
+                credits: vec![],
+                proof: vec![],
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        // Only first preferences are in the live tally
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[0],
+            ("Cosmos Hub".to_string(), Uint128::new(1))
+        );
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(2))
+        );
+
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::FinalizePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(poll.result.unwrap().winner, Some("Osmosis".to_string()));
+
+        let msg = QueryMsg::TallyRounds {
+            poll_id: "some_id".to_string(),
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let res: TallyRoundsResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.rounds.len(), 2);
+        assert_eq!(res.rounds[0].eliminated, vec!["Cosmos Hub".to_string()]);
+        assert_eq!(
+            res.rounds[1].tallies,
+            vec![
+                ("Juno".to_string(), Uint128::new(2)),
+                ("Osmosis".to_string(), Uint128::new(3))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_execute_vote_quadratic() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Every voter gets 100 credits
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            tally_type: TallyType::Quadratic,
+            credit_budget: Some(100),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Spending more than the budget is rejected
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            proposal_id: None,
+            vote: None,
+            option_index: None,
+            votes: vec!["Juno".to_string(), "Osmosis".to_string()],
+            credits: vec![64, 37],
+            proof: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::CreditBudgetExceeded { budget } if budget == Uint128::new(100)
+        ));
+
+        // A quadratic poll needs credits for every selection
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::CreditsMismatch {}));
+
+        // 64 credits buy 8 votes, 36 buy 6
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            proposal_id: None,
+            vote: None,
+            option_index: None,
+            votes: vec!["Juno".to_string(), "Osmosis".to_string()],
+            credits: vec![64, 36],
+            proof: vec![],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(8))
+        );
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[2],
+            ("Osmosis".to_string(), Uint128::new(6))
+        );
+
+        // Revoting puts everything back first, 99 credits round down to 9 votes
+        let msg = ExecuteMsg::Vote {
+            poll_id: "some_id".to_string(),
+            proposal_id: None,
+            vote: Some("Cosmos Hub".to_string()),
+            option_index: None,
+            votes: vec![],
+            credits: vec![99],
+            proof: vec![],
+        };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap(),
+            vec![
+                ("Cosmos Hub".to_string(), Uint128::new(9)),
+                ("Juno".to_string(), Uint128::new(0)),
+                ("Osmosis".to_string(), Uint128::new(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gov_style_veto() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Two gov style polls, one with a 50% veto threshold
+        let thresholds = [("vetoed", None), ("passed", Some(Decimal::percent(50)))];
+        for (poll_id, veto_threshold) in thresholds {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                options: vec!["Yes".to_string(), "No".to_string()],
+                end_height: Some(env.block.height + 10),
+                gov_style: true,
+                veto_threshold,
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let poll = polls().load(&deps.storage, "vetoed".to_string()).unwrap();
+        let labels: Vec<_> = poll
+            .options
+            .iter()
+            .map(|option| option.label.clone())
+            .collect();
+        assert_eq!(labels, vec!["Yes", "No", "Abstain", "NoWithVeto"]);
+
+        // Yes leads but 2 of 5 votes are vetoes
+        for poll_id in ["vetoed", "passed"] {
+            for (voter, vote) in [
+                ("voter1", "Yes"),
+                ("voter2", "Yes"),
+                ("voter3", "Yes"),
+                ("voter4", "NoWithVeto"),
+                ("voter5", "NoWithVeto"),
+            ] {
+                let msg = vote_msg(poll_id, vote);
+                let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+            }
+        }
+
+        env.block.height += 10;
+        let outcomes = [
+            ("vetoed", PollOutcome::Vetoed),
+            ("passed", PollOutcome::Passed),
+        ];
+        for (poll_id, outcome) in outcomes {
+            let msg = ExecuteMsg::EndPoll {
+                poll_id: poll_id.to_string(),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let bin = query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Poll {
+                    poll_id: poll_id.to_string(),
+                },
+            )
+            .unwrap();
+            let res: PollResponse = from_binary(&bin).unwrap();
+            assert_eq!(res.poll.unwrap().result.unwrap().outcome, outcome);
+        }
+    }
+
+    #[test]
+    fn test_quorum_and_threshold() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A quorum is meaningless without knowing the electorate
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            quorum: Some(Decimal::percent(40)),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::MissingEligiblePower {}));
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            threshold: Some(Decimal::percent(101)),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidThreshold {}));
+
+        // 10 eligible voters, 40% quorum and a 60% pass threshold
+        for poll_id in ["quorum", "threshold", "passed"] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                end_height: Some(env.block.height + 10),
+                quorum: Some(Decimal::percent(40)),
+                threshold: Some(Decimal::percent(60)),
+                eligible_power: Some(10),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let ballots = [
+            ("quorum", vec!["Juno", "Juno", "Osmosis"]),
+            ("threshold", vec!["Juno", "Juno", "Osmosis", "Cosmos Hub"]),
+            ("passed", vec!["Juno", "Juno", "Juno", "Osmosis"]),
+        ];
+        for (poll_id, votes) in ballots {
+            for (i, vote) in votes.into_iter().enumerate() {
+                let voter = mock_info(&format!("voter{}", i), &[]);
+                let msg = vote_msg(poll_id, vote);
+                let _res = execute(deps.as_mut(), env.clone(), voter, msg).unwrap();
+            }
+        }
+
+        env.block.height += 10;
+        let outcomes = [
+            ("quorum", PollOutcome::QuorumNotMet),
+            ("threshold", PollOutcome::Rejected),
+            ("passed", PollOutcome::Passed),
+        ];
+        for (poll_id, outcome) in outcomes {
+            let msg = ExecuteMsg::EndPoll {
+                poll_id: poll_id.to_string(),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let poll = polls().load(&deps.storage, poll_id.to_string()).unwrap();
+            assert_eq!(poll.result.unwrap().outcome, outcome);
+        }
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Commits close after 10 blocks, reveals after 20
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            end_height: Some(env.block.height + 10),
+            commit_reveal: true,
+            reveal_end_height: Some(env.block.height + 20),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Plain votes would be public straight away
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::CommitRevealRequired {}));
+
+        let hash = vote_commitment("some_id", ADDR1, "Juno", "pepper").unwrap();
+        let msg = ExecuteMsg::CommitVote {
+            poll_id: "some_id".to_string(),
+            hash,
+            proof: vec![],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Too early to reveal
+        let reveal = |vote: &str, salt: &str| ExecuteMsg::RevealVote {
+            poll_id: "some_id".to_string(),
+            vote: vote.to_string(),
+            salt: salt.to_string(),
+        };
+        let msg = reveal("Juno", "pepper");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::RevealNotStarted {}));
+
+        // Commits are closed, but the poll can't end until the reveal is over
+        env.block.height += 10;
+        let msg = ExecuteMsg::CommitVote {
+            poll_id: "some_id".to_string(),
+            hash: vote_commitment("some_id", ADDR1, "Osmosis", "salt").unwrap(),
+            proof: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotExpired {}));
+
+        // Only the committed vote can be revealed, and only by the committer
+        let msg = reveal("Osmosis", "pepper");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentMismatch {}));
+        let other = mock_info(ADDR2, &[]);
+        let err = execute(deps.as_mut(), env.clone(), other, reveal("Juno", "pepper")).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentNotFound {}));
+
+        let msg = reveal("Juno", "pepper");
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "reveal_vote"));
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(1))
+        );
+
+        // Revealing twice would count the vote twice
+        let msg = reveal("Juno", "pepper");
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentNotFound {}));
+    }
+
+    #[test]
+    fn test_private_ballots() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            private_ballots: true,
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        // Neither the event nor the attributes say what was voted for
+        assert!(res.events[0].attributes.iter().all(|a| a.key != "option"));
+        assert!(res.attributes.iter().all(|a| a.key != "option"));
+
+        // The ballot is stored under the hashed voter only
+        let key = (Addr::unchecked(ADDR1), "some_id".to_string());
+        assert!(!BALLOTS.has(&deps.storage, key));
+        let key = ("some_id".to_string(), Addr::unchecked(ADDR1));
+        assert!(!POLL_VOTERS.has(&deps.storage, key));
+        let key = ("some_id".to_string(), voter_hash("some_id", ADDR1).unwrap());
+        assert!(PRIVATE_BALLOTS.has(&deps.storage, key));
+
+        // Nobody can query the ballot, but the voter can read it back and totals stay public
+        let msg = QueryMsg::Vote {
+            poll_id: "some_id".to_string(),
+            address: ADDR1.to_string(),
+            viewing_key: None,
+        };
+        query(deps.as_ref(), env.clone(), msg).unwrap_err();
+        let msg = ExecuteMsg::MyVote {
+            poll_id: "some_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let res: VoteResponse = from_binary(&res.data.unwrap()).unwrap();
+        assert_eq!(res.vote.unwrap().options, vec!["Juno".to_string()]);
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(1))
+        );
+
+        // Revoking finds the hashed ballot too
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "some_id".to_string(),
+            vote: "Juno".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(0))
+        );
+    }
+
+    #[test]
+    fn test_viewing_key() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, private_ballots) in [("public", false), ("private", true)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                private_ballots,
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let vote_query = |poll_id: &str, viewing_key: Option<&str>| QueryMsg::Vote {
+            poll_id: poll_id.to_string(),
+            address: ADDR1.to_string(),
+            viewing_key: viewing_key.map(String::from),
+        };
+
+        // Ballots stay public until a key is set
+        let _res = query(deps.as_ref(), env.clone(), vote_query("public", None)).unwrap();
+        let msg = ExecuteMsg::SetViewingKey {
+            key: "secret".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert!(res.attributes.iter().all(|a| a.value != "secret"));
+        for viewing_key in [None, Some("guess")] {
+            let msg = vote_query("public", viewing_key);
+            query(deps.as_ref(), env.clone(), msg).unwrap_err();
+        }
+        let msg = QueryMsg::PollVoters {
+            poll_id: "public".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: PollVotersResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert!(res.voters.is_empty());
+
+        // The key reads both public and private ballots
+        for poll_id in ["public", "private"] {
+            let msg = vote_query(poll_id, Some("secret"));
+            let res: VoteResponse =
+                from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            assert_eq!(res.vote.unwrap().options, vec!["Juno".to_string()]);
+        }
+
+        // An empty key makes the ballots public again
+        let msg = ExecuteMsg::SetViewingKey { key: String::new() };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let _res = query(deps.as_ref(), env.clone(), vote_query("public", None)).unwrap();
+        query(deps.as_ref(), env, vote_query("private", None)).unwrap_err();
+    }
+
+    #[test]
+    fn test_reputation() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("first"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            min_reputation: Some(1),
+            ..new_poll("second")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let reputation = |deps: Deps, env: Env, address: &str| -> Reputation {
+            let msg = QueryMsg::Reputation {
+                address: address.to_string(),
+            };
+            from_binary(&query(deps, env, msg).unwrap()).unwrap()
+        };
+        let res = reputation(deps.as_ref(), env.clone(), ADDR1);
+        assert_eq!((res.polls_created, res.polls_voted, res.streak), (2, 0, 1));
+
+        // Newcomers have to take part somewhere else first
+        let voter = mock_info(ADDR2, &[]);
+        let msg = vote_msg("second", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), voter.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+        let msg = vote_msg("first", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), voter.clone(), msg).unwrap();
+        // Changing a vote isn't voting in another poll
+        let msg = vote_msg("first", "Osmosis");
+        let _res = execute(deps.as_mut(), env.clone(), voter.clone(), msg).unwrap();
+
+        // Activity in the next period extends the streak
+        env.block.time = env.block.time.plus_seconds(REPUTATION_PERIOD);
+        let msg = vote_msg("second", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), voter.clone(), msg).unwrap();
+        let res = reputation(deps.as_ref(), env.clone(), ADDR2);
+        assert_eq!((res.polls_voted, res.streak), (2, 2));
+        let msg = ExecuteMsg::Revoke {
+            poll_id: "second".to_string(),
+            vote: "Juno".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), voter, msg).unwrap();
+        let res = reputation(deps.as_ref(), env.clone(), ADDR2);
+        assert_eq!((res.polls_voted, res.streak), (1, 2));
+
+        // And a whole period without any ends it
+        env.block.time = env.block.time.plus_seconds(2 * REPUTATION_PERIOD);
+        let res = reputation(deps.as_ref(), env, ADDR2);
+        assert_eq!((res.polls_voted, res.streak), (1, 0));
+    }
+
+    #[test]
+    fn test_leaderboard() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        for (creator, poll_id) in [(ADDR1, "first"), (ADDR1, "second"), (ADDR2, "third")] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(creator, &[]), msg).unwrap();
+        }
+        for (voter, poll_id) in [("addr3", "first"), ("addr3", "third"), (ADDR2, "first")] {
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::Leaderboard {
+            kind: LeaderboardKind::PollsCreated,
+            limit: None,
+        };
+        let res: LeaderboardResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res.entries,
+            vec![(Addr::unchecked(ADDR1), 2), (Addr::unchecked(ADDR2), 1)]
+        );
+        // ADDR1 never voted, so it isn't ranked at all
+        let msg = QueryMsg::Leaderboard {
+            kind: LeaderboardKind::PollsVoted,
+            limit: None,
+        };
+        let res: LeaderboardResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res.entries,
+            vec![(Addr::unchecked("addr3"), 2), (Addr::unchecked(ADDR2), 1)]
+        );
+        let msg = QueryMsg::Leaderboard {
+            kind: LeaderboardKind::PollsVoted,
+            limit: Some(1),
+        };
+        let res: LeaderboardResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(res.entries, vec![(Addr::unchecked("addr3"), 2)]);
+    }
+
+    #[test]
+    fn test_polls_by_id_prefix() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for poll_id in ["dao1/b", "dao10", "dao1/a", "dao2/x", "dao1"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let poll_ids = |deps: Deps, prefix: &str, limit: Option<u32>| -> Vec<String> {
+            let msg = QueryMsg::PollsByIdPrefix {
+                prefix: prefix.to_string(),
+                limit,
+            };
+            let res: AllPollsResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.polls.into_iter().map(|info| info.poll_id).collect()
+        };
+
+        assert_eq!(
+            poll_ids(deps.as_ref(), "dao1/", None),
+            vec!["dao1/a", "dao1/b"]
+        );
+        assert_eq!(
+            poll_ids(deps.as_ref(), "dao1", None),
+            vec!["dao1", "dao1/a", "dao1/b", "dao10"]
+        );
+        assert_eq!(
+            poll_ids(deps.as_ref(), "dao1", Some(2)),
+            vec!["dao1", "dao1/a"]
+        );
+        assert_eq!(poll_ids(deps.as_ref(), "", None).len(), 5);
+        assert!(poll_ids(deps.as_ref(), "dao3", None).is_empty());
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        let owner = mock_info(ADDR1, &[]);
+        let other = mock_info(ADDR2, &[]);
+        let register = |namespace: &str| ExecuteMsg::RegisterNamespace {
+            namespace: namespace.to_string(),
+        };
+        let err =
+            execute(deps.as_mut(), env.clone(), owner.clone(), register("dao/x")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidNamespace {}));
+
+        // A prefix someone else already used can't be claimed
+        let msg = ExecuteMsg::CreatePoll(new_poll("squat/1"));
+        let _res = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap();
+        let err =
+            execute(deps.as_mut(), env.clone(), owner.clone(), register("squat")).unwrap_err();
+        assert!(matches!(err, ContractError::NamespaceTaken { .. }));
+        let msg = ExecuteMsg::CreatePoll(new_poll("dao/1"));
+        let _res = execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), owner.clone(), register("dao")).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), register("dao")).unwrap_err();
+        assert!(matches!(err, ContractError::NamespaceTaken { .. }));
+
+        // Only the owner and the creators it adds can use the namespace
+        let msg = ExecuteMsg::CreatePoll(new_poll("dao/2"));
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NamespaceUnauthorized { .. }));
+        let add = ExecuteMsg::AddNamespaceCreators {
+            namespace: "dao".to_string(),
+            creators: vec![ADDR2.to_string()],
+        };
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), add.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), owner.clone(), add).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap();
+        // Ids merely sharing the letters are outside the namespace
+        let msg = ExecuteMsg::CreatePoll(new_poll("daos/1"));
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("addr3", &[]), msg).unwrap();
+
+        let msg = QueryMsg::Namespace {
+            namespace: "dao".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: NamespaceResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.owner, Some(Addr::unchecked(ADDR1)));
+        assert_eq!(res.creators, vec![Addr::unchecked(ADDR2)]);
+
+        let msg = ExecuteMsg::RemoveNamespaceCreators {
+            namespace: "dao".to_string(),
+            creators: vec![ADDR2.to_string()],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("dao/3"));
+        let err = execute(deps.as_mut(), env, other, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NamespaceUnauthorized { .. }));
+    }
+
+    #[test]
+    fn test_reports() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: Some(2),
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        for poll_id in ["first", "second"] {
+            let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+            let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        }
+        let report = |reason: &str| ExecuteMsg::ReportPoll {
+            poll_id: "first".to_string(),
+            reason: reason.to_string(),
+        };
+        let listed = |deps: Deps| -> Vec<String> {
+            let msg = QueryMsg::AllPolls {
+                start_after: None,
+                limit: None,
+                order: None,
+            };
+            let res: AllPollsResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.polls.into_iter().map(|info| info.poll_id).collect()
+        };
+        let reported = |deps: Deps| -> Vec<ReportedPoll> {
+            let msg = QueryMsg::ReportedPolls {
+                start_after: None,
+                limit: None,
+            };
+            let res: ReportedPollsResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.polls
+        };
+
+        let reporter = mock_info(ADDR2, &[]);
+        let err = execute(deps.as_mut(), env.clone(), reporter.clone(), report("")).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InvalidReportReason { max: 280 }
+        ));
+        let _res = execute(deps.as_mut(), env.clone(), reporter.clone(), report("spam")).unwrap();
+        let err =
+            execute(deps.as_mut(), env.clone(), reporter.clone(), report("spam")).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyReported {}));
+        assert_eq!(listed(deps.as_ref()), vec!["first", "second"]);
+
+        // Reaching the threshold hides the poll from listings, not from direct queries
+        let msg = report("scam");
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("addr3", &[]), msg).unwrap();
+        assert_eq!(listed(deps.as_ref()), vec!["second"]);
+        let msg = QueryMsg::Poll {
+            poll_id: "first".to_string(),
+        };
+        let res: PollResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert!(res.poll.unwrap().hidden);
+        assert_eq!(
+            reported(deps.as_ref()),
+            vec![ReportedPoll {
+                poll_id: "first".to_string(),
+                reports: 2,
+                hidden: true,
+            }]
+        );
+
+        // Moderators clear the queue and decide what stays hidden
+        let resolve = ExecuteMsg::ResolveReport {
+            poll_id: "first".to_string(),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            reporter.clone(),
+            resolve.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), resolve.clone()).unwrap();
+        assert!(reported(deps.as_ref()).is_empty());
+        assert_eq!(listed(deps.as_ref()), vec!["second"]);
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), resolve).unwrap_err();
+        assert!(matches!(err, ContractError::NoReports {}));
+        let msg = ExecuteMsg::UnhidePoll {
+            poll_id: "first".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::HidePoll {
+            poll_id: "second".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin, msg).unwrap();
+        assert_eq!(listed(deps.as_ref()), vec!["first"]);
+        // Resolved reports can be made again
+        let _res = execute(deps.as_mut(), env, reporter, report("spam")).unwrap();
+        assert_eq!(reported(deps.as_ref())[0].reports, 1);
+    }
+
+    #[test]
+    fn test_banned_words() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::AddBannedWords {
+            words: vec!["Scam".to_string(), "rug".to_string()],
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::AddBannedWords {
+            words: vec![" ".to_string()],
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBannedWord {}));
+
+        // Matched anywhere in the question or an option, whatever the case
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            question: "Is this a SCAMcoin?".to_string(),
+            ..new_poll("question")
+        });
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::BannedWord { word } if word == "scam"));
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            options: vec!["Juno".to_string(), "Rugpull".to_string()],
+            ..new_poll("option")
+        });
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::BannedWord { word } if word == "rug"));
+        let msg = ExecuteMsg::CreatePoll(new_poll("clean"));
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdatePoll {
+            poll_id: "clean".to_string(),
+            question: Some("A scam after all".to_string()),
+            options: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::BannedWord { .. }));
+
+        let msg = QueryMsg::BannedWords {
+            start_after: None,
+            limit: None,
+        };
+        let res: BannedWordsResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.words, vec!["rug".to_string(), "scam".to_string()]);
+        let msg = ExecuteMsg::RemoveBannedWords {
+            words: vec!["RUG".to_string()],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            options: vec!["Juno".to_string(), "Rugpull".to_string()],
+            ..new_poll("option")
+        });
+        let _res = execute(deps.as_mut(), env, admin, msg).unwrap();
+    }
+
+    #[test]
+    fn test_force_finalize_and_invalidate() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let admin = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: Some(coin(10, "ujuno")),
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let creator = mock_info(ADDR2, &[]);
+        let funded = mock_info(ADDR2, &[coin(10, "ujuno"), coin(100, "uosmo")]);
+        for poll_id in ["stuck", "bad", "cancelled"] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                end_time: Some(env.block.time.plus_seconds(1000)),
+                reward: Some(coin(100, "uosmo")),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), funded.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        }
+
+        // A stuck poll is counted as it stands, long before it would end
+        let msg = ExecuteMsg::ForceFinalize {
+            poll_id: "stuck".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg.clone()).unwrap();
+        let poll = polls().load(&deps.storage, "stuck".to_string()).unwrap();
+        assert_eq!(poll.status, PollStatus::Finalized);
+        assert_eq!(poll.result.unwrap().winner, Some("Juno".to_string()));
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTransition { .. }));
+
+        // An invalid poll has no winner and hands everything back to its creator
+        let msg = ExecuteMsg::InvalidatePoll {
+            poll_id: "bad".to_string(),
+            reason: "Rigged".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), msg.clone()).unwrap();
+        let refunds: Vec<_> = res
+            .messages
+            .iter()
+            .map(|sub| match &sub.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    (to_address.clone(), amount.clone())
+                }
+                msg => panic!("unexpected message {:?}", msg),
+            })
+            .collect();
+        assert_eq!(
+            refunds,
+            vec![
+                (ADDR2.to_string(), vec![coin(100, "uosmo")]),
+                (ADDR2.to_string(), vec![coin(10, "ujuno")])
+            ]
+        );
+        let poll = polls().load(&deps.storage, "bad".to_string()).unwrap();
+        assert_eq!(poll.status, PollStatus::Finalized);
+        assert_eq!(poll.invalid_reason, Some("Rigged".to_string()));
+        let result = poll.result.unwrap();
+        assert_eq!(result.outcome, PollOutcome::Invalidated);
+        assert_eq!(result.winner, None);
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::PollAlreadyEnded {
+                status: PollStatus::Finalized
+            }
+        ));
+
+        // Nor is there anything left to hand back once a poll is cancelled
+        let msg = ExecuteMsg::CancelPoll {
+            poll_id: "cancelled".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::InvalidatePoll {
+            poll_id: "cancelled".to_string(),
+            reason: "Rigged".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::PollAlreadyEnded {
+                status: PollStatus::Cancelled
+            }
+        ));
+        let msg = ExecuteMsg::ClaimReward {
+            poll_id: "bad".to_string(),
+        };
+        let err = execute(deps.as_mut(), env, admin, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoReward {}));
+    }
+
+    #[test]
+    fn test_cancel_poll() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: Some(coin(10, "ujuno")),
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+        let creator = mock_info(ADDR1, &[]);
+        for poll_id in ["cancelled", "ended"] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                end_time: Some(env.block.time.plus_seconds(1000)),
+                ..new_poll(poll_id)
+            });
+            let funded = mock_info(ADDR1, &[coin(10, "ujuno")]);
+            let _res = execute(deps.as_mut(), env.clone(), funded, msg).unwrap();
+        }
+        for voter in [ADDR1, ADDR2] {
+            let msg = vote_msg("cancelled", "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let cancel = |poll_id: &str| ExecuteMsg::CancelPoll {
+            poll_id: poll_id.to_string(),
+        };
+        let msg = cancel("cancelled");
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            cancel("cancelled"),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR1.to_string(),
+                amount: vec![coin(10, "ujuno")],
+            })
+        );
+        let poll = polls()
+            .load(&deps.storage, "cancelled".to_string())
+            .unwrap();
+        assert_eq!(poll.status, PollStatus::Cancelled);
+        assert_eq!(poll.deposit, None);
+        let key = (Addr::unchecked(ADDR2), "cancelled".to_string());
+        assert!(BALLOTS.load(&deps.storage, key).unwrap().void);
+
+        // A cancelled poll takes no more votes and can't be cancelled again
+        let msg = vote_msg("cancelled", "Osmosis");
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            creator.clone(),
+            cancel("cancelled"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTransition { .. }));
+
+        // Nor can a poll whose voting is over
+        env.block.time = env.block.time.plus_seconds(1000);
+        let err = execute(deps.as_mut(), env, creator, cancel("ended")).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+    }
+
+    #[test]
+    fn test_vote_fee() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let admin = mock_info(ADDR1, &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let update = |share: &str| ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: Some(share.parse().unwrap()),
+            keeper_bounty: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), update("1.5")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVoteFeeShare {}));
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), update("0.25")).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            vote_fee: Some(coin(100, "ujuno")),
+            commit_reveal: true,
+            ..new_poll("secret")
+        });
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVoteFee {}));
+        for poll_id in ["paid", "cancelled"] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                vote_fee: Some(coin(100, "ujuno")),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        }
+
+        // The fee is paid exactly, and only with the first ballot
+        let paying = mock_info(ADDR2, &[coin(100, "ujuno")]);
+        let msg = vote_msg("paid", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::VoteFeeRequired { .. }));
+        let msg = vote_msg("paid", "Juno");
+        let info = mock_info(ADDR2, &[coin(150, "ujuno")]);
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::VoteFeeRequired { .. }));
+        for (poll_id, voter) in [("paid", &paying), ("cancelled", &paying), ("paid", &admin)] {
+            let msg = vote_msg(poll_id, "Juno");
+            let info = mock_info(voter.sender.as_str(), &[coin(100, "ujuno")]);
+            let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
+        let msg = vote_msg("paid", "Osmosis");
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        let msg = vote_msg("paid", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), paying, msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NonPayable {})
+        ));
+
+        // Cancelling pays the fees back
+        let msg = ExecuteMsg::CancelPoll {
+            poll_id: "cancelled".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR2.to_string(),
+                amount: vec![coin(100, "ujuno")],
+            })
+        );
+
+        // Finalizing shares them between the treasury and the creator
+        let msg = ExecuteMsg::ForceFinalize {
+            poll_id: "paid".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let treasury = TREASURY.load(&deps.storage, "ujuno".to_string()).unwrap();
+        assert_eq!(treasury, Uint128::new(50));
+        let msg = QueryMsg::CreatorFees {
+            address: ADDR1.to_string(),
+        };
+        let res: CreatorFeesResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.fees, vec![coin(150, "ujuno")]);
+
+        let msg = ExecuteMsg::ClaimCreatorFees {};
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR1.to_string(),
+                amount: vec![coin(150, "ujuno")],
+            })
+        );
+        let msg = ExecuteMsg::ClaimCreatorFees {};
+        let err = execute(deps.as_mut(), env, admin, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoFees {}));
+    }
+
+    #[test]
+    fn test_treasury() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: Some(coin(5, "ujuno")),
+            poll_deposit: Some(coin(10, "ujuno")),
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let admin = mock_info(ADDR1, &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("deleted"));
+        let info = mock_info(ADDR2, &[coin(15, "ujuno")]);
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        // Deleting the poll forfeits its deposit
+        let msg = ExecuteMsg::Delete {
+            poll_id: "deleted".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+
+        let treasury = |deps: Deps| -> TreasuryResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::Treasury {}).unwrap()).unwrap()
+        };
+        let res = treasury(deps.as_ref());
+        assert_eq!(res.balance, vec![coin(15, "ujuno")]);
+        assert_eq!(
+            res.sources,
+            vec![
+                (TreasurySource::PollFees, vec![coin(5, "ujuno")]),
+                (TreasurySource::VoteFees, vec![]),
+                (TreasurySource::SlashedDeposits, vec![coin(10, "ujuno")]),
+            ]
+        );
+
+        // Only the admin spends, and no more than the treasury holds
+        let spend = |amount: u128| ExecuteMsg::Spend {
+            to: ADDR2.to_string(),
+            amount: coin(amount, "ujuno"),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), spend(8)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), spend(20)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientTreasury { .. }));
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), spend(8)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR2.to_string(),
+                amount: vec![coin(8, "ujuno")],
+            })
+        );
+        let res = treasury(deps.as_ref());
+        assert_eq!(res.balance, vec![coin(7, "ujuno")]);
+        assert_eq!(res.sources[0], (TreasurySource::PollFees, vec![]));
+        assert_eq!(
+            res.sources[2],
+            (TreasurySource::SlashedDeposits, vec![coin(7, "ujuno")])
+        );
+
+        // Withdrawing empties every source
+        let msg = ExecuteMsg::WithdrawFees { recipient: None };
+        let _res = execute(deps.as_mut(), env, admin, msg).unwrap();
+        let res = treasury(deps.as_ref());
+        assert!(res.balance.is_empty());
+        assert!(res.sources.iter().all(|(_, coins)| coins.is_empty()));
+    }
+
+    #[test]
+    fn test_executor_payload() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let payloads: Vec<Binary> = ["hub", "juno", "osmosis", "extra"]
+            .into_iter()
+            .map(|payload| to_binary(payload).unwrap())
+            .collect();
+        let proposal = |payloads: &[Binary]| {
+            ExecuteMsg::CreatePoll(NewPoll {
+                executor: Some("executor".to_string()),
+                payload_per_option: payloads.to_vec(),
+                ..new_poll("some_id")
+            })
+        };
+        // Only the admin can have the executor called, with no more payloads than options
+        let msg = proposal(&payloads[..3]);
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        for payloads in [&payloads[..], &[]] {
+            let msg = proposal(payloads);
+            let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidPayloads { max: 3 }));
+        }
+
+        // The winner's payload is forwarded when the poll passes
+        let msg = proposal(&payloads[..3]);
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "some_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "executor".to_string(),
+                msg: payloads[1].clone(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_tick_expired() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, end) in [("first", Some(100)), ("second", Some(200)), ("open", None)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                end_time: end.map(|seconds| env.block.time.plus_seconds(seconds)),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            end_height: Some(env.block.height + 10),
+            ..new_poll("by_height")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            end_time: Some(env.block.time.plus_seconds(50)),
+            commit_reveal: true,
+            reveal_end_time: Some(env.block.time.plus_seconds(1000)),
+            ..new_poll("revealing")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Nothing has ended yet
+        let tick = |limit| ExecuteMsg::TickExpired { limit };
+        let keeper = mock_info(ADDR2, &[]);
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), tick(None)).unwrap();
+        assert!(res.events.is_empty());
+
+        // Anyone finalizes what has ended, oldest first, a poll still in its reveal is passed over
+        // but still counts towards the limit
+        env.block.time = env.block.time.plus_seconds(200);
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), tick(Some(1))).unwrap();
+        assert!(res.events.is_empty());
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), tick(Some(2))).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].ty, "poll_ended");
+        assert_eq!(res.events[0].attributes[0], attr("poll_id", "first"));
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), tick(None)).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].attributes[0], attr("poll_id", "second"));
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), tick(None)).unwrap();
+        assert!(res.events.is_empty());
+
+        // Polls ending at a height are found just the same
+        env.block.height += 10;
+        let res = execute(deps.as_mut(), env, keeper, tick(None)).unwrap();
+        assert_eq!(res.events.len(), 1);
+        assert_eq!(res.events[0].attributes[0], attr("poll_id", "by_height"));
+        for (poll_id, status) in [
+            ("first", PollStatus::Finalized),
+            ("second", PollStatus::Finalized),
+            ("by_height", PollStatus::Finalized),
+            ("open", PollStatus::Active),
+            ("revealing", PollStatus::Active),
+        ] {
+            let poll = polls().load(&deps.storage, poll_id.to_string()).unwrap();
+            assert_eq!(poll.status, status);
+        }
+    }
+
+    #[test]
+    fn test_keeper_bounty() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let admin = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: Some(coin(5, "ujuno")),
+            poll_deposit: Some(coin(3, "ujuno")),
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let update = |poll_deposit, keeper_bounty| ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty,
+        };
+        let msg = update(None, Some(coin(2, "ujuno")));
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let create = |poll_id: &str, env: &Env| {
+            ExecuteMsg::CreatePoll(NewPoll {
+                end_time: Some(env.block.time.plus_seconds(100)),
+                ..new_poll(poll_id)
+            })
+        };
+        let funded = mock_info(ADDR1, &[coin(8, "ujuno")]);
+        for poll_id in ["deposit", "closed"] {
+            let msg = create(poll_id, &env);
+            let _res = execute(deps.as_mut(), env.clone(), funded.clone(), msg).unwrap();
+        }
+        // Without a deposit the treasury pays
+        let msg = update(Some(coin(0, "ujuno")), None);
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        let msg = create("treasury", &env);
+        let info = mock_info(ADDR1, &[coin(5, "ujuno")]);
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "closed".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+
+        let bounty = CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADDR2.to_string(),
+            amount: vec![coin(2, "ujuno")],
+        });
+        let end = |poll_id: &str| ExecuteMsg::EndPoll {
+            poll_id: poll_id.to_string(),
+        };
+        let keeper = mock_info(ADDR2, &[]);
+        env.block.time = env.block.time.plus_seconds(100);
+        // The deposit pays the keeper first, what's left goes back to the creator
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), end("deposit")).unwrap();
+        let msgs: Vec<_> = res.messages.into_iter().map(|msg| msg.msg).collect();
+        assert_eq!(
+            msgs,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: ADDR1.to_string(),
+                    amount: vec![coin(1, "ujuno")],
+                }),
+                bounty.clone()
+            ]
+        );
+        let msg = ExecuteMsg::TickExpired { limit: None };
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), msg).unwrap();
+        assert_eq!(res.messages[0].msg, bounty);
+        let treasury = TREASURY.load(&deps.storage, "ujuno".to_string()).unwrap();
+        assert_eq!(treasury, Uint128::new(13));
+        let keeper_paid = KEEPER_BOUNTIES
+            .load(&deps.storage, "treasury".to_string())
+            .unwrap();
+        assert_eq!(keeper_paid, keeper.sender);
+
+        // A poll its creator closed in time earns nothing
+        let res = execute(deps.as_mut(), env, keeper, end("closed")).unwrap();
+        assert!(!res.messages.iter().any(|msg| msg.msg == bounty));
+    }
+
+    #[test]
+    fn test_tally_at_height() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // One vote per block, the second voter changing their mind
+        let start = env.block.height;
+        for (voter, vote) in [(ADDR1, "Juno"), (ADDR2, "Juno"), (ADDR2, "Osmosis")] {
+            env.block.height += 1;
+            let msg = vote_msg("some_id", vote);
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+
+        let tally_at = |height| -> Vec<u128> {
+            let msg = QueryMsg::TallyAtHeight {
+                poll_id: "some_id".to_string(),
+                height,
+            };
+            let res: TallyAtHeightResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            assert_eq!(res.height, height);
+            res.tallies.iter().map(|(_, votes)| votes.u128()).collect()
+        };
+        assert_eq!(tally_at(start + 1), vec![0, 0, 0]);
+        assert_eq!(tally_at(start + 2), vec![0, 1, 0]);
+        assert_eq!(tally_at(start + 3), vec![0, 2, 0]);
+        assert_eq!(tally_at(start + 4), vec![0, 1, 1]);
+    }
+
+    #[test]
+    fn test_vote_log() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("public"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            private_ballots: true,
+            ..new_poll("private")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let revoke = ExecuteMsg::Revoke {
+            poll_id: "public".to_string(),
+            vote: "Osmosis".to_string(),
+        };
+        for msg in [
+            vote_msg("public", "Juno"),
+            vote_msg("private", "Juno"),
+            vote_msg("public", "Osmosis"),
+            revoke,
+        ] {
+            env.block.height += 1;
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // Every change stays on record, even with the ballot gone
+        let log = |poll_id: Option<&str>, start_after| {
+            let msg = QueryMsg::VoteLog {
+                poll_id: poll_id.map(String::from),
+                start_after,
+                limit: None,
+            };
+            let res: VoteLogResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            res.entries
+        };
+        let entries = log(Some("public"), None);
+        let actions: Vec<_> = entries.iter().map(|(_, entry)| entry.action).collect();
+        assert_eq!(
+            actions,
+            vec![VoteAction::Vote, VoteAction::Revote, VoteAction::Revoke]
+        );
+        assert_eq!(entries[2].0, 3);
+        assert_eq!(entries[2].1.options, vec!["Osmosis".to_string()]);
+        assert_eq!(entries[2].1.voter, Addr::unchecked(ADDR1));
+        assert_eq!(entries[2].1.height, env.block.height);
+
+        // Private polls only log that something happened
+        let entries = log(None, Some(0));
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].1.poll_id, "private");
+        assert!(entries[0].1.options.is_empty());
+    }
+
+    #[test]
+    fn test_result_attestation() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (voter, vote) in [(ADDR2, "Osmosis"), (ADDR1, "Juno")] {
+            let msg = vote_msg("some_id", vote);
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+        }
+        let attestation = |deps: Deps| {
+            let msg = QueryMsg::ResultAttestation {
+                poll_id: "some_id".to_string(),
+            };
+            let res: ResultAttestationResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.attestation
+        };
+        assert_eq!(attestation(deps.as_ref()), None);
+
+        let msg = ExecuteMsg::ForceFinalize {
+            poll_id: "some_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        // Anyone can recompute it from the published result
+        let payload = AttestedResult {
+            poll_id: "some_id".to_string(),
+            tallies: vec![
+                ("Cosmos Hub".to_string(), Uint128::zero()),
+                ("Juno".to_string(), Uint128::one()),
+                ("Osmosis".to_string(), Uint128::one()),
+            ],
+            voters: vec![ADDR1.to_string(), ADDR2.to_string()],
+        };
+        let expected = hex::encode(Sha256::digest(to_vec(&payload).unwrap()));
+        assert_eq!(attestation(deps.as_ref()), Some(expected.clone()));
+        let event = res.events.iter().find(|event| event.ty == "poll_ended");
+        assert!(event
+            .unwrap()
+            .attributes
+            .contains(&attr("attestation", expected)));
+    }
+
+    #[test]
+    fn test_export_state() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, private_ballots) in [("private", true), ("public", false)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                private_ballots,
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        }
+        for address in [ADDR1, ADDR2] {
+            let msg = ExecuteMsg::SetViewingKey {
+                key: "secret".to_string(),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), mock_info(address, &[]), msg).unwrap();
+        }
+
+        let export = |address: &str, viewing_key: &str, start_after: Option<&str>| {
+            let msg = QueryMsg::ExportState {
+                address: address.to_string(),
+                viewing_key: viewing_key.to_string(),
+                start_after: start_after.map(String::from),
+                limit: Some(1),
+            };
+            query(deps.as_ref(), mock_env(), msg)
+        };
+        // Only an admin with their viewing key can export
+        assert!(export(ADDR2, "secret", None).is_err());
+        assert!(export(ADDR1, "wrong", None).is_err());
+
+        let res: ExportStateResponse =
+            from_binary(&export(ADDR1, "secret", None).unwrap()).unwrap();
+        assert_eq!(res.polls.len(), 1);
+        assert_eq!(res.polls[0].poll_id, "private");
+        let res: ExportStateResponse =
+            from_binary(&export(ADDR1, "secret", Some("private")).unwrap()).unwrap();
+        assert_eq!(res.polls[0].poll_id, "public");
+
+        // Ballots are paged a poll at a time, so no poll is too big to export
+        let export_ballots = |viewing_key: &str, poll_id: &str, start_after: Option<&str>| {
+            let msg = QueryMsg::ExportBallots {
+                address: ADDR1.to_string(),
+                viewing_key: viewing_key.to_string(),
+                poll_id: poll_id.to_string(),
+                start_after: start_after.map(String::from),
+                limit: Some(1),
+            };
+            query(deps.as_ref(), mock_env(), msg)
+        };
+        assert!(export_ballots("wrong", "private", None).is_err());
+        let res: ExportBallotsResponse =
+            from_binary(&export_ballots("secret", "private", None).unwrap()).unwrap();
+        assert_eq!(res.ballots[0].poll_id, "private");
+        assert_eq!(res.ballots[0].voter, voter_hash("private", ADDR2).unwrap());
+        let res: ExportBallotsResponse =
+            from_binary(&export_ballots("secret", "public", None).unwrap()).unwrap();
+        assert_eq!(res.ballots[0].voter, ADDR2);
+        assert_eq!(res.ballots[0].ballot.options, vec!["Juno".to_string()]);
+        let res: ExportBallotsResponse =
+            from_binary(&export_ballots("secret", "public", Some(ADDR2)).unwrap()).unwrap();
+        assert!(res.ballots.is_empty());
+    }
+
+    #[test]
+    fn test_import_state() {
+        let mut old = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(old.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, private_ballots) in [("private", true), ("public", false)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                private_ballots,
+                ..new_poll(poll_id)
+            });
+            let _res = execute(old.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(old.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+        }
+        let msg = ExecuteMsg::SetViewingKey {
+            key: "secret".to_string(),
+        };
+        let _res = execute(old.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = QueryMsg::ExportState {
+            address: ADDR1.to_string(),
+            viewing_key: "secret".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let export: ExportStateResponse =
+            from_binary(&query(old.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        let mut ballots = vec![];
+        for exported in &export.polls {
+            let msg = QueryMsg::ExportBallots {
+                address: ADDR1.to_string(),
+                viewing_key: "secret".to_string(),
+                poll_id: exported.poll_id.clone(),
+                start_after: None,
+                limit: None,
+            };
+            let res: ExportBallotsResponse =
+                from_binary(&query(old.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            ballots.extend(res.ballots);
+        }
+
+        // A contract instantiated without bootstrapping never imports
+        let msg = ExecuteMsg::ImportPolls { polls: vec![] };
+        let err = execute(old.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::BootstrapEnded {}));
+
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: true,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let polls = export.polls;
+        let msg = ExecuteMsg::ImportPolls {
+            polls: polls.clone(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // Polls are checked rather than taken on trust
+        let tampered = |tamper: fn(&mut Poll)| {
+            let mut polls = polls.clone();
+            tamper(&mut polls[0].poll);
+            ExecuteMsg::ImportPolls { polls }
+        };
+        for msg in [
+            tampered(|poll| poll.created_at_time = poll.created_at_time.plus_seconds(1)),
+            tampered(|poll| poll.status = PollStatus::Finalized),
+            tampered(|poll| poll.end_time = Some(poll.created_at_time)),
+            tampered(|poll| poll.quorum = Some(Decimal::percent(50))),
+            tampered(|poll| {
+                poll.status = PollStatus::Finalized;
+                poll.result = Some(PollResult {
+                    winner: None,
+                    tied: vec![],
+                    outcome: PollOutcome::Rejected,
+                });
+                poll.reward = Some(RewardPool {
+                    amount: coin(10, "ujuno"),
+                    total_weight: Uint128::zero(),
+                    claimed: Uint128::zero(),
+                    cw20: false,
+                });
+            }),
+        ] {
+            let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidImport { .. }));
+        }
+
+        // Whatever the polls hold comes along with them
+        let mut polls = polls;
+        polls[1].poll.deposit = Some(coin(10, "ujuno"));
+        let mut finalized = polls[1].clone();
+        finalized.poll_id = "finalized".to_string();
+        finalized.poll.status = PollStatus::Finalized;
+        finalized.poll.result = Some(PollResult {
+            winner: Some("Juno".to_string()),
+            tied: vec![],
+            outcome: PollOutcome::Passed,
+        });
+        finalized.poll.deposit = None;
+        finalized.poll.reward = Some(RewardPool {
+            amount: coin(10, "ujuno"),
+            total_weight: Uint128::new(1),
+            claimed: Uint128::zero(),
+            cw20: false,
+        });
+        polls.push(finalized);
+        let msg = ExecuteMsg::ImportPolls {
+            polls: polls.clone(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ImportFundsRequired { .. }));
+        let funded = mock_info(ADDR1, &[coin(20, "ujuno")]);
+        let _res = execute(deps.as_mut(), env.clone(), funded, msg).unwrap();
+        let held = HELD.load(&deps.storage, "ujuno".to_string()).unwrap();
+        assert_eq!(held, Uint128::new(20));
+        let msg = ExecuteMsg::ImportPolls { polls };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollAlreadyExists { .. }));
+        let msg = ExecuteMsg::ImportBallots {
+            ballots: ballots.clone(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A finalized poll takes no more weight than its reward is shared by
+        let mut late = ballots[1].clone();
+        late.poll_id = "finalized".to_string();
+        let msg = ExecuteMsg::ImportBallots {
+            ballots: vec![late.clone()],
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        late.voter = "addr3".to_string();
+        let msg = ExecuteMsg::ImportBallots {
+            ballots: vec![late],
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidImport { .. }));
+        assert!(!BALLOTS.has(
+            &deps.storage,
+            (Addr::unchecked("addr3"), "finalized".to_string())
+        ));
+
+        // Nor are weightless or voided ballots counted into a poll that wasn't cancelled
+        let mut weightless = ballots[1].clone();
+        weightless.voter = "addr3".to_string();
+        weightless.ballot.weight = Uint128::zero();
+        let mut voided = ballots[1].clone();
+        voided.voter = "addr3".to_string();
+        voided.ballot.void = true;
+        for ballot in [weightless, voided] {
+            let msg = ExecuteMsg::ImportBallots {
+                ballots: vec![ballot],
+            };
+            let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidImport { .. }));
+        }
+
+        // The imported ballots are counted as they were in the old contract
+        for poll_id in ["private", "public"] {
+            let msg = QueryMsg::Poll {
+                poll_id: poll_id.to_string(),
+            };
+            let res: PollResponse =
+                from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+            assert_eq!(res.tallies[1], ("Juno".to_string(), Uint128::new(1)));
+            assert_eq!(res.total_votes, Uint128::new(1));
+        }
+        assert!(BALLOTS.has(
+            &deps.storage,
+            (Addr::unchecked(ADDR2), "public".to_string())
+        ));
+        let msg = vote_msg("public", "Osmosis");
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+
+        // Ending the bootstrap is for good
+        let msg = ExecuteMsg::EndBootstrap {};
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::ImportBallots { ballots };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::BootstrapEnded {}));
+        let err = execute(deps.as_mut(), env, info, ExecuteMsg::EndBootstrap {}).unwrap_err();
+        assert!(matches!(err, ContractError::BootstrapEnded {}));
     }
 
     #[test]
-    fn test_execute_vote_invalid() {
+    fn test_initial_polls() {
+        let env = mock_env();
+        let info = mock_info("deployer", &[]);
+        let instantiate_msg = |initial_polls: Vec<NewPoll>| InstantiateMsg {
+            admin: Some(ADDR1.to_string()),
+            poll_fee: Some(coin(10, "ujuno")),
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls,
+            settings: None,
+        };
+
+        // Seed polls are held to the same rules as any other
+        let mut deps = mock_dependencies();
+        let msg = instantiate_msg(vec![NewPoll {
+            options: vec!["Juno".to_string()],
+            ..new_poll("seed")
+        }]);
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TooFewOptions {}));
+        let msg = instantiate_msg(vec![NewPoll {
+            factory: true,
+            ..new_poll("seed")
+        }]);
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::FactorySeedPoll {}));
+
+        // They are the admin's, and don't pay the poll fee
+        let msg = instantiate_msg(vec![new_poll("first"), new_poll("second")]);
+        let res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(res.events.len(), 2);
+        let poll = polls().load(&deps.storage, "second".to_string()).unwrap();
+        assert_eq!(poll.creator, Addr::unchecked(ADDR1));
+        assert_eq!(POLL_COUNT.load(&deps.storage).unwrap(), 2);
+        assert!(TREASURY_SOURCES.is_empty(&deps.storage));
+
+        // The fee applies from then on
+        let msg = ExecuteMsg::CreatePoll(new_poll("third"));
+        let err = execute(deps.as_mut(), env, mock_info(ADDR1, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFee { .. }));
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let instantiate_msg = |settings: Settings| InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: Some(settings),
+        };
+        let msg = instantiate_msg(Settings {
+            vote_fee_share: Some(Decimal::percent(150)),
+            ..Settings::default()
+        });
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVoteFeeShare {}));
+        let msg = instantiate_msg(Settings {
+            max_options: Some(2),
+            default_poll_duration: Some(3600),
+            restricted_creation: true,
+            ..Settings::default()
+        });
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Polls are held to the configured policy
+        let msg = ExecuteMsg::CreatePoll(new_poll("poll"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyOptions { max: 2 }));
+        let two_options = |poll_id: &str| {
+            ExecuteMsg::CreatePoll(NewPoll {
+                options: vec!["Juno".to_string(), "Osmosis".to_string()],
+                ..new_poll(poll_id)
+            })
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            two_options("poll"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // And take the default duration unless they end otherwise
+        let _res = execute(deps.as_mut(), env.clone(), info, two_options("poll")).unwrap();
+        let poll = polls().load(&deps.storage, "poll".to_string()).unwrap();
+        assert_eq!(poll.end_time, Some(env.block.time.plus_seconds(3600)));
+    }
+
+    #[test]
+    fn test_vote_receipts() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: Some("receipts".to_string()),
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, private_ballots) in [("public", false), ("private", true)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                private_ballots,
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // Every ballot mints the next receipt, private ones without the choice
+        let mint = |token_id: &str, poll_id: &str, choice_hash: Option<String>| {
+            let mint = Cw721ExecuteMsg::Mint {
+                token_id: token_id.to_string(),
+                owner: ADDR1.to_string(),
+                token_uri: None,
+                extension: ReceiptExtension {
+                    poll_id: poll_id.to_string(),
+                    choice_hash,
+                },
+            };
+            let execute = WasmMsg::Execute {
+                contract_addr: "receipts".to_string(),
+                msg: to_binary(&mint).unwrap(),
+                funds: vec![],
+            };
+            SubMsg::reply_on_error(execute, RECEIPT_REPLY_ID)
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            vote_msg("public", "Juno"),
+        )
+        .unwrap();
+        let choice_hash = hex::encode(Sha256::digest("Juno"));
+        assert_eq!(res.messages, vec![mint("1", "public", Some(choice_hash))]);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            vote_msg("private", "Juno"),
+        )
+        .unwrap();
+        assert_eq!(res.messages, vec![mint("2", "private", None)]);
+
+        // A receipt that can't be minted leaves the vote counted
+        let msg = Reply {
+            id: RECEIPT_REPLY_ID,
+            result: SubMsgResult::Err("token already claimed".to_string()),
+        };
+        let res = reply(deps.as_mut(), env, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "receipt_failed"));
+    }
+
+    #[test]
+    fn test_vote_delegation() {
+        let mut deps = mock_dependencies_with_balances(&[
+            (ADDR1, &[coin(100, "ujuno")]),
+            (ADDR2, &[coin(20, "ujuno")]),
+            ("addr3", &[coin(5, "ujuno")]),
+        ]);
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // addr3 -> addr2 -> addr1
+        let delegate = |delegate: &str| ExecuteMsg::DelegateVote {
+            delegate: delegate.to_string(),
+        };
+        let (addr3, other) = (mock_info("addr3", &[]), mock_info(ADDR2, &[]));
+        let _res = execute(deps.as_mut(), env.clone(), addr3, delegate(ADDR2)).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), other.clone(), delegate(ADDR1)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), delegate("addr3")).unwrap_err();
+        assert!(matches!(err, ContractError::DelegationCycle {}));
+
+        let msg = QueryMsg::Delegations {
+            address: ADDR2.to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: DelegationsResponse = from_binary(&bin).unwrap();
+        assert_eq!(res.delegate, Some(Addr::unchecked(ADDR1)));
+        assert_eq!(res.delegators, vec![Addr::unchecked("addr3")]);
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            weight_denom: Some("ujuno".to_string()),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // addr1 votes with the whole chain behind it
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(125))
+        );
+
+        // Delegators can't vote over their representative
+        let msg = vote_msg("some_id", "Osmosis");
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyRepresented { .. }));
+
+        // Once addr2 leaves and addr1 votes again, addr2 and addr3 are free to vote themselves
+        let msg = ExecuteMsg::UndelegateVote {};
+        let _res = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = vote_msg("some_id", "Osmosis");
+        let _res = execute(deps.as_mut(), env, other, msg).unwrap();
+        let poll = polls().load(&deps.storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[1],
+            ("Juno".to_string(), Uint128::new(100))
+        );
+        assert_eq!(
+            poll.tallies(&deps.storage, "some_id").unwrap()[2],
+            ("Osmosis".to_string(), Uint128::new(25))
+        );
+    }
+
+    #[test]
+    fn test_allowed_voters() {
         let mut deps = mock_dependencies();
         let env = mock_env();
-        let info = mock_info(ADDR1, &vec![]);
+        let info = mock_info(ADDR1, &[]);
         // Instantiate the contract
-        let msg = InstantiateMsg { admin: None };
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
         let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
-        // Create the vote, some_id poll is not created yet.
-        let msg = ExecuteMsg::Vote {
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            allowed_voters: Some(vec![ADDR1.to_string()]),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let other = mock_info(ADDR2, &[]);
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+
+        // Only the creator manages the list
+        let msg = ExecuteMsg::AddVoters {
             poll_id: "some_id".to_string(),
-            vote: "Juno".to_string(),
+            voters: vec![ADDR2.to_string()],
         };
-        // Unwrap to assert error
-        let _err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), other, msg).unwrap();
 
-        // Create the poll
-        let msg = ExecuteMsg::CreatePoll {
+        let msg = ExecuteMsg::RemoveVoters {
             poll_id: "some_id".to_string(),
-            question: "What's your favourite Cosmos coin?".to_string(),
-            options: vec![
-                "Cosmos Hub".to_string(),
-                "Juno".to_string(),
-                "Osmosis".to_string(),
-            ],
+            voters: vec![ADDR1.to_string()],
         };
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+    }
 
-        // Vote on a now existing poll but the option "DVPN" does not exist
-        let msg = ExecuteMsg::Vote {
+    #[test]
+    fn test_merkle_eligibility() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // A two leaf tree of addr1 and addr2
+        let leaf = |address: &str| -> [u8; 32] { Sha256::digest(address.as_bytes()).into() };
+        let (leaf1, leaf2) = (leaf(ADDR1), leaf(ADDR2));
+        let (left, right) = if leaf1 <= leaf2 {
+            (leaf1, leaf2)
+        } else {
+            (leaf2, leaf1)
+        };
+        let root = Sha256::digest([left, right].concat());
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            merkle_root: Some("not hex".to_string()),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMerkleHash {}));
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            merkle_root: Some(hex::encode(root)),
+            ..new_poll("some_id")
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let vote = |proof: Vec<String>| ExecuteMsg::Vote {
             poll_id: "some_id".to_string(),
-            vote: "DVPN".to_string(),
+            proposal_id: None,
+            vote: Some("Juno".to_string()),
+            option_index: None,
+            votes: vec![],
+            credits: vec![],
+            proof,
         };
-        let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        // Without a proof, or with the wrong one, addr1 isn't eligible
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), vote(vec![])).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+        let msg = vote(vec![hex::encode(leaf1)]);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+
+        let msg = vote(vec![hex::encode(leaf2)]);
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = vote(vec![hex::encode(leaf1)]);
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap();
+
+        // Anyone outside the tree can't borrow a proof
+        let msg = vote(vec![hex::encode(leaf2)]);
+        let err = execute(deps.as_mut(), env, mock_info("addr3", &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NotEligible {}));
+    }
+
+    #[test]
+    fn test_poll_fee() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract with a 5ujuno fee
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: Some(coin(5, "ujuno")),
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let underpaid = mock_info(ADDR2, &[coin(4, "ujuno"), coin(5, "uosmo")]);
+        let err = execute(deps.as_mut(), env.clone(), underpaid, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFee { .. }));
+        let paid = mock_info(ADDR2, &[coin(5, "ujuno")]);
+        let _res = execute(deps.as_mut(), env.clone(), paid, msg).unwrap();
+
+        // Only the admin withdraws, and only once
+        let msg = ExecuteMsg::WithdrawFees { recipient: None };
+        let other = mock_info(ADDR2, &[]);
+        let err = execute(deps.as_mut(), env.clone(), other, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR1.to_string(),
+                amount: vec![coin(5, "ujuno")],
+            })
+        );
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::NoFees {}));
+
+        // A zero fee switches it off
+        let msg = ExecuteMsg::UpdateConfig {
+            poll_fee: Some(coin(0, "ujuno")),
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("other_id"));
+        let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn test_poll_deposit() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // A 10ujuno deposit on top of a 5ujuno fee
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: Some(coin(5, "ujuno")),
+            poll_deposit: Some(coin(10, "ujuno")),
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let creator = mock_info(ADDR2, &[coin(14, "ujuno")]);
+        let msg = ExecuteMsg::CreatePoll(new_poll("quorum"));
+        let err = execute(deps.as_mut(), env.clone(), creator, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFee { .. }));
+
+        // One poll reaches its quorum, the other doesn't
+        let creator = mock_info(ADDR2, &[coin(15, "ujuno")]);
+        for (poll_id, eligible_power) in [("quorum", 1), ("no_quorum", 10)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                end_height: Some(env.block.height + 10),
+                quorum: Some(Decimal::percent(50)),
+                eligible_power: Some(eligible_power),
+                ..new_poll(poll_id)
+            });
+            let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        env.block.height += 10;
+        let end = |poll_id: &str| ExecuteMsg::EndPoll {
+            poll_id: poll_id.to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), end("quorum")).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR2.to_string(),
+                amount: vec![coin(10, "ujuno")],
+            })
+        );
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), end("no_quorum")).unwrap();
+        assert!(res.messages.is_empty());
+
+        // Both fees and the slashed deposit end up in the treasury
+        let msg = ExecuteMsg::WithdrawFees { recipient: None };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR1.to_string(),
+                amount: vec![coin(20, "ujuno")],
+            })
+        );
+    }
+
+    #[test]
+    fn test_reward_pool() {
+        let mut deps = mock_dependencies_with_balances(&[
+            (ADDR1, &[coin(30, "ujuno")]),
+            (ADDR2, &[coin(10, "ujuno")]),
+        ]);
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Instantiate the contract
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            weight_denom: Some("ujuno".to_string()),
+            reward: Some(coin(100, "uosmo")),
+            ..new_poll("some_id")
+        });
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientReward { .. }));
+        let funded = mock_info(ADDR1, &[coin(100, "uosmo")]);
+        let _res = execute(deps.as_mut(), env.clone(), funded, msg).unwrap();
+
+        let other = mock_info(ADDR2, &[]);
+        for voter in [&info, &other] {
+            let msg = vote_msg("some_id", "Juno");
+            let _res = execute(deps.as_mut(), env.clone(), voter.clone(), msg).unwrap();
+        }
+
+        let claim = ExecuteMsg::ClaimReward {
+            poll_id: "some_id".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), claim.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotFinalized {}));
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // 30 of the 40 weight gets 75 of the 100
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), claim.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR1.to_string(),
+                amount: vec![coin(75, "uosmo")],
+            })
+        );
+        let err = execute(deps.as_mut(), env.clone(), info, claim.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::RewardClaimed {}));
+        let res = execute(deps.as_mut(), env, other, claim).unwrap();
+        assert_eq!(res.attributes[3], attr("amount", "25uosmo"));
+    }
+
+    #[test]
+    fn test_cw20_receive() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        // Accept "token" for rewards and 50 token prepaid polls, next to a native fee
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: Some(coin(5, "ujuno")),
+            poll_deposit: None,
+            accepted_token: Some(AcceptedToken {
+                address: "token".to_string(),
+                poll_fee: Some(Uint128::new(50)),
+            }),
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let receive = |sender: &str, amount: u128, msg: &ReceiveMsg| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_binary(msg).unwrap(),
+            })
+        };
+        // Other tokens are turned away
+        let msg = receive(ADDR2, 50, &ReceiveMsg::PayPollFee {});
+        let other_token = mock_info("other", &[]);
+        let err = execute(deps.as_mut(), env.clone(), other_token, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::UnacceptedToken {}));
+
+        // A prepaid creation skips the native fee, once
+        let token = mock_info("token", &[]);
+        let _res = execute(deps.as_mut(), env.clone(), token.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let other = mock_info(ADDR2, &[]);
+        let _res = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("other_id"));
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientFee { .. }));
+
+        // Anyone can fund the reward pool, the single voter takes it all
+        let fund = ReceiveMsg::FundPoll {
+            poll_id: "some_id".to_string(),
+        };
+        let msg = receive(ADDR1, 40, &fund);
+        let _res = execute(deps.as_mut(), env.clone(), token.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::ClosePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap();
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "some_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), other, msg).unwrap();
+        let msg = ExecuteMsg::ClaimReward {
+            poll_id: "some_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let transfer = Cw20ExecuteMsg::Transfer {
+            recipient: ADDR1.to_string(),
+            amount: Uint128::new(40),
+        };
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&transfer).unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // The pool is closed once the poll is finalized
+        let msg = receive(ADDR1, 40, &fund);
+        let err = execute(deps.as_mut(), env.clone(), token.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+
+        // Or cancelled, the tokens would be stuck with no one to claim them
+        let msg = receive(ADDR2, 50, &ReceiveMsg::PayPollFee {});
+        let _res = execute(deps.as_mut(), env.clone(), token.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("cancelled_id"));
+        let other = mock_info(ADDR2, &[]);
+        let _res = execute(deps.as_mut(), env.clone(), other.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CancelPoll {
+            poll_id: "cancelled_id".to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), other, msg).unwrap();
+        let fund = ReceiveMsg::FundPoll {
+            poll_id: "cancelled_id".to_string(),
+        };
+        let msg = receive(ADDR1, 40, &fund);
+        let err = execute(deps.as_mut(), env.clone(), token.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::PollClosed {}));
+
+        // Every whole fee buys a creation, the change is sent back
+        let msg = receive("addr3", 120, &ReceiveMsg::PayPollFee {});
+        let res = execute(deps.as_mut(), env, token, msg).unwrap();
+        assert_eq!(res.attributes[2], attr("credits", "2"));
+        let transfer = Cw20ExecuteMsg::Transfer {
+            recipient: "addr3".to_string(),
+            amount: Uint128::new(20),
+        };
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_binary(&transfer).unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info(ADDR1, &[]), msg).unwrap();
+
+        // Migrating to the same version is a no-op
+        let res = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1], attr("from_version", CONTRACT_VERSION));
+
+        // Downgrades and other contracts are refused
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "9.0.0").unwrap();
+        let err = migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::CannotMigrateVersion { .. }));
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:other", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), env, MigrateMsg {}).unwrap_err();
+        assert!(matches!(err, ContractError::CannotMigrate { .. }));
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let other = mock_info(ADDR2, &[]);
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            other.clone(),
+            ExecuteMsg::Pause {},
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+
+        // Everything but Unpause is refused, queries still answer
+        let msg = vote_msg("some_id", "Juno");
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::ContractPaused {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Pause {},
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::ContractPaused {}));
+        let query_msg = QueryMsg::Poll {
+            poll_id: "some_id".to_string(),
+        };
+        let _bin = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            other.clone(),
+            ExecuteMsg::Unpause {},
+        );
+        assert!(matches!(err.unwrap_err(), ContractError::Unauthorized {}));
+        let _res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Unpause {}).unwrap();
+        let _res = execute(deps.as_mut(), env, other, msg).unwrap();
+    }
+
+    #[test]
+    fn test_sudo() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = vote_msg("some_id", "Juno");
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // While frozen nothing can be executed, queries still answer
+        let _res = sudo(deps.as_mut(), env.clone(), SudoMsg::FreezeContract {}).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("other_id"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Frozen {}));
+        let query_msg = QueryMsg::Poll {
+            poll_id: "some_id".to_string(),
+        };
+        let _bin = query(deps.as_ref(), env.clone(), query_msg).unwrap();
+        let _res = sudo(deps.as_mut(), env.clone(), SudoMsg::UnfreezeContract {}).unwrap();
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Governance can remove any poll along with its ballots
+        let msg = SudoMsg::RemovePoll {
+            poll_id: "some_id".to_string(),
+        };
+        let res = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("ballots_removed", "1"));
+        assert!(!polls().has(&deps.storage, "some_id".to_string()));
+        assert!(!BALLOTS.has(&deps.storage, (info.sender.clone(), "some_id".to_string())));
+
+        // And hand the contract to a new admin
+        let msg = SudoMsg::SetAdmin {
+            admin: ADDR2.to_string(),
+        };
+        let _res = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        let bin = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
+        let config: Config = from_binary(&bin).unwrap();
+        assert_eq!(config.admin, ADDR2);
+    }
+
+    #[test]
+    fn test_sweep() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: Some(coin(5, "ujuno")),
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // Creation only takes the coins it charges for
+        let paid = mock_info(ADDR1, &[coin(5, "ujuno"), coin(1, "uatom")]);
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let err = execute(deps.as_mut(), env.clone(), paid, msg.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::ExtraDenom(denom)) if denom == "uatom"
+        ));
+        let paid = mock_info(ADDR1, &[coin(5, "ujuno")]);
+        let _res = execute(deps.as_mut(), env.clone(), paid.clone(), msg).unwrap();
+        // Nothing else takes any
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            paid,
+            vote_msg("some_id", "Juno"),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::Payment(PaymentError::NonPayable {})
+        ));
+
+        // Of the 12ujuno held, 5 are fees in the treasury
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(12, "ujuno")]);
+        let msg = ExecuteMsg::Sweep {
+            denom: "ujuno".to_string(),
+            recipient: Some(ADDR2.to_string()),
+        };
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(ADDR2, &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: ADDR2.to_string(),
+                amount: vec![coin(7, "ujuno")],
+            })
+        );
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(5, "ujuno")]);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToSweep { .. }));
+
+        // A sweep that fails to go out is owed to its recipient and can't be swept again
+        let failure = Reply {
+            id: res.messages[0].id,
+            result: SubMsgResult::Err("insufficient funds".to_string()),
+        };
+        let _res = reply(deps.as_mut(), env.clone(), failure).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(12, "ujuno")]);
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToSweep { .. }));
+        let held = HELD.load(&deps.storage, "ujuno".to_string()).unwrap();
+        assert_eq!(held, Uint128::new(12));
+    }
+
+    #[test]
+    fn test_failed_payout() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[coin(5, "ujuno")]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: Some(coin(5, "ujuno")),
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // The withdrawal goes out as a submessage that replies either way
+        let info = mock_info(ADDR1, &[]);
+        let msg = ExecuteMsg::WithdrawFees { recipient: None };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(res.messages[0].id, FIRST_PAYOUT_REPLY_ID);
+        let sent = res.messages[0].msg.clone();
+
+        // A failed transfer is kept for the recipient instead of reverting the withdrawal
+        let failure = Reply {
+            id: FIRST_PAYOUT_REPLY_ID,
+            result: SubMsgResult::Err("insufficient funds".to_string()),
+        };
+        let res = reply(deps.as_mut(), env.clone(), failure.clone()).unwrap();
+        assert_eq!(res.attributes[0], attr("action", "payout_failed"));
+        let msg = QueryMsg::FailedPayouts {
+            address: ADDR1.to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg.clone()).unwrap();
+        let failed: FailedPayoutsResponse = from_binary(&bin).unwrap();
+        assert_eq!(failed.payouts[0].amount, coin(5, "ujuno"));
+
+        // Only the recipient can retry it, and only once
+        let retry = ExecuteMsg::RetryPayouts {};
+        let other = mock_info(ADDR2, &[]);
+        let err = execute(deps.as_mut(), env.clone(), other, retry.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NoFailedPayouts {}));
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), retry.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, sent);
+        // Every payout replies under its own id
+        assert_eq!(res.messages[0].id, FIRST_PAYOUT_REPLY_ID + 1);
+        let err = execute(deps.as_mut(), env.clone(), info, retry).unwrap_err();
+        assert!(matches!(err, ContractError::NoFailedPayouts {}));
+
+        // This time it goes through
+        let success = Reply {
+            id: FIRST_PAYOUT_REPLY_ID + 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let _res = reply(deps.as_mut(), env.clone(), success).unwrap();
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let failed: FailedPayoutsResponse = from_binary(&bin).unwrap();
+        assert!(failed.payouts.is_empty());
+
+        // Replies nothing is waiting for are refused
+        let err = reply(deps.as_mut(), env.clone(), failure).unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
+        // Replies find their payout whatever order they come back in
+        let payout = |recipient: &str| Payout {
+            recipient: Addr::unchecked(recipient),
+            amount: coin(1, "ujuno"),
+            cw20: false,
+        };
+        let _first = send_payout(deps.as_mut().storage, payout(ADDR1)).unwrap();
+        let second = send_payout(deps.as_mut().storage, payout(ADDR2)).unwrap();
+        let failure = Reply {
+            id: second.id,
+            result: SubMsgResult::Err("insufficient funds".to_string()),
+        };
+        let _res = reply(deps.as_mut(), env.clone(), failure).unwrap();
+        let msg = QueryMsg::FailedPayouts {
+            address: ADDR2.to_string(),
+        };
+        let failed: FailedPayoutsResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(failed.payouts, vec![payout(ADDR2)]);
+    }
+
+    #[test]
+    fn test_proposal_msgs() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info(ADDR1, &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let grant = CosmosMsg::Bank(BankMsg::Send {
+            to_address: ADDR2.to_string(),
+            amount: vec![coin(100, "ujuno")],
+        });
+        let proposal = |poll_id: &str, option: &str| NewPoll {
+            msgs: vec![(option.to_string(), vec![grant.clone()])],
+            ..new_poll(poll_id)
+        };
+        // Only the admin can have the contract execute messages, and only for real options
+        let msg = ExecuteMsg::CreatePoll(proposal("some_id", "Juno"));
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADDR2, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let msg = ExecuteMsg::CreatePoll(proposal("some_id", "Yes"));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound { .. }));
+
+        // The messages run when their option wins, and not otherwise
+        for (poll_id, vote) in [("some_id", "Juno"), ("other_id", "Osmosis")] {
+            let msg = ExecuteMsg::CreatePoll(proposal(poll_id, "Juno"));
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = vote_msg(poll_id, vote);
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+            let msg = ExecuteMsg::ClosePoll {
+                poll_id: poll_id.to_string(),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "some_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(res.messages[0].msg, grant);
+        let msg = ExecuteMsg::EndPoll {
+            poll_id: "other_id".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert!(res.messages.is_empty());
     }
-    
 }
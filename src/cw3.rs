@@ -0,0 +1,511 @@
+//! A cw3 facade over the polls, so multisig wallets and tooling can propose, vote and execute here
+//! unchanged.
+//!
+//! Every proposal is a gov style poll with Yes and No options whose messages run when Yes wins,
+//! stored under the poll id `proposal-<id>`. The cw3 Vote query has the same name as the poll one,
+//! so a proposal's ballots are only listed through ListVotes.
+
+use cosmwasm_std::{
+    from_slice, to_binary, to_vec, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo,
+    Order, Response, StdError, StdResult, Storage,
+};
+use cw_storage_plus::Bound;
+use cw_utils::Expiration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::{
+    ensure_ended, execute_create_poll, finalize_poll, poll_result, DEFAULT_LIMIT, MAX_LIMIT,
+};
+use crate::error::ContractError;
+use crate::msg::NewPoll;
+use crate::state::{
+    polls, Poll, PollOutcome, PollResult, PollStatus, Proposal, ABSTAIN_OPTION, BALLOTS,
+    POLL_VOTERS, PROPOSALS, PROPOSAL_COUNT, VETO_OPTION,
+};
+
+const YES_OPTION: &str = "Yes";
+const NO_OPTION: &str = "No";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Vote {
+    Yes,
+    No,
+    Abstain,
+    Veto,
+}
+
+impl Vote {
+    /// The poll option the vote counts towards
+    fn option(self) -> &'static str {
+        match self {
+            Vote::Yes => YES_OPTION,
+            Vote::No => NO_OPTION,
+            Vote::Abstain => ABSTAIN_OPTION,
+            Vote::Veto => VETO_OPTION,
+        }
+    }
+
+    fn from_option(option: &str) -> Option<Vote> {
+        [Vote::Yes, Vote::No, Vote::Abstain, Vote::Veto]
+            .into_iter()
+            .find(|vote| vote.option() == option)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Pending,
+    Open,
+    Rejected,
+    Passed,
+    Executed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdResponse {
+    AbsoluteCount {
+        weight: u64,
+        total_weight: u64,
+    },
+    AbsolutePercentage {
+        percentage: Decimal,
+        total_weight: u64,
+    },
+    ThresholdQuorum {
+        threshold: Decimal,
+        quorum: Decimal,
+        total_weight: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalResponse {
+    pub id: u64,
+    pub title: String,
+    pub description: String,
+    pub msgs: Vec<CosmosMsg>,
+    pub status: Status,
+    pub expires: Expiration,
+    pub threshold: ThresholdResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalListResponse {
+    pub proposals: Vec<ProposalResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoteInfo {
+    pub proposal_id: u64,
+    pub voter: String,
+    pub vote: Vote,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoteListResponse {
+    pub votes: Vec<VoteInfo>,
+}
+
+/// A proposal passes when Yes wins, No winning is a rejection like any other
+fn yes_won(result: &PollResult) -> bool {
+    result.outcome == PollOutcome::Passed && result.winner.as_deref() == Some(YES_OPTION)
+}
+
+fn load_proposal(storage: &dyn Storage, proposal_id: u64) -> Result<Proposal, ContractError> {
+    PROPOSALS
+        .may_load(storage, proposal_id)?
+        .ok_or(ContractError::ProposalNotFound {})
+}
+
+pub fn execute_propose(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    title: String,
+    description: String,
+    msgs: Vec<CosmosMsg>,
+    latest: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let (end_height, end_time) = match latest {
+        Some(Expiration::AtHeight(height)) => (Some(height), None),
+        Some(Expiration::AtTime(time)) => (None, Some(time)),
+        Some(Expiration::Never {}) | None => (None, None),
+    };
+
+    // Ids taken by polls created under the same name are skipped
+    let mut proposal_id = PROPOSAL_COUNT.may_load(deps.storage)?.unwrap_or(0);
+    let poll_id = loop {
+        proposal_id += 1;
+        let poll_id = format!("proposal-{}", proposal_id);
+        if !polls().has(deps.storage, poll_id.clone()) {
+            break poll_id;
+        }
+    };
+    PROPOSAL_COUNT.save(deps.storage, &proposal_id)?;
+    let proposal = Proposal {
+        poll_id: poll_id.clone(),
+        description,
+    };
+    PROPOSALS.save(deps.storage, proposal_id, &proposal)?;
+
+    let msgs = if msgs.is_empty() {
+        vec![]
+    } else {
+        vec![(YES_OPTION.to_string(), msgs)]
+    };
+    let new_poll = NewPoll {
+        poll_id,
+        question: title,
+        options: vec![YES_OPTION.to_string(), NO_OPTION.to_string()],
+        end_height,
+        end_time,
+        msgs,
+        gov_style: true,
+        ..Default::default()
+    };
+    let res = execute_create_poll(deps, env, info, new_poll)?;
+    Ok(res.add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+pub fn execute_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    vote: Option<String>,
+) -> Result<Response, ContractError> {
+    let proposal = load_proposal(deps.storage, proposal_id)?;
+    let vote = vote.unwrap_or_default();
+    let parsed: Vote =
+        from_slice(&to_vec(&vote)?).map_err(|_| ContractError::InvalidProposalVote { vote })?;
+
+    let selections = vec![parsed.option().to_string()];
+    let res = crate::contract::execute_vote(
+        deps,
+        env,
+        info,
+        proposal.poll_id,
+        selections,
+        None,
+        vec![],
+        vec![],
+    )?;
+    Ok(res.add_attribute("proposal_id", proposal_id.to_string()))
+}
+
+/// Finalizes a proposal whose voting is over, executing requires it to pass and closing requires
+/// it not to
+pub fn execute_end(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    execute: bool,
+) -> Result<Response, ContractError> {
+    let proposal = load_proposal(deps.storage, proposal_id)?;
+    let mut poll = match polls().may_load(deps.storage, proposal.poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    ensure_ended(&poll, &env)?;
+
+    let res = finalize_poll(deps.storage, &proposal.poll_id, &mut poll)?;
+    let passed = poll.result.as_ref().is_some_and(yes_won);
+    if execute && !passed {
+        return Err(ContractError::WrongExecuteStatus {});
+    }
+    if !execute && passed {
+        return Err(ContractError::WrongCloseStatus {});
+    }
+    polls().save(deps.storage, proposal.poll_id.clone(), &poll)?;
+
+    Ok(res
+        .add_attribute("action", if execute { "execute" } else { "close" })
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("poll_id", proposal.poll_id)
+        .add_attribute("sender", info.sender))
+}
+
+fn proposal_status(
+    storage: &dyn Storage,
+    env: &Env,
+    poll_id: &str,
+    poll: &Poll,
+) -> StdResult<Status> {
+    // A finalized proposal that passed has had its messages executed with the finalization
+    let status = match (poll.status, &poll.result) {
+        (PollStatus::Draft, _) => Status::Pending,
+        (PollStatus::Active, _) if !poll.voting_ended(&env.block) => Status::Open,
+        (PollStatus::Finalized, Some(result)) if yes_won(result) => Status::Executed,
+        (PollStatus::Finalized, _) => Status::Rejected,
+        _ if yes_won(&poll_result(storage, poll_id, poll)?.0) => Status::Passed,
+        _ => Status::Rejected,
+    };
+    Ok(status)
+}
+
+fn proposal_response(
+    storage: &dyn Storage,
+    env: &Env,
+    proposal_id: u64,
+    proposal: Proposal,
+) -> StdResult<ProposalResponse> {
+    let poll = polls().load(storage, proposal.poll_id.clone())?;
+    let status = proposal_status(storage, env, &proposal.poll_id, &poll)?;
+    let expires = match (poll.end_height, poll.end_time) {
+        (Some(height), _) => Expiration::AtHeight(height),
+        (None, Some(time)) => Expiration::AtTime(time),
+        (None, None) => Expiration::Never {},
+    };
+    let threshold = ThresholdResponse::ThresholdQuorum {
+        threshold: poll.threshold.unwrap_or_else(|| Decimal::percent(50)),
+        quorum: poll.quorum.unwrap_or_default(),
+        total_weight: poll.eligible_power.unwrap_or(0),
+    };
+    let msgs = poll
+        .msgs
+        .into_iter()
+        .find(|(option, _)| option == YES_OPTION)
+        .map(|(_, msgs)| msgs)
+        .unwrap_or_default();
+
+    Ok(ProposalResponse {
+        id: proposal_id,
+        title: poll.question,
+        description: proposal.description,
+        msgs,
+        status,
+        expires,
+        threshold,
+    })
+}
+
+pub fn query_proposal(deps: Deps, env: Env, proposal_id: u64) -> StdResult<Binary> {
+    let proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+    to_binary(&proposal_response(
+        deps.storage,
+        &env,
+        proposal_id,
+        proposal,
+    )?)
+}
+
+pub fn query_list_proposals(
+    deps: Deps,
+    env: Env,
+    start: Option<u64>,
+    limit: Option<u32>,
+    order: Order,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let (min, max) = match order {
+        Order::Ascending => (start.map(Bound::exclusive), None),
+        Order::Descending => (None, start.map(Bound::exclusive)),
+    };
+    // Proposals whose poll was deleted are left out
+    let proposals = PROPOSALS
+        .range(deps.storage, min, max, order)
+        .filter(|item| match item {
+            Ok((_, proposal)) => polls().has(deps.storage, proposal.poll_id.clone()),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let (proposal_id, proposal) = item?;
+            proposal_response(deps.storage, &env, proposal_id, proposal)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&ProposalListResponse { proposals })
+}
+
+pub fn query_list_votes(
+    deps: Deps,
+    _env: Env,
+    proposal_id: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let proposal = PROPOSALS.load(deps.storage, proposal_id)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|voter| deps.api.addr_validate(&voter))
+        .transpose()?
+        .map(Bound::exclusive);
+    let votes = POLL_VOTERS
+        .prefix(proposal.poll_id.clone())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|voter| {
+            let voter = voter?;
+            let ballot = BALLOTS.load(deps.storage, (voter.clone(), proposal.poll_id.clone()))?;
+            let vote = ballot
+                .options
+                .first()
+                .and_then(|option| Vote::from_option(option))
+                .ok_or_else(|| StdError::generic_err("Ballot without a cw3 vote"))?;
+            Ok(VoteInfo {
+                proposal_id,
+                voter: voter.into_string(),
+                vote,
+                weight: u64::try_from(ballot.weight.u128()).unwrap_or(u64::MAX),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&VoteListResponse { votes })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, from_binary, BankMsg};
+
+    use super::*;
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn cw3_vote(proposal_id: u64, vote: &str) -> ExecuteMsg {
+        // Exactly what a cw3 client sends
+        let msg = format!(
+            r#"{{"vote":{{"proposal_id":{},"vote":"{}"}}}}"#,
+            proposal_id, vote
+        );
+        from_slice(msg.as_bytes()).unwrap()
+    }
+
+    fn list_proposals(deps: Deps, env: Env) -> Vec<ProposalResponse> {
+        let msg = QueryMsg::ListProposals {
+            start_after: None,
+            limit: None,
+        };
+        let bin = query(deps, env, msg).unwrap();
+        from_binary::<ProposalListResponse>(&bin).unwrap().proposals
+    }
+
+    #[test]
+    fn test_cw3_proposals() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        let info = mock_info("admin", &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let payment: CosmosMsg = BankMsg::Send {
+            to_address: "grantee".to_string(),
+            amount: coins(100, "ujuno"),
+        }
+        .into();
+        for title in ["Fund the grantee", "Do nothing"] {
+            let msg = ExecuteMsg::Propose {
+                title: title.to_string(),
+                description: "A proposal".to_string(),
+                msgs: vec![payment.clone()],
+                latest: Some(Expiration::AtHeight(env.block.height + 10)),
+            };
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+        let proposals = list_proposals(deps.as_ref(), env.clone());
+        assert_eq!(proposals.len(), 2);
+        assert_eq!(proposals[0].id, 1);
+        assert_eq!(proposals[0].status, Status::Open);
+        assert_eq!(proposals[0].msgs, vec![payment.clone()]);
+
+        // The first passes, the second is rejected
+        for (voter, first, second) in [("voter1", "yes", "no"), ("voter2", "yes", "abstain")] {
+            for (proposal_id, vote) in [(1, first), (2, second)] {
+                let msg = cw3_vote(proposal_id, vote);
+                let _res = execute(deps.as_mut(), env.clone(), mock_info(voter, &[]), msg).unwrap();
+            }
+        }
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("voter3", &[]),
+            cw3_vote(1, "maybe"),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProposalVote { .. }));
+
+        let msg = QueryMsg::ListVotes {
+            proposal_id: 2,
+            start_after: None,
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let votes = from_binary::<VoteListResponse>(&bin).unwrap().votes;
+        let votes: Vec<Vote> = votes.into_iter().map(|info| info.vote).collect();
+        assert_eq!(votes, vec![Vote::No, Vote::Abstain]);
+
+        // Nothing can be finalized before the proposals expire
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Execute { proposal_id: 1 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PollNotExpired {}));
+        env.block.height += 10;
+        let proposals = list_proposals(deps.as_ref(), env.clone());
+        assert_eq!(proposals[0].status, Status::Passed);
+        assert_eq!(proposals[1].status, Status::Rejected);
+
+        // Passed proposals are executed rather than closed, rejected ones the other way round
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Close { proposal_id: 1 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongCloseStatus {}));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Execute { proposal_id: 2 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WrongExecuteStatus {}));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::Execute { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let _res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::Close { proposal_id: 2 },
+        )
+        .unwrap();
+
+        let msg = QueryMsg::ReverseProposals {
+            start_before: None,
+            limit: None,
+        };
+        let bin = query(deps.as_ref(), env, msg).unwrap();
+        let proposals = from_binary::<ProposalListResponse>(&bin).unwrap().proposals;
+        let statuses: Vec<Status> = proposals.iter().map(|proposal| proposal.status).collect();
+        assert_eq!(statuses, vec![Status::Rejected, Status::Executed]);
+    }
+}
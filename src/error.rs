@@ -1,6 +1,9 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, StdError, Timestamp, Uint128};
+use cw_utils::{ParseReplyError, PaymentError};
 use thiserror::Error;
 
+use crate::state::PollStatus;
+
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
@@ -9,9 +12,405 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("No stranded {denom} to sweep")]
+    NothingToSweep { denom: String },
+
+    #[error("No poll code id is configured for factory mode")]
+    NoPollCodeId {},
+
+    #[error("Polls created at instantiation can't be in factory mode")]
+    FactorySeedPoll {},
+
+    #[error("Factory mode polls can't carry a reward")]
+    InvalidFactoryPoll {},
+
+    #[error("Proposal not found")]
+    ProposalNotFound {},
+
+    #[error("Invalid cw3 vote {vote}, expected yes, no, abstain or veto")]
+    InvalidProposalVote { vote: String },
+
+    #[error("Proposal must have passed to be executed")]
+    WrongExecuteStatus {},
+
+    #[error("Cannot close a proposal that passed")]
+    WrongCloseStatus {},
+
+    #[error("IBC voting is only available for unweighted polls open to everyone")]
+    InvalidIbcVoting {},
+
+    #[error("Poll doesn't accept IBC votes")]
+    NoIbcVoting {},
+
+    #[error("Only unordered IBC channels are supported")]
+    InvalidIbcOrder {},
+
+    #[error("Invalid IBC channel version {version}")]
+    InvalidIbcVersion { version: String },
+
+    #[error("Unknown IBC channel {channel_id}")]
+    UnknownIbcChannel { channel_id: String },
+
+    #[error("Hook {addr} is already registered")]
+    HookAlreadyRegistered { addr: String },
+
+    #[error("Hook {addr} is not registered")]
+    HookNotRegistered { addr: String },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("No failed payouts to retry")]
+    NoFailedPayouts {},
+
+    #[error("Contract is frozen")]
+    Frozen {},
+
+    #[error("Contract is paused")]
+    ContractPaused {},
+
+    #[error("Importing is only possible while bootstrapping")]
+    BootstrapEnded {},
+
+    #[error("Poll {poll_id} can't be imported as it stands")]
+    InvalidImport { poll_id: String },
+
+    #[error("The imported polls hold {held:?}, which have to be sent along")]
+    ImportFundsRequired { held: Vec<Coin> },
+
+    #[error("Address is banned")]
+    Banned {},
+
+    #[error("Too many polls created, retry after {retry_after}")]
+    RateLimited { retry_after: Timestamp },
+
+    #[error("Can have at most {max} polls open at once, close or delete one first")]
+    TooManyActivePolls { max: u32 },
+
+    #[error("Can't migrate from {contract}")]
+    CannotMigrate { contract: String },
+
+    #[error("Can't migrate from version {from} to {to}")]
+    CannotMigrateVersion { from: String, to: String },
+
+    #[error("Invalid version: {0}")]
+    InvalidVersion(String),
+
     #[error("Poll Not Found")]
     PollNotFound {},
 
-    #[error("Too many poll options")]
-    TooManyOptions {},
+    #[error("Poll {poll_id} already exists")]
+    PollAlreadyExists { poll_id: String },
+
+    #[error("Namespaces can't be empty or contain '/'")]
+    InvalidNamespace {},
+
+    #[error("Namespace {namespace} is already taken")]
+    NamespaceTaken { namespace: String },
+
+    #[error("Namespace Not Found")]
+    NamespaceNotFound {},
+
+    #[error("Polls in namespace {namespace} can only be created by its owner and creators")]
+    NamespaceUnauthorized { namespace: String },
+
+    #[error("Report reasons can't be empty or longer than {max} bytes")]
+    InvalidReportReason { max: u32 },
+
+    #[error("Poll already reported by this address")]
+    AlreadyReported {},
+
+    #[error("Poll has no open reports")]
+    NoReports {},
+
+    #[error("Banned words can't be empty")]
+    InvalidBannedWord {},
+
+    #[error("Contains the banned word {word}")]
+    BannedWord { word: String },
+
+    #[error("Vote fees can't be charged in commit-reveal or IBC polls")]
+    InvalidVoteFee {},
+
+    #[error("Voting in this poll costs {fee}")]
+    VoteFeeRequired { fee: Coin },
+
+    #[error("The treasury's share of vote fees can't be more than 1")]
+    InvalidVoteFeeShare {},
+
+    #[error("Survey Not Found")]
+    SurveyNotFound {},
+
+    #[error("Survey {survey_id} already exists")]
+    SurveyAlreadyExists { survey_id: String },
+
+    #[error("A survey needs an id and between 1 and {max} questions")]
+    InvalidSurvey { max: u32 },
+
+    #[error("Survey is closed")]
+    SurveyClosed {},
+
+    #[error("Expected an answer to each of the {expected} questions")]
+    AnswerCountMismatch { expected: u32 },
+
+    #[error("Raffle Not Found")]
+    RaffleNotFound {},
+
+    #[error("Poll {poll_id} already has a raffle")]
+    RaffleExists { poll_id: String },
+
+    #[error("A raffle needs a prize, at least one winner and a poll whose voters are public")]
+    InvalidRaffle {},
+
+    #[error("Raffle has already been drawn")]
+    RaffleAlreadyDrawn {},
+
+    #[error("No Nois proxy is configured")]
+    NoNoisProxy {},
+
+    #[error("Recurring poll Not Found")]
+    RecurringPollNotFound {},
+
+    #[error("Recurring poll {template_id} already exists")]
+    RecurringPollExists { template_id: String },
+
+    #[error("Recurring polls need a period, and take their ids and voting window from it")]
+    InvalidRecurringPoll {},
+
+    #[error("The next instance can't be spawned before {next}")]
+    InstanceNotDue { next: Timestamp },
+
+    #[error("A poll can have at most {max} options")]
+    TooManyOptions { max: u32 },
+
+    #[error("Question can be at most {max} characters")]
+    QuestionTooLong { max: u32 },
+
+    #[error("Option labels can be at most {max} characters")]
+    OptionTooLong { max: u32 },
+
+    #[error("Limits must allow a question and at least 2 options")]
+    InvalidLimits {},
+
+    #[error("A poll needs at least 2 options")]
+    TooFewOptions {},
+
+    #[error("Option labels can't be blank")]
+    EmptyOption {},
+
+    #[error("Option {option} is listed more than once")]
+    DuplicateOption { option: String },
+
+    #[error("Description or metadata URI is too long")]
+    MetadataTooLong {},
+
+    #[error("At most {max} distinct tags of 1 to {max_length} characters are allowed")]
+    InvalidTags { max: usize, max_length: usize },
+
+    #[error("Option {option} not found in poll")]
+    OptionNotFound { option: String },
+
+    #[error("Poll has no option at index {index}")]
+    OptionIndexOutOfRange { index: u32 },
+
+    #[error("Tally for option {option} would go below zero")]
+    TallyUnderflow { option: String },
+
+    #[error("Tally for option {option} would overflow")]
+    TallyOverflow { option: String },
+
+    #[error("Voter has no voting power in this poll")]
+    NoVotingPower {},
+
+    #[error("Voting power does not fit in a tally")]
+    VotingPowerTooLarge {},
+
+    #[error("A poll can only use one source of voting power")]
+    MultipleWeightSources {},
+
+    #[error("NFT weighting needs an NFT collection")]
+    MissingNftCollection {},
+
+    #[error("A cw4 snapshot needs a cw4 group")]
+    MissingCw4Group {},
+
+    #[error("Only polls weighted by a balance, stake or NFTs can use a snapshot")]
+    InvalidSnapshot {},
+
+    #[error("Ballot already exists")]
+    BallotAlreadyExists {},
+
+    #[error("Ballot Not Found")]
+    BallotNotFound {},
+
+    #[error("A vote must select at least one option")]
+    NoSelection {},
+
+    #[error("At most {max} options may be selected")]
+    TooManySelections { max: u32 },
+
+    #[error("Option {option} was selected more than once")]
+    DuplicateSelection { option: String },
+
+    #[error("Poll {poll_id} was voted in more than once")]
+    DuplicatePollVote { poll_id: String },
+
+    #[error("Max selections must be between 1 and the number of options")]
+    InvalidMaxSelections {},
+
+    #[error("An executor needs between 1 and {max} payloads, one per option")]
+    InvalidPayloads { max: u32 },
+
+    #[error("Credits must be given for each selection of a quadratic poll, and only then")]
+    CreditsMismatch {},
+
+    #[error("Spent credits exceed the budget of {budget}")]
+    CreditBudgetExceeded { budget: Uint128 },
+
+    #[error("Gov style polls can't be ranked choice or take write-ins")]
+    InvalidGovStyle {},
+
+    #[error("Quorum and thresholds must be between 0 and 1")]
+    InvalidThreshold {},
+
+    #[error("A quorum needs a non-zero eligible_power")]
+    MissingEligiblePower {},
+
+    #[error("Commit-reveal polls need a single choice plurality tally and both deadlines")]
+    InvalidCommitReveal {},
+
+    #[error("Votes on this poll must be committed and revealed")]
+    CommitRevealRequired {},
+
+    #[error("Poll does not use commit-reveal voting")]
+    NotCommitReveal {},
+
+    #[error("Commitment must be a hex encoded sha256 hash")]
+    InvalidCommitment {},
+
+    #[error("Votes can't be revealed until the poll has expired")]
+    RevealNotStarted {},
+
+    #[error("No committed vote found")]
+    CommitmentNotFound {},
+
+    #[error("Vote and salt don't match the commitment")]
+    CommitmentMismatch {},
+
+    #[error("Not eligible to vote in this poll")]
+    NotEligible {},
+
+    #[error("Poll has no allowlist")]
+    NoAllowlist {},
+
+    #[error("Merkle roots and proofs must be hex encoded sha256 hashes")]
+    InvalidMerkleHash {},
+
+    #[error("Delegation would create a cycle")]
+    DelegationCycle {},
+
+    #[error("No vote delegation found")]
+    NoDelegation {},
+
+    #[error("Already represented in this poll by {representative}")]
+    AlreadyRepresented { representative: String },
+
+    #[error("Ballot was cast for a different option")]
+    VoteMismatch {},
+
+    #[error("Poll is closed")]
+    PollClosed {},
+
+    #[error("Poll can't be changed once votes have been cast")]
+    PollHasVotes {},
+
+    #[error("Poll end must be in the future")]
+    InvalidExpiration {},
+
+    #[error("No admin handover is pending")]
+    NoPendingAdmin {},
+
+    #[error("The admin handover has expired")]
+    AdminClaimExpired {},
+
+    #[error("Poll is not active")]
+    PollNotActive {},
+
+    #[error("Public keys have to be 33 byte compressed secp256k1 keys")]
+    InvalidPubkey {},
+
+    #[error("Signature doesn't match the vote and public key")]
+    InvalidSignature {},
+
+    #[error("Wrong nonce, expected {expected}")]
+    InvalidNonce { expected: u64 },
+
+    #[error("Signed vote has expired")]
+    SignatureExpired {},
+
+    #[error("Already voted, ballots in this poll can't be changed")]
+    AlreadyVoted {},
+
+    #[error("Poll has not started yet")]
+    PollNotStarted {},
+
+    #[error("A poll has to start before it ends")]
+    InvalidStartTime {},
+
+    #[error("The revote deadline has to fall while the poll is open")]
+    InvalidRevoteDeadline {},
+
+    #[error("Ballots in this poll can no longer be changed")]
+    RevoteClosed {},
+
+    #[error("Poll has not expired yet")]
+    PollNotExpired {},
+
+    #[error("Creating a poll costs {fee}")]
+    InsufficientFee { fee: Coin },
+
+    #[error("Creating a poll needs a deposit of {deposit}")]
+    InsufficientDeposit { deposit: Coin },
+
+    #[error("The reward of {reward} was not sent")]
+    InsufficientReward { reward: Coin },
+
+    #[error("Poll has no reward pool")]
+    NoReward {},
+
+    #[error("Reward pool is in a different token")]
+    RewardDenomMismatch {},
+
+    #[error("Token is not accepted")]
+    UnacceptedToken {},
+
+    #[error("Poll fees can't be paid in cw20 tokens")]
+    NoCw20Fee {},
+
+    #[error("Reward already claimed")]
+    RewardClaimed {},
+
+    #[error("Poll is not finalized")]
+    PollNotFinalized {},
+
+    #[error("Poll still owes its voters unclaimed rewards or an undrawn raffle")]
+    PollUnsettled {},
+
+    #[error("No fees to withdraw")]
+    NoFees {},
+
+    #[error("The treasury only holds {available}")]
+    InsufficientTreasury { available: Coin },
+
+    #[error("Poll has already ended as {status:?}")]
+    PollAlreadyEnded { status: PollStatus },
+
+    #[error("Cannot move poll from {from:?} to {to:?}")]
+    InvalidTransition { from: PollStatus, to: PollStatus },
 }
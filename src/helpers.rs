@@ -1,11 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{
-    to_binary, Addr, CosmosMsg, CustomQuery, Querier, QuerierWrapper, StdResult, WasmMsg, WasmQuery,
-};
+use cosmwasm_std::{to_binary, to_vec, Addr, CosmosMsg, StdResult, WasmMsg};
+use sha2::{Digest, Sha256};
 
-use crate::msg::{CustomResponse, ExecuteMsg, QueryMsg};
+use crate::msg::ExecuteMsg;
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this. Rename it to your contract name.
@@ -46,3 +45,17 @@ impl CwTemplateContract {
     }
     */
 }
+
+/// The hash to commit for a vote in a commit-reveal poll: the hex encoded sha256 of the JSON array
+/// `[poll_id, voter, vote, salt]`. Including the voter stops anyone from copying a commitment.
+pub fn vote_commitment(poll_id: &str, voter: &str, vote: &str, salt: &str) -> StdResult<String> {
+    let preimage = to_vec(&(poll_id, voter, vote, salt))?;
+    Ok(hex::encode(Sha256::digest(preimage)))
+}
+
+/// The storage key of a voter's ballot in a private poll: the hex encoded sha256 of the JSON
+/// array `[poll_id, voter]`, so raw storage doesn't list addresses next to their votes.
+pub fn voter_hash(poll_id: &str, voter: &str) -> StdResult<String> {
+    let preimage = to_vec(&(poll_id, voter))?;
+    Ok(hex::encode(Sha256::digest(preimage)))
+}
@@ -0,0 +1,309 @@
+//! IBC entry points letting a counterpart contract on another chain relay votes into polls here.
+//!
+//! Remote voters can't be validated as local addresses, so each one votes as
+//! `<local channel id>/<remote address>`. The `/` never appears in a local address, which keeps
+//! remote voters apart from local ones and from the same address relayed over another channel.
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_slice, to_binary, Addr, Binary, DepsMut, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcOrder, IbcPacketAckMsg,
+    IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, Never,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::{cast_vote, ensure_open};
+use crate::error::ContractError;
+use crate::state::{polls, CONFIG, IBC_CHANNELS};
+
+pub const IBC_VERSION: &str = "cw-starter-votes-1";
+
+/// Packets the counterpart contract sends
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketMsg {
+    /// The same ballot as a local Vote, cast on behalf of `voter` on the sending chain
+    Vote {
+        poll_id: String,
+        voter: String,
+        vote: Option<String>,
+        #[serde(default)]
+        votes: Vec<String>,
+        #[serde(default)]
+        credits: Vec<u64>,
+    },
+}
+
+/// Acknowledgement written for every packet, in the envelope ICS-20 uses
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketAck {
+    Result(Binary),
+    Error(String),
+}
+
+/// The address a remote voter votes as
+pub fn remote_voter(channel_id: &str, voter: &str) -> Addr {
+    Addr::unchecked(format!("{}/{}", channel_id, voter))
+}
+
+fn validate_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    // Votes are independent of each other, there's nothing to gain from ordering
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::InvalidIbcOrder {});
+    }
+    for version in [Some(channel.version.as_str()), counterparty_version]
+        .into_iter()
+        .flatten()
+    {
+        if version != IBC_VERSION {
+            return Err(ContractError::InvalidIbcVersion {
+                version: version.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel = msg.channel();
+    validate_channel(channel, msg.counterparty_version())?;
+    IBC_CHANNELS.save(
+        deps.storage,
+        channel.endpoint.channel_id.clone(),
+        &channel.counterparty_endpoint,
+    )?;
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    // Ballots already relayed stay counted
+    let channel = msg.channel();
+    IBC_CHANNELS.remove(deps.storage, channel.endpoint.channel_id.clone());
+
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", &channel.endpoint.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    // Failing here would leave the packet stuck, so errors go back in the acknowledgement
+    let channel_id = msg.packet.dest.channel_id;
+    match receive_packet(deps, env, &channel_id, &msg.packet.data) {
+        Ok(res) => Ok(res),
+        Err(err) => Ok(IbcReceiveResponse::new()
+            .set_ack(to_binary(&PacketAck::Error(err.to_string())).unwrap())
+            .add_attribute("action", "ibc_packet_receive")
+            .add_attribute("error", err.to_string())),
+    }
+}
+
+fn receive_packet(
+    deps: DepsMut,
+    env: Env,
+    channel_id: &str,
+    data: &Binary,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.frozen {
+        return Err(ContractError::Frozen {});
+    }
+    if config.paused {
+        return Err(ContractError::ContractPaused {});
+    }
+    if !IBC_CHANNELS.has(deps.storage, channel_id.to_string()) {
+        return Err(ContractError::UnknownIbcChannel {
+            channel_id: channel_id.to_string(),
+        });
+    }
+
+    match from_slice(data)? {
+        PacketMsg::Vote {
+            poll_id,
+            voter,
+            vote,
+            votes,
+            credits,
+        } => {
+            let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+                Some(poll) => poll,
+                None => return Err(ContractError::PollNotFound {}),
+            };
+            ensure_open(&poll, &env)?;
+            if !poll.ibc_voting {
+                return Err(ContractError::NoIbcVoting {});
+            }
+            let voter = remote_voter(channel_id, &voter);
+            let selections = vote.into_iter().chain(votes).collect();
+            let res = cast_vote(
+                deps, &env, poll_id, poll, voter, selections, credits, "ibc_vote",
+            )?;
+
+            Ok(IbcReceiveResponse::new()
+                .set_ack(to_binary(&PacketAck::Result(Binary::default()))?)
+                .add_submessages(res.messages)
+                .add_attributes(res.attributes)
+                .add_events(res.events))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    // Nothing is ever sent from this side
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_ack"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_timeout"))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_ibc_channel_close_init, mock_ibc_channel_connect_ack,
+        mock_ibc_channel_open_init, mock_ibc_channel_open_try, mock_ibc_packet_recv, mock_info,
+    };
+    use cosmwasm_std::{from_binary, Uint128};
+
+    use super::*;
+    use crate::contract::{execute, instantiate};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, NewPoll};
+    use crate::state::BALLOTS;
+
+    const CHANNEL: &str = "channel-0";
+
+    fn vote_packet(poll_id: &str, voter: &str) -> PacketMsg {
+        PacketMsg::Vote {
+            poll_id: poll_id.to_string(),
+            voter: voter.to_string(),
+            vote: Some("Juno".to_string()),
+            votes: vec![],
+            credits: vec![],
+        }
+    }
+
+    fn receive(deps: DepsMut, channel_id: &str, packet: &PacketMsg) -> PacketAck {
+        let msg = mock_ibc_packet_recv(channel_id, packet).unwrap();
+        let res = ibc_packet_receive(deps, mock_env(), msg).unwrap();
+        from_binary(&res.acknowledgement).unwrap()
+    }
+
+    #[test]
+    fn test_ibc_voting() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for (poll_id, ibc_voting) in [("local", false), ("remote", true)] {
+            let msg = ExecuteMsg::CreatePoll(NewPoll {
+                poll_id: poll_id.to_string(),
+                question: "What's your favourite Cosmos coin?".to_string(),
+                options: vec!["Juno".to_string(), "Osmosis".to_string()],
+                ibc_voting,
+                ..Default::default()
+            });
+            let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // Only unordered channels speaking our version are accepted
+        let msg = mock_ibc_channel_open_init(CHANNEL, IbcOrder::Ordered, IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), env.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcOrder {}));
+        let msg = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Unordered, "ics20-1");
+        let err = ibc_channel_open(deps.as_mut(), env.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcVersion { .. }));
+        let msg = mock_ibc_channel_open_init(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+        ibc_channel_open(deps.as_mut(), env.clone(), msg).unwrap();
+
+        // Packets only count once the channel is connected
+        let ack = receive(deps.as_mut(), CHANNEL, &vote_packet("remote", "juno1voter"));
+        assert!(matches!(ack, PacketAck::Error(_)));
+        let msg = mock_ibc_channel_connect_ack(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+        let _res = ibc_channel_connect(deps.as_mut(), env.clone(), msg).unwrap();
+        let ack = receive(deps.as_mut(), CHANNEL, &vote_packet("remote", "juno1voter"));
+        assert_eq!(ack, PacketAck::Result(Binary::default()));
+
+        // The same remote address over another channel is another voter
+        let msg = mock_ibc_channel_connect_ack("channel-1", IbcOrder::Unordered, IBC_VERSION);
+        let _res = ibc_channel_connect(deps.as_mut(), env.clone(), msg).unwrap();
+        let ack = receive(
+            deps.as_mut(),
+            "channel-1",
+            &vote_packet("remote", "juno1voter"),
+        );
+        assert_eq!(ack, PacketAck::Result(Binary::default()));
+        let poll = polls().load(&deps.storage, "remote".to_string()).unwrap();
+        assert_eq!(
+            poll.tallies(&deps.storage, "remote").unwrap()[0],
+            ("Juno".to_string(), Uint128::new(2))
+        );
+        let key = (remote_voter(CHANNEL, "juno1voter"), "remote".to_string());
+        assert!(BALLOTS.has(&deps.storage, key));
+
+        // Polls without IBC voting turn relayed votes away
+        let ack = receive(deps.as_mut(), CHANNEL, &vote_packet("local", "juno1voter"));
+        assert_eq!(
+            ack,
+            PacketAck::Error(ContractError::NoIbcVoting {}.to_string())
+        );
+
+        // Closing a channel stops its votes
+        let msg = mock_ibc_channel_close_init(CHANNEL, IbcOrder::Unordered, IBC_VERSION);
+        let _res = ibc_channel_close(deps.as_mut(), env, msg).unwrap();
+        let ack = receive(deps.as_mut(), CHANNEL, &vote_packet("remote", "juno1other"));
+        assert!(matches!(ack, PacketAck::Error(_)));
+    }
+}
@@ -0,0 +1,724 @@
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdResult,
+    Uint128,
+};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Bound, Map, SnapshotMap, Strategy};
+use serde::{Deserialize, Serialize};
+
+use crate::external::{
+    Cw20BalanceResponse, Cw20QueryMsg, Cw4MemberResponse, Cw4QueryMsg, Cw721QueryMsg,
+    Cw721TokensResponse,
+};
+use crate::msg::{
+    ExecuteMsg, HookExecuteMsg, InstantiateMsg, NewPoll, PollContractResponse, PollHookMsg,
+    PollResponse, QueryMsg,
+};
+use crate::ContractError;
+
+const ADMIN: &str = "admin";
+const HOLDER: &str = "holder";
+const OUTSIDER: &str = "outsider";
+
+fn poll_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply);
+    Box::new(contract)
+}
+
+// A stand-in cw20 that only knows its initial balances, enough to answer Balance queries
+mod mock_cw20 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct InstantiateMsg {
+        pub balances: Vec<(String, Uint128)>,
+    }
+
+    const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        for (address, amount) in msg.balances {
+            let address = deps.api.addr_validate(&address)?;
+            BALANCES.save(deps.storage, &address, &amount)?;
+        }
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
+        match msg {
+            Cw20QueryMsg::Balance { address } => {
+                let address = deps.api.addr_validate(&address)?;
+                let balance = BALANCES
+                    .may_load(deps.storage, &address)?
+                    .unwrap_or_default();
+                to_binary(&Cw20BalanceResponse { balance })
+            }
+        }
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+// A stand-in cw721 that mints its tokens at instantiation, enough to answer Tokens queries
+mod mock_cw721 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct InstantiateMsg {
+        pub tokens: Vec<(String, String)>,
+    }
+
+    const OWNERS: Map<&str, Addr> = Map::new("owners");
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        for (token_id, owner) in msg.tokens {
+            let owner = deps.api.addr_validate(&owner)?;
+            OWNERS.save(deps.storage, &token_id, &owner)?;
+        }
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: Cw721QueryMsg) -> StdResult<Binary> {
+        match msg {
+            Cw721QueryMsg::Tokens {
+                owner,
+                start_after,
+                limit,
+            } => {
+                let start = start_after.as_deref().map(Bound::exclusive);
+                let tokens = OWNERS
+                    .range(deps.storage, start, None, Order::Ascending)
+                    .filter(|item| item.as_ref().map_or(true, |(_, holder)| *holder == owner))
+                    .take(limit.unwrap_or(10) as usize)
+                    .map(|item| item.map(|(token_id, _)| token_id))
+                    .collect::<StdResult<Vec<_>>>()?;
+                to_binary(&Cw721TokensResponse { tokens })
+            }
+        }
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+// A stand-in cw4-group keeping a member weight changelog, enough to answer Member queries
+mod mock_cw4 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct InstantiateMsg {
+        pub members: Vec<(String, u64)>,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct ExecuteMsg {
+        pub members: Vec<(String, u64)>,
+    }
+
+    const MEMBERS: SnapshotMap<&Addr, u64> = SnapshotMap::new(
+        "members",
+        "members__checkpoints",
+        "members__changelog",
+        Strategy::EveryBlock,
+    );
+
+    fn save_members(deps: DepsMut, env: Env, members: Vec<(String, u64)>) -> StdResult<Response> {
+        for (address, weight) in members {
+            let address = deps.api.addr_validate(&address)?;
+            MEMBERS.save(deps.storage, &address, &weight, env.block.height)?;
+        }
+        Ok(Response::new())
+    }
+
+    pub fn instantiate(
+        deps: DepsMut,
+        env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        save_members(deps, env, msg.members)
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        env: Env,
+        _info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        save_members(deps, env, msg.members)
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: Cw4QueryMsg) -> StdResult<Binary> {
+        match msg {
+            Cw4QueryMsg::Member { addr, at_height } => {
+                let addr = deps.api.addr_validate(&addr)?;
+                let weight = match at_height {
+                    Some(height) => MEMBERS.may_load_at_height(deps.storage, &addr, height)?,
+                    None => MEMBERS.may_load(deps.storage, &addr)?,
+                };
+                to_binary(&Cw4MemberResponse { weight })
+            }
+        }
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+// Counts the poll hooks it receives, or rejects all of them
+mod mock_hook {
+    use super::*;
+    use cosmwasm_std::StdError;
+    use cw_storage_plus::Item;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct InstantiateMsg {
+        pub fail: bool,
+    }
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum QueryMsg {
+        Received {},
+    }
+
+    const FAIL: Item<bool> = Item::new("fail");
+    const RECEIVED: Item<Vec<PollHookMsg>> = Item::new("received");
+
+    pub fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: InstantiateMsg,
+    ) -> StdResult<Response> {
+        FAIL.save(deps.storage, &msg.fail)?;
+        RECEIVED.save(deps.storage, &vec![])?;
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: HookExecuteMsg,
+    ) -> StdResult<Response> {
+        if FAIL.load(deps.storage)? {
+            return Err(StdError::generic_err("hook failed"));
+        }
+        let HookExecuteMsg::PollHook(hook) = msg;
+        RECEIVED.update(deps.storage, |mut received| -> StdResult<_> {
+            received.push(hook);
+            Ok(received)
+        })?;
+        Ok(Response::new())
+    }
+
+    pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+        match msg {
+            QueryMsg::Received {} => to_binary(&RECEIVED.load(deps.storage)?),
+        }
+    }
+
+    pub fn contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(execute, instantiate, query))
+    }
+}
+
+fn instantiate_poll_contract(app: &mut App) -> Addr {
+    let code_id = app.store_code(poll_contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(ADMIN),
+        &InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        },
+        &[],
+        "polls",
+        None,
+    )
+    .unwrap()
+}
+
+fn new_poll(poll_id: &str) -> NewPoll {
+    NewPoll {
+        poll_id: poll_id.to_string(),
+        question: "What's your favourite Cosmos coin?".to_string(),
+        options: vec![
+            "Cosmos Hub".to_string(),
+            "Juno".to_string(),
+            "Osmosis".to_string(),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn cw20_weighted_poll() {
+    let mut app = App::default();
+    let polls = instantiate_poll_contract(&mut app);
+
+    let cw20_id = app.store_code(mock_cw20::contract());
+    let token = app
+        .instantiate_contract(
+            cw20_id,
+            Addr::unchecked(ADMIN),
+            &mock_cw20::InstantiateMsg {
+                balances: vec![(HOLDER.to_string(), Uint128::new(250))],
+            },
+            &[],
+            "token",
+            None,
+        )
+        .unwrap();
+
+    let msg = ExecuteMsg::CreatePoll(NewPoll {
+        cw20_address: Some(token.to_string()),
+        ..new_poll("some_id")
+    });
+    app.execute_contract(Addr::unchecked(ADMIN), polls.clone(), &msg, &[])
+        .unwrap();
+
+    // The holder's vote counts for their token balance
+    let vote = ExecuteMsg::Vote {
+        poll_id: "some_id".to_string(),
+        proposal_id: None,
+        vote: Some("Juno".to_string()),
+        option_index: None,
+        votes: vec![],
+        credits: vec![],
+        proof: vec![],
+    };
+    app.execute_contract(Addr::unchecked(HOLDER), polls.clone(), &vote, &[])
+        .unwrap();
+    let res: PollResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &polls,
+            &QueryMsg::Poll {
+                poll_id: "some_id".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.tallies[1], ("Juno".to_string(), Uint128::new(250)));
+
+    // Addresses without tokens are turned away
+    let err = app
+        .execute_contract(Addr::unchecked(OUTSIDER), polls, &vote, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::NoVotingPower {}
+    ));
+}
+
+#[test]
+fn nft_weighted_poll() {
+    let mut app = App::default();
+    let polls = instantiate_poll_contract(&mut app);
+
+    // More tokens than fit in one page of the Tokens query
+    let cw721_id = app.store_code(mock_cw721::contract());
+    let collection = app
+        .instantiate_contract(
+            cw721_id,
+            Addr::unchecked(ADMIN),
+            &mock_cw721::InstantiateMsg {
+                tokens: (0..45)
+                    .map(|id| {
+                        let owner = if id % 3 == 0 { ADMIN } else { HOLDER };
+                        (format!("punk{:02}", id), owner.to_string())
+                    })
+                    .collect(),
+            },
+            &[],
+            "collection",
+            None,
+        )
+        .unwrap();
+
+    let msg = ExecuteMsg::CreatePoll(NewPoll {
+        nft_collection: Some(collection.to_string()),
+        nft_weighted: true,
+        ..new_poll("some_id")
+    });
+    app.execute_contract(Addr::unchecked(ADMIN), polls.clone(), &msg, &[])
+        .unwrap();
+
+    // The holder's vote counts once for each of their 30 NFTs
+    let vote = ExecuteMsg::Vote {
+        poll_id: "some_id".to_string(),
+        proposal_id: None,
+        vote: Some("Juno".to_string()),
+        option_index: None,
+        votes: vec![],
+        credits: vec![],
+        proof: vec![],
+    };
+    app.execute_contract(Addr::unchecked(HOLDER), polls.clone(), &vote, &[])
+        .unwrap();
+    let res: PollResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &polls,
+            &QueryMsg::Poll {
+                poll_id: "some_id".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.tallies[1], ("Juno".to_string(), Uint128::new(30)));
+
+    // Addresses without NFTs are turned away
+    let err = app
+        .execute_contract(Addr::unchecked(OUTSIDER), polls, &vote, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::NoVotingPower {}
+    ));
+}
+
+#[test]
+fn cw4_snapshot_poll() {
+    let mut app = App::default();
+    let polls = instantiate_poll_contract(&mut app);
+
+    let cw4_id = app.store_code(mock_cw4::contract());
+    let group = app
+        .instantiate_contract(
+            cw4_id,
+            Addr::unchecked(ADMIN),
+            &mock_cw4::InstantiateMsg {
+                members: vec![(HOLDER.to_string(), 7)],
+            },
+            &[],
+            "group",
+            None,
+        )
+        .unwrap();
+    app.update_block(|block| block.height += 1);
+
+    for (poll_id, cw4_snapshot) in [("live", false), ("snapshot", true)] {
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            cw4_group: Some(group.to_string()),
+            cw4_snapshot,
+            ..new_poll(poll_id)
+        });
+        app.execute_contract(Addr::unchecked(ADMIN), polls.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    // Membership changes after the polls were created
+    app.update_block(|block| block.height += 1);
+    let update = mock_cw4::ExecuteMsg {
+        members: vec![(HOLDER.to_string(), 20), (OUTSIDER.to_string(), 5)],
+    };
+    app.execute_contract(Addr::unchecked(ADMIN), group, &update, &[])
+        .unwrap();
+    app.update_block(|block| block.height += 1);
+
+    let vote = |poll_id: &str| ExecuteMsg::Vote {
+        poll_id: poll_id.to_string(),
+        proposal_id: None,
+        vote: Some("Juno".to_string()),
+        option_index: None,
+        votes: vec![],
+        credits: vec![],
+        proof: vec![],
+    };
+    for poll_id in ["live", "snapshot"] {
+        app.execute_contract(Addr::unchecked(HOLDER), polls.clone(), &vote(poll_id), &[])
+            .unwrap();
+    }
+    let juno_votes = |app: &App, poll_id: &str| {
+        let res: PollResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &polls,
+                &QueryMsg::Poll {
+                    poll_id: poll_id.to_string(),
+                },
+            )
+            .unwrap();
+        res.tallies[1].1
+    };
+    // The snapshot poll keeps the weight the holder had when it was created
+    assert_eq!(juno_votes(&app, "live"), Uint128::new(20));
+    assert_eq!(juno_votes(&app, "snapshot"), Uint128::new(7));
+
+    // Members who joined later only count in the live poll
+    app.execute_contract(Addr::unchecked(OUTSIDER), polls.clone(), &vote("live"), &[])
+        .unwrap();
+    let err = app
+        .execute_contract(Addr::unchecked(OUTSIDER), polls, &vote("snapshot"), &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::NoVotingPower {}
+    ));
+}
+
+#[test]
+fn poll_hooks() {
+    let mut app = App::default();
+    let polls = instantiate_poll_contract(&mut app);
+
+    let hook_id = app.store_code(mock_hook::contract());
+    let mut hooks = vec![];
+    for fail in [false, true] {
+        let hook = app
+            .instantiate_contract(
+                hook_id,
+                Addr::unchecked(ADMIN),
+                &mock_hook::InstantiateMsg { fail },
+                &[],
+                "hook",
+                None,
+            )
+            .unwrap();
+        let msg = ExecuteMsg::AddHook {
+            addr: hook.to_string(),
+        };
+        app.execute_contract(Addr::unchecked(ADMIN), polls.clone(), &msg, &[])
+            .unwrap();
+        hooks.push(hook);
+    }
+    // Only the admin registers hooks
+    let msg = ExecuteMsg::AddHook {
+        addr: OUTSIDER.to_string(),
+    };
+    let err = app
+        .execute_contract(Addr::unchecked(OUTSIDER), polls.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+
+    // The failing hook doesn't stop the poll from going ahead
+    let msg = ExecuteMsg::CreatePoll(new_poll("some_id"));
+    app.execute_contract(Addr::unchecked(ADMIN), polls.clone(), &msg, &[])
+        .unwrap();
+    let vote = ExecuteMsg::Vote {
+        poll_id: "some_id".to_string(),
+        proposal_id: None,
+        vote: Some("Juno".to_string()),
+        option_index: None,
+        votes: vec![],
+        credits: vec![],
+        proof: vec![],
+    };
+    app.execute_contract(Addr::unchecked(HOLDER), polls.clone(), &vote, &[])
+        .unwrap();
+    let msg = ExecuteMsg::ClosePoll {
+        poll_id: "some_id".to_string(),
+    };
+    app.execute_contract(Addr::unchecked(ADMIN), polls.clone(), &msg, &[])
+        .unwrap();
+    let msg = ExecuteMsg::EndPoll {
+        poll_id: "some_id".to_string(),
+    };
+    app.execute_contract(Addr::unchecked(ADMIN), polls, &msg, &[])
+        .unwrap();
+
+    let received: Vec<PollHookMsg> = app
+        .wrap()
+        .query_wasm_smart(&hooks[0], &mock_hook::QueryMsg::Received {})
+        .unwrap();
+    assert_eq!(
+        received[..2],
+        [
+            PollHookMsg::PollCreated {
+                poll_id: "some_id".to_string(),
+                creator: Addr::unchecked(ADMIN),
+            },
+            PollHookMsg::Voted {
+                poll_id: "some_id".to_string(),
+                voter: Addr::unchecked(HOLDER),
+            },
+        ]
+    );
+    assert!(matches!(
+        &received[2],
+        PollHookMsg::PollFinalized { result, .. } if result.winner.as_deref() == Some("Juno")
+    ));
+}
+
+#[test]
+fn factory_poll() {
+    let mut app = App::default();
+    let code_id = app.store_code(poll_contract());
+    let msg = InstantiateMsg {
+        admin: None,
+        poll_fee: None,
+        poll_deposit: None,
+        accepted_token: None,
+        poll_code_id: Some(code_id),
+        limits: None,
+        bootstrap: false,
+        initial_polls: vec![],
+        settings: None,
+    };
+    let factory = app
+        .instantiate_contract(code_id, Addr::unchecked(ADMIN), &msg, &[], "polls", None)
+        .unwrap();
+
+    let msg = ExecuteMsg::CreatePoll(NewPoll {
+        factory: true,
+        ..new_poll("some_id")
+    });
+    let err = app
+        .execute_contract(Addr::unchecked(OUTSIDER), factory.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::Unauthorized {}
+    ));
+    app.execute_contract(Addr::unchecked(ADMIN), factory.clone(), &msg, &[])
+        .unwrap();
+    let err = app
+        .execute_contract(Addr::unchecked(ADMIN), factory.clone(), &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::PollAlreadyExists { .. }
+    ));
+
+    // The poll lives in its own contract, voted on there
+    let res: PollContractResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &factory,
+            &QueryMsg::PollContract {
+                poll_id: "some_id".to_string(),
+            },
+        )
+        .unwrap();
+    let child = res.address.unwrap();
+    let vote = ExecuteMsg::Vote {
+        poll_id: "some_id".to_string(),
+        proposal_id: None,
+        vote: Some("Juno".to_string()),
+        option_index: None,
+        votes: vec![],
+        credits: vec![],
+        proof: vec![],
+    };
+    app.execute_contract(Addr::unchecked(HOLDER), child.clone(), &vote, &[])
+        .unwrap();
+
+    let query = QueryMsg::Poll {
+        poll_id: "some_id".to_string(),
+    };
+    let res: PollResponse = app.wrap().query_wasm_smart(&factory, &query).unwrap();
+    assert!(res.poll.is_none());
+    let res: PollResponse = app.wrap().query_wasm_smart(&child, &query).unwrap();
+    assert_eq!(res.tallies[1], ("Juno".to_string(), Uint128::new(1)));
+}
+
+#[test]
+fn vote_many() {
+    let mut app = App::default();
+    let polls = instantiate_poll_contract(&mut app);
+    for poll_id in ["first", "second"] {
+        let msg = ExecuteMsg::CreatePoll(new_poll(poll_id));
+        app.execute_contract(Addr::unchecked(ADMIN), polls.clone(), &msg, &[])
+            .unwrap();
+    }
+    let vote_many = |second: &str| ExecuteMsg::VoteMany {
+        votes: vec![
+            ("first".to_string(), "Juno".to_string()),
+            ("second".to_string(), second.to_string()),
+        ],
+    };
+    let unique_voters = |app: &App, poll_id: &str| {
+        let query = QueryMsg::Poll {
+            poll_id: poll_id.to_string(),
+        };
+        let res: PollResponse = app.wrap().query_wasm_smart(&polls, &query).unwrap();
+        res.unique_voters
+    };
+
+    // One bad vote undoes the others
+    let err = app
+        .execute_contract(
+            Addr::unchecked(HOLDER),
+            polls.clone(),
+            &vote_many("Doge"),
+            &[],
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::OptionNotFound { .. }
+    ));
+    assert_eq!(unique_voters(&app, "first"), 0);
+
+    app.execute_contract(
+        Addr::unchecked(HOLDER),
+        polls.clone(),
+        &vote_many("Osmosis"),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(unique_voters(&app, "first"), 1);
+    assert_eq!(unique_voters(&app, "second"), 1);
+
+    let msg = ExecuteMsg::VoteMany {
+        votes: vec![
+            ("first".to_string(), "Juno".to_string()),
+            ("first".to_string(), "Osmosis".to_string()),
+        ],
+    };
+    let err = app
+        .execute_contract(Addr::unchecked(HOLDER), polls, &msg, &[])
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast().unwrap(),
+        ContractError::DuplicatePollVote { .. }
+    ));
+}
@@ -1,7 +1,15 @@
 pub mod contract;
+pub mod cw3;
 mod error;
+pub mod external;
 pub mod helpers;
+pub mod ibc;
+#[cfg(test)]
+mod integration_tests;
+mod migrations;
 pub mod msg;
+pub mod raffle;
 pub mod state;
+pub mod survey;
 
 pub use crate::error::ContractError;
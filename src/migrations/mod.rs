@@ -0,0 +1,6 @@
+//! Storage migrations between released versions, run in order by the `migrate` entry point.
+//!
+//! Each step reads the layout written by one version and rewrites it in the layout of the next,
+//! keeping its own copies of the old types so later changes to `state` don't affect it.
+
+pub mod v0_1_to_v0_2;
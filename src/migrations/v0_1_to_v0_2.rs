@@ -0,0 +1,289 @@
+//! 0.1 stored single choice, one vote per address polls. 0.2 adds everything since, most of it
+//! switched off for the polls being carried over.
+
+use cosmwasm_std::{Addr, BlockInfo, Decimal, Empty, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::REPUTATION_PERIOD;
+use crate::state::{
+    polls, reputations, Ballot, Config, Poll, PollLimits, PollOption, PollStatus, Reputation,
+    Stats, TallyType, ACTIVE_POLLS, BALLOTS, CONFIG, CREATION_NUMBERS, CREATION_ORDER, POLL_COUNT,
+    POLL_VOTERS, STATS, TALLIES, VOTERS, VOTE_COUNTS,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct ConfigV0_1 {
+    admin: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct PollV0_1 {
+    creator: Addr,
+    question: String,
+    options: Vec<(String, u64)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+struct BallotV0_1 {
+    option: String,
+}
+
+const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+const POLLS_V0_1: Map<String, PollV0_1> = Map::new("polls");
+const BALLOTS_V0_1: Map<(Addr, String), BallotV0_1> = Map::new("ballots");
+
+pub fn migrate(storage: &mut dyn Storage, block: &BlockInfo) -> StdResult<()> {
+    let config = CONFIG_V0_1.load(storage)?;
+    CONFIG.save(
+        storage,
+        &Config {
+            admin: config.admin,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            cw20_poll_fee: None,
+            frozen: false,
+            poll_code_id: None,
+            limits: PollLimits::default(),
+            restricted_creation: false,
+            default_poll_duration: None,
+            paused: false,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: None,
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: Decimal::zero(),
+            keeper_bounty: None,
+            bootstrap: false,
+        },
+    )?;
+
+    let old_polls = POLLS_V0_1
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    POLL_COUNT.save(storage, &(old_polls.len() as u64))?;
+    let mut stats = Stats {
+        polls_created: old_polls.len() as u64,
+        ..Stats::default()
+    };
+    // Creation order wasn't kept, the polls are numbered by id instead
+    for (number, (poll_id, poll)) in (1..).zip(old_polls) {
+        CREATION_ORDER.save(storage, number, &poll_id)?;
+        CREATION_NUMBERS.save(storage, poll_id.clone(), &number)?;
+        ACTIVE_POLLS.update(storage, poll.creator.clone(), |count| -> StdResult<_> {
+            Ok(count.unwrap_or(0) + 1)
+        })?;
+        update_reputation(storage, &poll.creator, block, |reputation| {
+            reputation.polls_created += 1
+        })?;
+        // Tallies moved out of the poll
+        for (position, (_, votes)) in (0u32..).zip(&poll.options) {
+            TALLIES.save(
+                storage,
+                (&poll_id, position),
+                &Uint128::from(*votes),
+                block.height,
+            )?;
+        }
+        // The indexed map would try to read the 0.1 poll back to unindex it
+        POLLS_V0_1.remove(storage, poll_id.clone());
+        polls().save(storage, poll_id, &poll_v0_2(poll, block))?;
+    }
+
+    // 0.1 ballots were worth one vote, and had no index to find them by poll. When they were cast
+    // wasn't recorded and stays unknown, the migrating block would pass every legacy voter off as
+    // a late one
+    let ballots = BALLOTS_V0_1
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for ((voter, poll_id), ballot) in ballots {
+        POLL_VOTERS.save(storage, (poll_id.clone(), voter.clone()), &Empty {})?;
+        VOTE_COUNTS.update(storage, &poll_id, |counts| -> StdResult<_> {
+            let mut counts = counts.unwrap_or_default();
+            counts.total_votes += Uint128::one();
+            counts.unique_voters += 1;
+            Ok(counts)
+        })?;
+        stats.votes_cast += 1;
+        update_reputation(storage, &voter, block, |reputation| {
+            reputation.polls_voted += 1
+        })?;
+        if !VOTERS.has(storage, voter.clone()) {
+            VOTERS.save(storage, voter.clone(), &Empty {})?;
+            stats.unique_voters += 1;
+        }
+        BALLOTS.save(
+            storage,
+            (voter, poll_id),
+            &Ballot {
+                options: vec![ballot.option],
+                weight: Uint128::one(),
+                credits: vec![],
+                represented: vec![],
+                reward_claimed: false,
+                cast_at_height: None,
+                cast_at_time: None,
+                void: false,
+            },
+        )?;
+    }
+    STATS.save(storage, &stats)
+}
+
+/// Nothing was dated in 0.1, so everything carried over counts as activity of the migrating block
+fn update_reputation(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    block: &BlockInfo,
+    update: impl FnOnce(&mut Reputation),
+) -> StdResult<()> {
+    let mut reputation = reputations()
+        .may_load(storage, address.clone())?
+        .unwrap_or_default();
+    reputation.streak = 1;
+    reputation.last_active_period = block.time.seconds() / REPUTATION_PERIOD;
+    update(&mut reputation);
+    reputations().save(storage, address.clone(), &reputation)
+}
+
+/// 0.1 didn't record when polls were created, the block migrating them stands in for it
+fn poll_v0_2(poll: PollV0_1, block: &BlockInfo) -> Poll {
+    Poll {
+        creator: poll.creator,
+        created_at_height: block.height,
+        created_at_time: block.time,
+        question: poll.question,
+        description: String::new(),
+        metadata_uri: None,
+        tags: vec![],
+        options: poll
+            .options
+            .into_iter()
+            .map(|(label, _)| PollOption {
+                label,
+                description: String::new(),
+                image_uri: None,
+            })
+            .collect(),
+        start_time: None,
+        revote_deadline: None,
+        end_height: None,
+        end_time: None,
+        weight_denom: None,
+        cw20_address: None,
+        staking_weighted: false,
+        nft_collection: None,
+        nft_weighted: false,
+        cw4_group: None,
+        cw4_snapshot_height: None,
+        snapshot_height: None,
+        ibc_voting: false,
+        msgs: vec![],
+        executor: None,
+        payload_per_option: vec![],
+        max_selections: 1,
+        tally_type: TallyType::Plurality,
+        credit_budget: None,
+        gov_style: false,
+        veto_threshold: None,
+        quorum: None,
+        threshold: None,
+        eligible_power: None,
+        commit_reveal: false,
+        reveal_end_height: None,
+        reveal_end_time: None,
+        private_ballots: false,
+        allow_write_ins: false,
+        locked_ballots: false,
+        allowlist: false,
+        merkle_root: None,
+        min_reputation: None,
+        vote_fee: None,
+        deposit: None,
+        reward: None,
+        status: PollStatus::Active,
+        result: None,
+        hidden: false,
+        invalid_reason: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies();
+        let storage = deps.as_mut().storage;
+        CONFIG_V0_1
+            .save(
+                storage,
+                &ConfigV0_1 {
+                    admin: Addr::unchecked("admin"),
+                },
+            )
+            .unwrap();
+        let poll = PollV0_1 {
+            creator: Addr::unchecked("creator"),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec![("Juno".to_string(), 1), ("Osmosis".to_string(), 0)],
+        };
+        POLLS_V0_1
+            .save(storage, "some_id".to_string(), &poll)
+            .unwrap();
+        let ballot = BallotV0_1 {
+            option: "Juno".to_string(),
+        };
+        let key = (Addr::unchecked("voter"), "some_id".to_string());
+        BALLOTS_V0_1.save(storage, key.clone(), &ballot).unwrap();
+
+        let env = mock_env();
+        migrate(storage, &env.block).unwrap();
+
+        assert_eq!(CONFIG.load(storage).unwrap().admin, "admin");
+        let migrated = polls().load(storage, "some_id".to_string()).unwrap();
+        assert_eq!(
+            migrated.tallies(storage, "some_id").unwrap(),
+            vec![
+                ("Juno".to_string(), Uint128::one()),
+                ("Osmosis".to_string(), Uint128::zero())
+            ]
+        );
+        assert_eq!(migrated.status, PollStatus::Active);
+        assert_eq!(migrated.created_at_height, env.block.height);
+        let ballot = BALLOTS.load(storage, key).unwrap();
+        assert_eq!(ballot.options, vec!["Juno".to_string()]);
+        assert_eq!(ballot.weight, Uint128::one());
+        assert_eq!(ballot.cast_at_height, None);
+        assert_eq!(ballot.cast_at_time, None);
+        let reputation = reputations()
+            .load(storage, Addr::unchecked("voter"))
+            .unwrap();
+        assert_eq!(reputation.polls_voted, 1);
+        assert!(POLL_VOTERS.has(storage, ("some_id".to_string(), Addr::unchecked("voter"))));
+        let counts = VOTE_COUNTS.load(storage, "some_id").unwrap();
+        assert_eq!(
+            (counts.total_votes, counts.unique_voters),
+            (Uint128::one(), 1)
+        );
+        let by_creator = polls()
+            .idx
+            .creator
+            .prefix(poll.creator)
+            .keys(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(by_creator, vec!["some_id".to_string()]);
+        let stats = STATS.load(storage).unwrap();
+        assert_eq!(
+            (stats.polls_created, stats.votes_cast, stats.unique_voters),
+            (1, 1, 1)
+        );
+    }
+}
@@ -1,47 +1,848 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use crate::state::{Poll, Ballot};
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, Decimal, Order, Timestamp, Uint128};
+use cw_utils::Expiration;
+use crate::external::{Cw20ReceiveMsg, NoisCallback};
+use crate::state::{
+    Poll, Ballot, Payout, PollLimits, PollResult, PollStatus, RateLimit, Role, Survey,
+    SurveyQuestion, TallyRound, TallyType, TreasurySource, VoteLogEntry,
+};
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
     pub admin: Option<String>,
+    /// Fee for creating a poll, none when unset
+    pub poll_fee: Option<Coin>,
+    /// Deposit for creating a poll, refunded when it ends with quorum
+    pub poll_deposit: Option<Coin>,
+    pub accepted_token: Option<AcceptedToken>,
+    /// Code id of this contract, enabling factory mode polls
+    pub poll_code_id: Option<u64>,
+    /// Question and option bounds, the defaults when unset
+    pub limits: Option<PollLimits>,
+    /// Starts the contract accepting ImportPolls and ImportBallots until EndBootstrap
+    #[serde(default)]
+    pub bootstrap: bool,
+    /// Polls created along with the contract, e.g. examples for a demo. They are created by the
+    /// admin and validated like any other poll, but free of the poll fee and deposit.
+    #[serde(default)]
+    pub initial_polls: Vec<NewPoll>,
+    pub settings: Option<Settings>,
 }
 
+/// Contract wide policy, every field left unset keeps the default
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct Settings {
+    /// Replaces the `max_options` of the limits
+    pub max_options: Option<u32>,
+    /// In seconds, how long polls created without an end height or time accept votes
+    pub default_poll_duration: Option<u64>,
+    /// Share of a finalized poll's vote fees kept in the treasury, none by default
+    pub vote_fee_share: Option<Decimal>,
+    /// Paid to whoever ends an expired poll
+    pub keeper_bounty: Option<Coin>,
+    /// Only addresses holding the PollCreator role or above may create polls
+    #[serde(default)]
+    pub restricted_creation: bool,
+}
+
+/// A cw20 token the contract accepts through Receive
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub struct AcceptedToken {
+    pub address: String,
+    /// Price of a prepaid poll creation, PayPollFee is disabled when None
+    pub poll_fee: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct OptionDetails {
+    #[serde(default)]
+    pub description: String,
+    pub image_uri: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct NewPoll {
+    /// Left empty, the poll gets the next free number as its id
+    #[serde(default)]
+    pub poll_id: String,
+    pub question: String,
+    /// Longer text shown alongside the question
+    #[serde(default)]
+    pub description: String,
+    /// Where more about the poll can be found, e.g. an IPFS document
+    pub metadata_uri: Option<String>,
+    /// Labels the poll can be looked up by with PollsByTag
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub options: Vec<String>,
+    /// What to show with some of the options, (option, details)
+    #[serde(default)]
+    pub option_details: Vec<(String, OptionDetails)>,
+    /// Voting opens once block time reaches this timestamp, so a poll can be announced early
+    pub start_time: Option<Timestamp>,
+    /// Voting closes once the chain reaches this height
+    pub end_height: Option<u64>,
+    /// Voting closes once block time reaches this timestamp
+    pub end_time: Option<Timestamp>,
+    /// Ballots can only be changed or revoked before this timestamp, new votes count until the end
+    #[serde(default)]
+    pub revote_deadline: Option<Timestamp>,
+    /// Weight votes by the voter's balance of this native denom instead of one vote per address
+    pub weight_denom: Option<String>,
+    /// Gate and weight votes by the voter's balance of this cw20 token
+    pub cw20_address: Option<String>,
+    /// Weight votes by the voter's total bonded stake across all validators
+    #[serde(default)]
+    pub staking_weighted: bool,
+    /// Only owners of an NFT from this cw721 collection may vote
+    pub nft_collection: Option<String>,
+    /// Weight votes by the number of NFTs held from the collection
+    #[serde(default)]
+    pub nft_weighted: bool,
+    /// Weight votes by the voter's membership weight in this cw4 group
+    pub cw4_group: Option<String>,
+    /// Read group weights as of the creation block, so later membership changes don't count
+    #[serde(default)]
+    pub cw4_snapshot: bool,
+    /// Only count power checkpointed before the poll starts, capped at the voter's current power
+    #[serde(default)]
+    pub snapshot: bool,
+    /// Accept votes relayed over IBC, only for unweighted polls open to everyone
+    #[serde(default)]
+    pub ibc_voting: bool,
+    /// Messages the contract executes when the poll passes with the option winning, (option,
+    /// messages). Only the admin can create polls with messages.
+    #[serde(default)]
+    pub msgs: Vec<(String, Vec<CosmosMsg>)>,
+    /// Contract the winning option's payload is sent to when the poll passes. Only the admin can
+    /// create polls with an executor.
+    pub executor: Option<String>,
+    /// ExecuteMsg for the executor per option, in the order of the options. Options past the
+    /// end, like write-ins, send nothing when they win.
+    #[serde(default)]
+    pub payload_per_option: Vec<Binary>,
+    /// How many options a voter may select, defaults to 1 or to every option when ranking
+    pub max_selections: Option<u32>,
+    #[serde(default)]
+    pub tally_type: TallyType,
+    /// Credits each voter may spend in a quadratic poll, defaults to their voting power
+    pub credit_budget: Option<u64>,
+    /// Add Abstain and NoWithVeto options, vetoing the poll when NoWithVeto passes the threshold
+    #[serde(default)]
+    pub gov_style: bool,
+    /// Veto threshold for gov style polls, defaults to 33.4% like the Cosmos SDK
+    pub veto_threshold: Option<Decimal>,
+    /// Share of `eligible_power` that has to vote, otherwise the poll ends as QuorumNotMet
+    pub quorum: Option<Decimal>,
+    /// Share of the counted votes the winner needs, otherwise the poll is Rejected
+    pub threshold: Option<Decimal>,
+    /// Total voting power of the electorate, required with a quorum
+    pub eligible_power: Option<u64>,
+    /// Vote with CommitVote while the poll is open and RevealVote once it has expired
+    #[serde(default)]
+    pub commit_reveal: bool,
+    /// End of the reveal window of a commit-reveal poll, at least one is required
+    pub reveal_end_height: Option<u64>,
+    pub reveal_end_time: Option<Timestamp>,
+    /// Hide who voted for what, only the totals stay public
+    #[serde(default)]
+    pub private_ballots: bool,
+    /// Voting for an option the poll doesn't have adds it, within the configured option limits
+    #[serde(default)]
+    pub allow_write_ins: bool,
+    /// Ballots can't be changed or revoked once cast
+    #[serde(default)]
+    pub locked_ballots: bool,
+    /// Restrict voting to these addresses, anyone may vote when None
+    pub allowed_voters: Option<Vec<String>>,
+    /// Restrict voting to a merkle tree of addresses. Leaves are the sha256 of the address and
+    /// each step hashes the sorted pair of nodes, all hex encoded
+    pub merkle_root: Option<String>,
+    /// Only addresses that have voted in at least this many other polls may vote
+    pub min_reputation: Option<u64>,
+    /// Charged to each voter with their first ballot, split between the creator and the
+    /// treasury once the poll is finalized
+    #[serde(default)]
+    pub vote_fee: Option<Coin>,
+    /// Part of the sent funds to share between the voters once the poll is finalized
+    pub reward: Option<Coin>,
+    /// Create the poll as a draft, it must be opened before accepting votes
+    #[serde(default)]
+    pub draft: bool,
+    /// Run the poll in a contract of its own, instantiated from the configured code id. Only the
+    /// admin can create these, and they can't carry a reward.
+    #[serde(default)]
+    pub factory: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+// Messages are parsed once per call, boxing CreatePoll would only make them clumsier to build
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
-    CreatePoll {
+    CreatePoll(NewPoll),
+    Vote {
+        #[serde(default)]
         poll_id: String,
-        question: String,
-        options: Vec<String>,
+        /// Set instead of `poll_id` by cw3 clients, whose `vote` is then yes, no, abstain or veto
+        #[serde(default)]
+        proposal_id: Option<u64>,
+        /// A single choice, the usual way to vote
+        vote: Option<String>,
+        /// A single choice by its position in the poll's options, counted along with `vote` and
+        /// `votes`. Spares clients matching labels exactly.
+        #[serde(default)]
+        option_index: Option<u32>,
+        /// Several choices at once, for polls allowing more than one selection
+        #[serde(default)]
+        votes: Vec<String>,
+        /// Credits spent on each selection, in the same order, for quadratic polls
+        #[serde(default)]
+        credits: Vec<u64>,
+        /// Merkle proof of eligibility for polls with a merkle root
+        #[serde(default)]
+        proof: Vec<String>,
     },
-    Vote {
+    /// A single choice vote in each of several polls, (poll_id, vote). Either every vote counts
+    /// or none does.
+    VoteMany {
+        votes: Vec<(String, String)>,
+    },
+    /// A single choice vote submitted on the voter's behalf, signed with their secp256k1 key
+    /// over the sha256 of the JSON encoded `SignedVote`. The vote counts for the address the
+    /// compressed public key belongs to.
+    VoteBySig {
         poll_id: String,
         vote: String,
+        voter_pubkey: Binary,
+        signature: Binary,
+        /// Has to be the voter's next nonce, see the SigNonce query
+        nonce: u64,
+        /// The signed vote is turned away from this time on
+        expires: Timestamp,
     },
     Delete {
         poll_id: String,
     },
+    /// Flags a poll for the moderators, once per address until its reports are resolved
+    ReportPoll {
+        poll_id: String,
+        reason: String,
+    },
+    /// Clears a poll's reports, whether it is hidden stays as it is. Moderator only.
+    ResolveReport {
+        poll_id: String,
+    },
+    /// Leaves a poll out of AllPolls, it can still be queried and voted in. Moderator only.
+    HidePoll {
+        poll_id: String,
+    },
+    UnhidePoll {
+        poll_id: String,
+    },
+    /// Deletes a finalized poll and its ballots, keeping a summary of the result, once its rewards
+    /// are claimed and its raffle drawn. Creator or admin only.
+    ArchivePoll {
+        poll_id: String,
+    },
+    /// Deletes up to `limit` finalized polls along with their ballots, oldest ids first, to
+    /// reclaim storage. Polls with unclaimed rewards or an undrawn raffle are left alone. Admin
+    /// only.
+    PruneExpired {
+        limit: Option<u32>,
+        /// Keep a summary of each poll, as ArchivePoll does
+        #[serde(default)]
+        keep_summaries: bool,
+    },
+    /// Creates a survey of several questions, each with its own options
+    CreateSurvey {
+        survey_id: String,
+        title: String,
+        questions: Vec<SurveyQuestion>,
+        end_time: Option<Timestamp>,
+    },
+    /// Answers every question of a survey at once, one option per question in order. Submitting
+    /// again replaces the earlier answers.
+    SubmitSurveyResponse {
+        survey_id: String,
+        answers: Vec<String>,
+    },
+    /// Puts the sent funds up as a prize for `winners` voters of the poll, drawn at random once
+    /// it's finalized. Poll creator only.
+    CreateRaffle {
+        poll_id: String,
+        winners: u32,
+    },
+    /// Requests the randomness for a finalized poll's raffle from the Nois proxy, forwarding
+    /// the sent funds as the proxy's fee
+    DrawRaffle {
+        poll_id: String,
+    },
+    /// Delivers requested randomness, only accepted from the configured Nois proxy
+    NoisReceive {
+        callback: NoisCallback,
+    },
+    /// Creates a poll with the question, options and settings of an existing one, owned by the
+    /// caller with no votes. It runs for as long as the original did, counted from now, and
+    /// creating it costs what creating any poll does.
+    ClonePoll {
+        poll_id: String,
+        new_poll_id: String,
+    },
+    /// Schedules `poll` to be created once every `period` seconds, starting now. Instances are
+    /// named `<template_id>-<instance>` and stay open until the end of their period.
+    CreateRecurringPoll {
+        template_id: String,
+        poll: NewPoll,
+        period: u64,
+    },
+    /// Creates the next instance of a recurring poll once its period has come, skipping periods
+    /// nobody spawned. Anyone may call it, paying what creating a poll costs, and the instance
+    /// belongs to the recurring poll's creator.
+    SpawnNextInstance {
+        template_id: String,
+    },
+    /// Stops spawning a recurring poll, its instances stay. Creator or admin only.
+    StopRecurringPoll {
+        template_id: String,
+    },
     Revoke {
         poll_id: String,
         vote: String,
     },
+    /// Adds addresses to a poll's allowlist
+    AddVoters {
+        poll_id: String,
+        voters: Vec<String>,
+    },
+    /// Removes addresses from a poll's allowlist, ballots they already cast still count
+    RemoveVoters {
+        poll_id: String,
+        voters: Vec<String>,
+    },
+    /// Claims the poll ids starting with `<namespace>/` for the sender. Fails if someone else
+    /// already created a poll under it.
+    RegisterNamespace {
+        namespace: String,
+    },
+    /// Lets addresses other than the owner create polls in a namespace
+    AddNamespaceCreators {
+        namespace: String,
+        creators: Vec<String>,
+    },
+    RemoveNamespaceCreators {
+        namespace: String,
+        creators: Vec<String>,
+    },
+    /// Commits to a vote in a commit-reveal poll, see `helpers::vote_commitment` for the hash
+    CommitVote {
+        poll_id: String,
+        hash: String,
+        /// Merkle proof of eligibility for polls with a merkle root
+        #[serde(default)]
+        proof: Vec<String>,
+    },
+    /// Reveals a committed vote once the poll has expired, counting it
+    RevealVote {
+        poll_id: String,
+        vote: String,
+        salt: String,
+    },
+    /// Lets the delegate vote with the sender's power in every poll the sender doesn't vote in
+    DelegateVote {
+        delegate: String,
+    },
+    UndelegateVote {},
+    /// Records the current voting power of `address`, the sender by default, for the weight
+    /// source of a poll, so it counts in snapshot polls on that source starting afterwards.
+    /// Anyone may refresh anyone's checkpoint.
+    CheckpointPower {
+        poll_id: String,
+        address: Option<String>,
+    },
+    /// Pays the sender their share of a finalized poll's reward pool
+    ClaimReward {
+        poll_id: String,
+    },
+    /// Pays the sender their share of the vote fees of the polls they created
+    ClaimCreatorFees {},
+    /// Returns the sender's own ballot as a VoteResponse in the response data, the only way to
+    /// read a ballot of a private poll without a viewing key
+    MyVote {
+        poll_id: String,
+    },
+    /// Sets the key the sender's ballots can only be queried with, an empty key makes them
+    /// public again
+    SetViewingKey {
+        key: String,
+    },
+    OpenPoll {
+        poll_id: String,
+    },
+    ClosePoll {
+        poll_id: String,
+    },
+    FinalizePoll {
+        poll_id: String,
+    },
+    EndPoll {
+        poll_id: String,
+    },
+    /// Ends up to `limit` active polls whose end time has passed, oldest first. Commit-reveal polls
+    /// still in their reveal are passed over but count towards the limit. Anyone may call it, so
+    /// an automation agent like CronCat can keep results coming in on time.
+    TickExpired {
+        limit: Option<u32>,
+    },
+    /// Calls off an active poll before it ends, creator only. Its ballots are voided, vote fees
+    /// are refunded and the deposit, reward and raffle prize go back to the creator.
+    CancelPoll {
+        poll_id: String,
+    },
+    /// Finalizes an active or closed poll right away, whether or not voting is over. Admin only.
+    ForceFinalize {
+        poll_id: String,
+    },
+    /// Ends a poll without a winner, nothing is executed, vote fees are refunded and the deposit,
+    /// reward and raffle prize go back to the creator. Admin only.
+    InvalidatePoll {
+        poll_id: String,
+        reason: String,
+    },
+    /// Lets the creator fix the question or options until the first vote, fields left as None
+    /// keep their current value
+    UpdatePoll {
+        poll_id: String,
+        question: Option<String>,
+        options: Option<Vec<String>>,
+    },
+    /// Fields left as None keep their current value
+    UpdateConfig {
+        /// A zero amount removes the fee
+        poll_fee: Option<Coin>,
+        /// A zero amount removes the deposit, polls already created keep theirs
+        poll_deposit: Option<Coin>,
+        accepted_token: Option<AcceptedToken>,
+        poll_code_id: Option<u64>,
+        /// Only apply to polls created or updated afterwards
+        limits: Option<PollLimits>,
+        restricted_creation: Option<bool>,
+        /// Zero max polls or a zero window removes the limit
+        rate_limit: Option<RateLimit>,
+        /// Zero removes the cap
+        max_active_polls: Option<u32>,
+        /// An empty address removes the proxy, raffles can't be drawn without one
+        #[serde(default)]
+        nois_proxy: Option<String>,
+        /// An empty address stops minting vote receipts
+        #[serde(default)]
+        receipt_nft: Option<String>,
+        /// Zero stops hiding reported polls automatically
+        #[serde(default)]
+        report_threshold: Option<u32>,
+        /// Share of vote fees kept in the treasury, at most 1
+        #[serde(default)]
+        vote_fee_share: Option<Decimal>,
+        /// A zero bounty stops paying keepers
+        #[serde(default)]
+        keeper_bounty: Option<Coin>,
+    },
+    /// Stops every execute message but Unpause until unpaused, admin only. Queries keep working.
+    Pause {},
+    Unpause {},
+    /// Gives an address a role, replacing the one it held. Admin only.
+    GrantRole {
+        address: String,
+        role: Role,
+    },
+    RevokeRole {
+        address: String,
+    },
+    /// Bars an address from creating polls and voting, admin only. Its existing polls and
+    /// ballots stay.
+    BanAddress {
+        address: String,
+    },
+    UnbanAddress {
+        address: String,
+    },
+    /// Refuses polls whose question or options contain any of the words, ignoring case. Admin
+    /// only, polls already created are left alone.
+    AddBannedWords {
+        words: Vec<String>,
+    },
+    RemoveBannedWords {
+        words: Vec<String>,
+    },
+    /// Offers the admin role to `address`, which has to accept it before `deadline`, a week from
+    /// now by default. Replaces any earlier proposal. Admin only.
+    ProposeNewAdmin {
+        address: String,
+        deadline: Option<Expiration>,
+    },
+    /// Takes over the admin role proposed to the sender
+    AcceptAdmin {},
+    /// Entry point for cw20 tokens sent with an embedded ReceiveMsg
+    Receive(Cw20ReceiveMsg),
+    /// Sends the whole treasury, from every source, to the recipient, the admin by default
+    WithdrawFees {
+        recipient: Option<String>,
+    },
+    /// Sends part of the treasury to an address, admin only
+    Spend {
+        to: String,
+        amount: Coin,
+    },
+    /// Seeds the contract with polls exported from another deployment, admin only and only while
+    /// bootstrapping. The deposits and native rewards they hold have to be sent along, their
+    /// ballots follow through ImportBallots.
+    ImportPolls {
+        polls: Vec<PollInfo>,
+    },
+    /// Adds exported ballots to imported polls, counting them into the tallies. A finalized poll's
+    /// ballots can't add up to more than its reward is shared by.
+    ImportBallots {
+        ballots: Vec<ImportedBallot>,
+    },
+    /// Stops importing for good, admin only
+    EndBootstrap {},
+    /// Sends the sender's failed payouts again
+    RetryPayouts {},
+    /// Sends the balance of a native denom the contract holds without accounting for it, e.g.
+    /// coins attached to a message by mistake, to the recipient or the admin. Admin only.
+    Sweep {
+        denom: String,
+        recipient: Option<String>,
+    },
+    /// Registers a contract to be notified of poll events, admin only
+    AddHook {
+        addr: String,
+    },
+    RemoveHook {
+        addr: String,
+    },
+    /// cw3 Propose, creating a gov style Yes/No poll that runs `msgs` once executed
+    Propose {
+        title: String,
+        description: String,
+        msgs: Vec<CosmosMsg>,
+        latest: Option<Expiration>,
+    },
+    /// cw3 Execute, finalizing a passed proposal and running its messages
+    Execute {
+        proposal_id: u64,
+    },
+    /// cw3 Close, finalizing a proposal that didn't pass
+    Close {
+        proposal_id: u64,
+    },
+}
+
+/// Sent to every registered hook contract, wrapped as `{"poll_hook": ...}`. A hook that fails
+/// doesn't hold up the poll.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollHookMsg {
+    PollCreated { poll_id: String, creator: Addr },
+    Voted { poll_id: String, voter: Addr },
+    PollFinalized { poll_id: String, result: PollResult },
+    PollCancelled { poll_id: String },
+}
+
+/// The execute message hook contracts receive
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookExecuteMsg {
+    PollHook(PollHookMsg),
+}
+
+/// Messages only the chain itself can send, through governance on permissioned chains
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Refuses every execute message until unfrozen, queries keep working
+    FreezeContract {},
+    UnfreezeContract {},
+    /// Deletes a poll the way its creator could, refunding what it holds
+    RemovePoll {
+        poll_id: String,
+    },
+    SetAdmin {
+        admin: String,
+    },
+}
+
+/// Messages embedded in a cw20 Send of the accepted token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// Adds the tokens to a poll's reward pool
+    FundPoll { poll_id: String },
+    /// Buys the sender a poll creation that skips the native poll fee for every whole fee sent,
+    /// the rest is sent back
+    PayPollFee {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    AllPolls {},
+    /// Hidden polls are left out
+    AllPolls {
+        start_after: Option<String>,
+        limit: Option<u32>,
+        /// By ascending poll id unless set, `start_after` follows the order
+        order: Option<SortOrder>,
+    },
+    /// The most recently created polls, newest first
+    RecentPolls {
+        limit: Option<u32>,
+    },
     Poll {
         poll_id: String,
     },
+    /// How many polls there are, without loading any
+    PollCount {},
+    /// The nonce the address's next signed vote has to use
+    SigNonce {
+        address: String,
+    },
+    /// Whether a poll exists, without loading it
+    PollExists {
+        poll_id: String,
+    },
+    /// Needs the voter's viewing key once they've set one, and for ballots of private polls
     Vote {
         poll_id: String,
         address: String,
+        viewing_key: Option<String>,
+    },
+    Config {},
+    /// Polls by poll id, for moving a deployment to a new contract, their ballots are paged
+    /// through ExportBallots. Takes the viewing key of an admin.
+    ExportState {
+        address: String,
+        viewing_key: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// A poll's ballots, by voter address or by voter hash for private polls, ready for
+    /// ImportBallots. Takes the viewing key of an admin, as it includes private ballots.
+    ExportBallots {
+        address: String,
+        viewing_key: String,
+        poll_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// The admin handover waiting to be accepted, if any
+    PendingAdmin {},
+    /// The role granted to an address, if any
+    Role {
+        address: String,
+    },
+    /// Banned addresses, in ascending order
+    BannedAddresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Banned words, lowercased and in ascending order
+    BannedWords {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Contract-wide counters, returned as `Stats`
+    Stats {},
+    /// What an address has done in the contract, returned as `Reputation`
+    Reputation {
+        address: String,
+    },
+    /// Vote log entries by sequence number, of a single poll when `poll_id` is set
+    VoteLog {
+        poll_id: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Addresses with the highest count of the given kind, most first
+    Leaderboard {
+        kind: LeaderboardKind,
+        limit: Option<u32>,
+    },
+    /// Who owns a namespace and who else may create polls in it, returned as
+    /// `NamespaceResponse`
+    Namespace {
+        namespace: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Polls with open reports, by poll id
+    ReportedPolls {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Who an address delegates to and who delegates to it
+    Delegations {
+        address: String,
+    },
+    /// The instant-runoff rounds of a finalized ranked choice poll
+    TallyRounds {
+        poll_id: String,
+    },
+    /// The attestation hash a poll was finalized with, see AttestedResult
+    ResultAttestation {
+        poll_id: String,
+    },
+    /// A poll's tallies as they stood at the start of a block, so counts can be checked later
+    TallyAtHeight {
+        poll_id: String,
+        height: u64,
+    },
+    /// Payouts to an address that failed and can be retried
+    FailedPayouts {
+        address: String,
     },
-    GetConfig {},
+    /// What the treasury holds, in total and per source
+    Treasury {},
+    /// Vote fee shares an address can claim with ClaimCreatorFees
+    CreatorFees {
+        address: String,
+    },
+    Hooks {},
+    /// Who voted in a poll and how, by address. Fails for polls with private ballots, and voters
+    /// who set a viewing key are left out.
+    PollVoters {
+        poll_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Ballots an address cast, by poll id. Private ballots are never listed, and the voter's
+    /// viewing key is needed once they've set one.
+    VotesByVoter {
+        address: String,
+        viewing_key: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Polls created by an address, by poll id
+    PollsByCreator {
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Polls whose id starts with `prefix`, e.g. everything under `dao1/`, by poll id
+    PollsByIdPrefix {
+        prefix: String,
+        limit: Option<u32>,
+    },
+    /// Polls in a status, by poll id
+    PollsByStatus {
+        status: PollStatus,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Polls carrying a tag, by poll id
+    PollsByTag {
+        tag: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Active polls that haven't ended yet, soonest end first
+    EndingPolls {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Current totals of a poll with each option's share and the leader
+    PollResults {
+        poll_id: String,
+    },
+    /// The summary of an archived poll, if any
+    ArchivedPoll {
+        poll_id: String,
+    },
+    /// The raffle of a poll, if it has one
+    Raffle {
+        poll_id: String,
+    },
+    /// A survey with how often each option of each question was chosen
+    Survey {
+        survey_id: String,
+    },
+    /// The answers an address gave to a survey
+    SurveyAnswers {
+        survey_id: String,
+        address: String,
+    },
+    /// Everyone who answered a survey with their answers, by address
+    SurveyRespondents {
+        survey_id: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// A recurring poll's template and schedule, if any
+    RecurringPoll {
+        template_id: String,
+    },
+    /// Polls spawned from a recurring poll, by instance number
+    RecurringInstances {
+        template_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// The contract running a poll created in factory mode
+    PollContract {
+        poll_id: String,
+    },
+    /// cw3 Proposal, returning a ProposalResponse
+    Proposal {
+        proposal_id: u64,
+    },
+    /// cw3 ListProposals, by ascending id
+    ListProposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// cw3 ReverseProposals, by descending id
+    ReverseProposals {
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// cw3 ListVotes, by voter address
+    ListVotes {
+        proposal_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+/// Direction of a listing, cosmwasm_std's Order can't be sent in a message
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Order {
+        match order {
+            SortOrder::Ascending => Order::Ascending,
+            SortOrder::Descending => Order::Descending,
+        }
+    }
+}
+
+/// The counter a leaderboard ranks addresses by
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardKind {
+    PollsCreated,
+    PollsVoted,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -52,20 +853,259 @@ pub struct CustomResponse {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollVotersResponse {
+    pub voters: Vec<VoterInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VoterInfo {
+    pub voter: Addr,
+    pub ballot: Ballot,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VotesByVoterResponse {
+    pub votes: Vec<VoteInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VoteInfo {
+    pub poll_id: String,
+    pub ballot: Ballot,
+}
+
+/// A ballot as exported by ExportBallots
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ImportedBallot {
+    pub poll_id: String,
+    /// The voter's address, or their voter hash for private polls
+    pub voter: String,
+    pub ballot: Ballot,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ExportStateResponse {
+    pub polls: Vec<PollInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ExportBallotsResponse {
+    pub ballots: Vec<ImportedBallot>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollInfo {
+    pub poll_id: String,
+    pub poll: Poll,
+}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AllPollsResponse {
-    pub polls: Vec<Poll>,
+    pub polls: Vec<PollInfo>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct PollResponse {
     pub poll: Option<Poll>,
+    /// Each option's label and votes, stored apart from the poll
+    pub tallies: Vec<(String, Uint128)>,
+    /// Combined weight of the ballots cast
+    pub total_votes: Uint128,
+    pub unique_voters: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SurveyResponse {
+    pub survey: Option<Survey>,
+    /// For each question, every option's label and how many chose it
+    pub results: Vec<Vec<(String, u64)>>,
+    pub respondents: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SurveyAnswersResponse {
+    /// The chosen option for each question, None if the address didn't answer
+    pub answers: Option<Vec<String>>,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SurveyRespondentsResponse {
+    /// (respondent, answers)
+    pub respondents: Vec<(Addr, Vec<String>)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct RecurringInstancesResponse {
+    /// (instance, poll_id)
+    pub instances: Vec<(u64, String)>,
+}
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct VoteResponse {
     pub vote: Option<Ballot>,
-}
\ No newline at end of file
+}
+
+/// What a voter signs to have a vote submitted with VoteBySig. The chain and contract keep the
+/// signature from counting anywhere else.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SignedVote {
+    pub chain_id: String,
+    pub contract: String,
+    pub poll_id: String,
+    pub vote: String,
+    pub nonce: u64,
+    pub expires: Timestamp,
+}
+
+/// What the attestation of a finalized poll is the hex encoded sha256 of, serialized as JSON. The
+/// tallies are sorted by option and the voters ascending, voters of private polls by their hash.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AttestedResult {
+    pub poll_id: String,
+    pub tallies: Vec<(String, Uint128)>,
+    pub voters: Vec<String>,
+}
+
+/// The extension of a vote receipt NFT
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReceiptExtension {
+    pub poll_id: String,
+    /// Hex encoded sha256 of the comma separated selections, left out for private polls
+    pub choice_hash: Option<String>,
+}
+
+/// Data of the CreatePoll response
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CreatePollResponse {
+    pub poll_id: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollCountResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SigNonceResponse {
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollExistsResponse {
+    pub exists: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollResultsResponse {
+    pub total_votes: Uint128,
+    pub options: Vec<OptionResult>,
+    /// The option with the most votes, None while nobody voted or the top options are tied
+    pub leader: Option<String>,
+    /// The options sharing the most votes when there's a tie
+    pub tied: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct OptionResult {
+    pub option: String,
+    pub votes: Uint128,
+    /// Share of the total votes, zero while there are none
+    pub percentage: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct PollContractResponse {
+    pub address: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct HooksResponse {
+    pub hooks: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BannedAddressesResponse {
+    pub addresses: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct BannedWordsResponse {
+    pub words: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct CreatorFeesResponse {
+    pub fees: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct FailedPayoutsResponse {
+    pub payouts: Vec<Payout>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LeaderboardResponse {
+    /// Addresses and their counts, ties broken by address in descending order
+    pub entries: Vec<(Addr, u64)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct NamespaceResponse {
+    /// None while the namespace is unclaimed
+    pub owner: Option<Addr>,
+    /// Addresses the owner authorized, in ascending order
+    pub creators: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReportedPollsResponse {
+    pub polls: Vec<ReportedPoll>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ReportedPoll {
+    pub poll_id: String,
+    /// Open reports against the poll
+    pub reports: u32,
+    pub hidden: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct DelegationsResponse {
+    pub delegate: Option<Addr>,
+    /// Addresses delegating directly to this one
+    pub delegators: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TallyAtHeightResponse {
+    pub height: u64,
+    /// By the poll's current options, ones added since count zero
+    pub tallies: Vec<(String, Uint128)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TallyRoundsResponse {
+    pub rounds: Vec<TallyRound>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct TreasuryResponse {
+    pub balance: Vec<Coin>,
+    pub sources: Vec<(TreasurySource, Vec<Coin>)>,
+    /// Poll fees paid in cw20 tokens, per token contract
+    pub cw20_poll_fees: Vec<(Addr, Uint128)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct VoteLogResponse {
+    pub entries: Vec<(u64, VoteLogEntry)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ResultAttestationResponse {
+    pub attestation: Option<String>,
+}
@@ -0,0 +1,327 @@
+//! Raffles: a prize shared between voters of a poll, drawn at random once the poll is finalized.
+//!
+//! The randomness comes from Nois. Drawing a raffle asks the configured Nois proxy for the next
+//! beacon round under the poll id as job id, and the proxy calls `NoisReceive` back once it's
+//! published, which picks the winners and pays them out. The outcome only depends on that
+//! randomness and the poll's voters, which were fixed by finalization before it was requested.
+
+use cosmwasm_std::{
+    coin, to_binary, Addr, DepsMut, Env, MessageInfo, Order, Response, StdResult, WasmMsg,
+};
+use sha2::{Digest, Sha256};
+
+use crate::contract::{reserve, send_payout};
+use crate::error::ContractError;
+use crate::external::{NoisCallback, NoisProxyExecuteMsg};
+use crate::state::{polls, Payout, PollStatus, Raffle, CONFIG, POLL_VOTERS, RAFFLES};
+
+pub fn execute_create_raffle(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+    winners: u32,
+) -> Result<Response, ContractError> {
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    if info.sender != poll.creator {
+        return Err(ContractError::Unauthorized {});
+    }
+    if RAFFLES.has(deps.storage, poll_id.clone()) {
+        return Err(ContractError::RaffleExists { poll_id });
+    }
+    // Winners are paid by address, which private polls don't keep
+    if winners == 0
+        || info.funds.is_empty()
+        || poll.private_ballots
+        || poll.status == PollStatus::Finalized
+    {
+        return Err(ContractError::InvalidRaffle {});
+    }
+
+    for prize in &info.funds {
+        reserve(deps.storage, prize)?;
+    }
+    let raffle = Raffle {
+        creator: info.sender.clone(),
+        winners,
+        prize: info.funds,
+        requested: false,
+        drawn: None,
+    };
+    RAFFLES.save(deps.storage, poll_id.clone(), &raffle)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_raffle")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("winners", winners.to_string()))
+}
+
+pub fn execute_draw_raffle(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    poll_id: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let proxy = config.nois_proxy.ok_or(ContractError::NoNoisProxy {})?;
+    let mut raffle = RAFFLES
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::RaffleNotFound {})?;
+    if raffle.requested {
+        return Err(ContractError::RaffleAlreadyDrawn {});
+    }
+    let poll = match polls().may_load(deps.storage, poll_id.clone())? {
+        Some(poll) => poll,
+        None => return Err(ContractError::PollNotFound {}),
+    };
+    // Voters can't change anymore once the poll is finalized
+    if poll.status != PollStatus::Finalized {
+        return Err(ContractError::PollNotExpired {});
+    }
+    raffle.requested = true;
+    RAFFLES.save(deps.storage, poll_id.clone(), &raffle)?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: proxy.into_string(),
+        msg: to_binary(&NoisProxyExecuteMsg::GetNextRandomness {
+            job_id: poll_id.clone(),
+        })?,
+        funds: info.funds,
+    };
+    Ok(Response::new()
+        .add_message(msg)
+        .add_attribute("action", "draw_raffle")
+        .add_attribute("poll_id", poll_id))
+}
+
+pub fn execute_nois_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    callback: NoisCallback,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.nois_proxy.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let poll_id = callback.job_id;
+    let mut raffle = RAFFLES
+        .may_load(deps.storage, poll_id.clone())?
+        .ok_or(ContractError::RaffleNotFound {})?;
+    if !raffle.requested || raffle.drawn.is_some() {
+        return Err(ContractError::RaffleAlreadyDrawn {});
+    }
+
+    // Voters relayed over IBC have no local address to be paid at
+    let voters = POLL_VOTERS
+        .prefix(poll_id.clone())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .filter(|voter| {
+            voter
+                .as_ref()
+                .map_or(true, |voter| !voter.as_str().contains('/'))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let winners = pick_winners(voters, raffle.winners, callback.randomness.as_slice());
+
+    // Shares are even, the first winner also gets what doesn't divide. Without any voters the
+    // prize goes back to the creator.
+    let recipients = if winners.is_empty() {
+        vec![raffle.creator.clone()]
+    } else {
+        winners.clone()
+    };
+    let mut res = Response::new();
+    for prize in &raffle.prize {
+        let share = prize.amount.u128() / recipients.len() as u128;
+        let rest = prize.amount.u128() % recipients.len() as u128;
+        for (i, recipient) in recipients.iter().enumerate() {
+            let amount = if i == 0 { share + rest } else { share };
+            if amount == 0 {
+                continue;
+            }
+            let payout = Payout {
+                recipient: recipient.clone(),
+                amount: coin(amount, &prize.denom),
+                cw20: false,
+            };
+            res = res.add_submessage(send_payout(deps.storage, payout)?);
+        }
+    }
+    raffle.drawn = Some(winners.clone());
+    RAFFLES.save(deps.storage, poll_id.clone(), &raffle)?;
+
+    let winners: Vec<&str> = winners.iter().map(Addr::as_str).collect();
+    Ok(res
+        .add_attribute("action", "nois_receive")
+        .add_attribute("poll_id", poll_id)
+        .add_attribute("winners", winners.join(",")))
+}
+
+/// Draws up to `count` distinct voters, shuffling the front of the list with numbers derived
+/// from the randomness one index at a time
+fn pick_winners(mut voters: Vec<Addr>, count: u32, randomness: &[u8]) -> Vec<Addr> {
+    let count = (count as usize).min(voters.len());
+    for i in 0..count {
+        let digest = Sha256::digest([randomness, &(i as u64).to_be_bytes()].concat());
+        let number = u64::from_be_bytes(digest[..8].try_into().unwrap());
+        let j = i + (number % (voters.len() - i) as u64) as usize;
+        voters.swap(i, j);
+    }
+    voters.truncate(count);
+    voters
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{from_binary, BankMsg, CosmosMsg, HexBinary};
+
+    use super::*;
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, NewPoll, QueryMsg};
+
+    fn vote(poll_id: &str) -> ExecuteMsg {
+        ExecuteMsg::Vote {
+            poll_id: poll_id.to_string(),
+            proposal_id: None,
+            vote: Some("Juno".to_string()),
+            option_index: None,
+            votes: vec![],
+            credits: vec![],
+            proof: vec![],
+        }
+    }
+
+    #[test]
+    fn test_raffle() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let creator = mock_info("creator", &[]);
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        let msg = ExecuteMsg::CreatePoll(NewPoll {
+            poll_id: "poll".to_string(),
+            question: "What's your favourite Cosmos coin?".to_string(),
+            options: vec!["Juno".to_string(), "Osmosis".to_string()],
+            ..Default::default()
+        });
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+
+        // Only the poll's creator can put a prize up
+        let prize = &[coin(101, "ujuno")];
+        let msg = ExecuteMsg::CreateRaffle {
+            poll_id: "poll".to_string(),
+            winners: 2,
+        };
+        let info = mock_info("alice", prize);
+        let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let info = mock_info("creator", prize);
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        for voter in ["alice", "bob", "carol"] {
+            let info = mock_info(voter, &[]);
+            let _res = execute(deps.as_mut(), env.clone(), info, vote("poll")).unwrap();
+        }
+
+        // Drawing needs a proxy and a finalized poll
+        let draw = ExecuteMsg::DrawRaffle {
+            poll_id: "poll".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), draw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::NoNoisProxy {}));
+        let msg = ExecuteMsg::UpdateConfig {
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            restricted_creation: None,
+            rate_limit: None,
+            max_active_polls: None,
+            nois_proxy: Some("nois".to_string()),
+            receipt_nft: None,
+            report_threshold: None,
+            vote_fee_share: None,
+            keeper_bounty: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), creator.clone(), draw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::PollNotExpired {}));
+        for msg in [
+            ExecuteMsg::ClosePoll {
+                poll_id: "poll".to_string(),
+            },
+            ExecuteMsg::FinalizePoll {
+                poll_id: "poll".to_string(),
+            },
+        ] {
+            let _res = execute(deps.as_mut(), env.clone(), creator.clone(), msg).unwrap();
+        }
+
+        // The proxy fee is forwarded with the request
+        let fee = &[coin(50, "unois")];
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", fee), draw).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "nois".to_string(),
+                msg: to_binary(&NoisProxyExecuteMsg::GetNextRandomness {
+                    job_id: "poll".to_string(),
+                })
+                .unwrap(),
+                funds: fee.to_vec(),
+            })
+        );
+
+        // Only the proxy delivers randomness, and only once
+        let receive = ExecuteMsg::NoisReceive {
+            callback: NoisCallback {
+                job_id: "poll".to_string(),
+                published: env.block.time,
+                randomness: HexBinary::from(&[7; 32]),
+            },
+        };
+        let err = execute(deps.as_mut(), env.clone(), creator, receive.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let nois = mock_info("nois", &[]);
+        let res = execute(deps.as_mut(), env.clone(), nois.clone(), receive.clone()).unwrap();
+        let msg = QueryMsg::Raffle {
+            poll_id: "poll".to_string(),
+        };
+        let raffle: Option<Raffle> =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        let winners = raffle.unwrap().drawn.unwrap();
+        assert_eq!(winners.len(), 2);
+        assert_ne!(winners[0], winners[1]);
+        let payouts: Vec<_> = res.messages.iter().map(|sub| sub.msg.clone()).collect();
+        assert_eq!(
+            payouts,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: winners[0].to_string(),
+                    amount: vec![coin(51, "ujuno")],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: winners[1].to_string(),
+                    amount: vec![coin(50, "ujuno")],
+                }),
+            ]
+        );
+        let err = execute(deps.as_mut(), env, nois, receive).unwrap_err();
+        assert!(matches!(err, ContractError::RaffleAlreadyDrawn {}));
+    }
+}
@@ -1,31 +1,713 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{
+    Addr, Binary, BlockInfo, Coin, CosmosMsg, Decimal, Empty, IbcEndpoint, StdResult, Storage,
+    Timestamp, Uint128,
+};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, SnapshotMap, Strategy};
+use cw_utils::Expiration;
+
+use crate::msg::NewPoll;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub admin: Addr
+    pub admin: Addr,
+    /// Charged for creating a poll and kept in the treasury
+    pub poll_fee: Option<Coin>,
+    /// Held while a poll runs, refunded once it ends with quorum and slashed otherwise
+    pub poll_deposit: Option<Coin>,
+    /// The cw20 token accepted through Receive
+    pub accepted_token: Option<Addr>,
+    /// Price in the accepted token of a prepaid poll creation, bought with PayPollFee
+    pub cw20_poll_fee: Option<Uint128>,
+    /// Set by chain governance through sudo, every execute message is refused while frozen
+    pub frozen: bool,
+    /// Code instantiated for polls created in factory mode, each in its own contract
+    pub poll_code_id: Option<u64>,
+    pub limits: PollLimits,
+    /// Only addresses holding the PollCreator role or above may create polls
+    pub restricted_creation: bool,
+    /// In seconds, the voting window of polls created without an end height or time
+    pub default_poll_duration: Option<u64>,
+    /// Set by an admin to stop every execute message but Unpause, e.g. after an exploit
+    pub paused: bool,
+    pub rate_limit: Option<RateLimit>,
+    /// How many drafts and polls accepting votes a single address may have at once
+    pub max_active_polls: Option<u32>,
+    /// The Nois proxy raffles get their randomness from
+    pub nois_proxy: Option<Addr>,
+    /// The cw721 contract minting a receipt for every ballot, with this contract as its minter.
+    /// It should refuse transfers, receipts are meant as participation badges.
+    pub receipt_nft: Option<Addr>,
+    /// Open reports after which a poll is hidden until a moderator looks at it
+    pub report_threshold: Option<u32>,
+    /// Share of a finalized poll's vote fees kept in the treasury, the rest goes to its creator
+    pub vote_fee_share: Decimal,
+    /// Paid to whoever ends an expired poll, out of its deposit or else the treasury
+    pub keeper_bounty: Option<Coin>,
+    /// Polls and ballots can be imported until the admin ends the bootstrap
+    pub bootstrap: bool,
+}
+
+/// How many polls a single address may create within a window of time
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RateLimit {
+    pub max_polls: u32,
+    /// In seconds, counted from the first creation after the previous window ran out
+    pub window: u64,
+}
+
+/// The polls an address created in its current rate limit window
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreationWindow {
+    pub start: Timestamp,
+    pub count: u32,
+}
+
+/// Roles the admin can grant, each one allowing everything the roles before it do. The configured
+/// admin holds all of them without a grant.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Creates polls while creation is restricted
+    PollCreator,
+    /// Deletes and hides anyone's polls, and works through the reports
+    Moderator,
+    /// Does everything the configured admin does, apart from handing over the contract
+    Admin,
+}
+
+/// Where the coins in the treasury came from
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TreasurySource {
+    PollFees,
+    VoteFees,
+    SlashedDeposits,
+}
+
+impl TreasurySource {
+    /// Every source, in the order spending draws them down
+    pub const ALL: [TreasurySource; 3] = [
+        TreasurySource::PollFees,
+        TreasurySource::VoteFees,
+        TreasurySource::SlashedDeposits,
+    ];
+
+    /// The first part of the source's keys in TREASURY_SOURCES
+    pub fn key(&self) -> &'static str {
+        match self {
+            TreasurySource::PollFees => "poll_fees",
+            TreasurySource::VoteFees => "vote_fees",
+            TreasurySource::SlashedDeposits => "slashed_deposits",
+        }
+    }
+}
+
+/// Bounds on what a poll may be created with, in characters for the lengths
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PollLimits {
+    pub max_question_len: u32,
+    pub max_option_len: u32,
+    pub max_options: u32,
+}
+
+impl Default for PollLimits {
+    fn default() -> Self {
+        PollLimits {
+            max_question_len: 256,
+            max_option_len: 64,
+            max_options: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollStatus {
+    /// Created but not yet accepting votes
+    Draft,
+    /// Accepting votes until closed or expired
+    Active,
+    /// No longer accepting votes, waiting to be finalized
+    Closed,
+    /// Results are final
+    Finalized,
+    /// Called off by the creator, its ballots are void
+    Cancelled,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TallyType {
+    /// Every selected option receives the ballot's weight, the highest tally wins
+    #[default]
+    Plurality,
+    /// Ballots rank options and are counted by instant-runoff elimination
+    RankedChoice,
+    /// Ballots spend credits across options, each option receives the square root of its credits
+    Quadratic,
+}
+
+/// The tallies of one instant-runoff round and the options knocked out after it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TallyRound {
+    pub tallies: Vec<(String, Uint128)>,
+    pub eliminated: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PollOutcome {
+    /// A winning option was found
+    Passed,
+    /// Nobody won, or the vote went against the proposal
+    Rejected,
+    /// Veto votes were above the poll's veto threshold
+    Vetoed,
+    /// Too little of the eligible voting power took part
+    QuorumNotMet,
+    /// An admin threw the poll out, see the poll's `invalid_reason`
+    Invalidated,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollResult {
+    /// The option with the strictly highest tally, None on a tie or when nobody voted
+    pub winner: Option<String>,
+    /// The options sharing the highest tally when there is a tie
+    pub tied: Vec<String>,
+    pub outcome: PollOutcome,
+}
+
+/// What is left of a finalized poll once it has been archived, its ballots gone
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollSummary {
+    pub creator: Addr,
+    pub question: String,
+    pub tallies: Vec<(String, Uint128)>,
+    pub result: Option<PollResult>,
+    pub archived_at: Timestamp,
+}
+
+/// Several questions answered together, each respondent's answers kept as one response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Survey {
+    pub creator: Addr,
+    pub title: String,
+    pub questions: Vec<SurveyQuestion>,
+    pub created_at_time: Timestamp,
+    /// Responses are turned away from this time on
+    pub end_time: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SurveyQuestion {
+    pub question: String,
+    pub options: Vec<String>,
+}
+
+/// A prize for voters of a poll, drawn with Nois randomness once the poll is finalized
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Raffle {
+    pub creator: Addr,
+    /// How many voters share the prize, every voter wins when there are fewer
+    pub winners: u32,
+    pub prize: Vec<Coin>,
+    /// Set once randomness has been requested from the Nois proxy
+    pub requested: bool,
+    /// The winning voters, set once the randomness arrived and the prize was paid out
+    pub drawn: Option<Vec<Addr>>,
+}
+
+/// A poll template respawned once per period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RecurringPoll {
+    pub creator: Addr,
+    /// What every instance is created from, each getting its own id and end time
+    pub template: NewPoll,
+    /// Seconds between instances, each one open for its whole period
+    pub period: u64,
+    /// When the next instance may be spawned
+    pub next_spawn: Timestamp,
+    /// Instances spawned so far
+    pub instances: u64,
 }
 
+/// Ballots currently standing in a poll
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct VoteCounts {
+    /// Combined weight of the ballots
+    pub total_votes: Uint128,
+    pub unique_voters: u64,
+}
+
+/// Funds a poll creator put up to be shared by the voters
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardPool {
+    pub amount: Coin,
+    /// Combined weight of every ballot, set when the poll is finalized
+    pub total_weight: Uint128,
+    /// How much of the amount has been claimed so far
+    pub claimed: Uint128,
+    /// The pool is in a cw20 token, whose address is the amount's denom
+    #[serde(default)]
+    pub cw20: bool,
+}
+
+/// Options added to every gov style poll
+pub const ABSTAIN_OPTION: &str = "Abstain";
+pub const VETO_OPTION: &str = "NoWithVeto";
+
 // Derive JSON serialization
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Poll {
     pub creator: Addr,
+    /// Block the poll was created in
+    pub created_at_height: u64,
+    pub created_at_time: Timestamp,
     pub question: String,
-    pub options: Vec<(String, u64)>,
+    pub description: String,
+    pub metadata_uri: Option<String>,
+    pub tags: Vec<String>,
+    pub options: Vec<PollOption>,
+    /// Votes are turned away before this time
+    pub start_time: Option<Timestamp>,
+    pub end_height: Option<u64>,
+    pub end_time: Option<Timestamp>,
+    /// Ballots can't be changed or revoked from this time on
+    pub revote_deadline: Option<Timestamp>,
+    /// When set, votes are weighted by the voter's bank balance of this denom
+    pub weight_denom: Option<String>,
+    /// When set, only holders of this cw20 token may vote, weighted by their balance
+    pub cw20_address: Option<Addr>,
+    /// When set, votes are weighted by the voter's total bonded stake
+    pub staking_weighted: bool,
+    /// When set, only owners of an NFT from this cw721 collection may vote
+    pub nft_collection: Option<Addr>,
+    /// Votes are weighted by the number of NFTs held from the collection
+    pub nft_weighted: bool,
+    /// When set, votes are weighted by the voter's membership weight in this cw4 group
+    pub cw4_group: Option<Addr>,
+    /// Group weights are read as of this height instead of the current one
+    pub cw4_snapshot_height: Option<u64>,
+    /// Votes count the power checkpointed before this height, set once the poll starts
+    pub snapshot_height: Option<u64>,
+    /// Votes relayed over IBC are accepted, each remote voter counting once
+    pub ibc_voting: bool,
+    /// Messages executed when the poll passes, for the option that won
+    pub msgs: Vec<(String, Vec<CosmosMsg>)>,
+    /// Contract receiving the winning option's payload when the poll passes
+    pub executor: Option<Addr>,
+    /// The executor's ExecuteMsg per option, by position
+    pub payload_per_option: Vec<Binary>,
+    /// How many options a single ballot may select
+    pub max_selections: u32,
+    /// For ranked choice polls the options tally only counts first preferences
+    pub tally_type: TallyType,
+    /// Credits each voter may spend in a quadratic poll, their voting power when None
+    pub credit_budget: Option<u64>,
+    /// Cosmos gov style poll, with Abstain and NoWithVeto options
+    pub gov_style: bool,
+    /// Share of all votes going to NoWithVeto above which a gov style poll is vetoed
+    pub veto_threshold: Option<Decimal>,
+    /// Share of the eligible power that has to vote for the result to count
+    pub quorum: Option<Decimal>,
+    /// Share of the counted votes the winner needs for the poll to pass
+    pub threshold: Option<Decimal>,
+    /// Total voting power of everyone allowed to vote, the number of voters for unweighted polls
+    pub eligible_power: Option<u64>,
+    /// Votes are committed as hashes while the poll is open and revealed afterwards
+    pub commit_reveal: bool,
+    pub reveal_end_height: Option<u64>,
+    pub reveal_end_time: Option<Timestamp>,
+    /// Ballots are kept under hashed voter keys and can't be queried by others
+    pub private_ballots: bool,
+    /// Votes for options the poll doesn't have add them
+    pub allow_write_ins: bool,
+    /// A ballot can't be changed or revoked once cast
+    pub locked_ballots: bool,
+    /// Only addresses in ALLOWED_VOTERS may vote
+    pub allowlist: bool,
+    /// Hex encoded sha256 merkle root of the eligible addresses, voters send a proof against it
+    pub merkle_root: Option<String>,
+    /// Voters need to have voted in this many polls before
+    pub min_reputation: Option<u64>,
+    /// Paid by each voter with their first ballot
+    pub vote_fee: Option<Coin>,
+    /// Deposit paid by the creator, cleared once it has been refunded or slashed
+    pub deposit: Option<Coin>,
+    /// Shared between the voters pro-rata by weight once the poll is finalized
+    pub reward: Option<RewardPool>,
+    pub status: PollStatus,
+    /// Set once the poll is finalized
+    pub result: Option<PollResult>,
+    /// Left out of AllPolls, by a moderator or once enough voters reported the poll
+    pub hidden: bool,
+    /// Why an admin invalidated the poll
+    pub invalid_reason: Option<String>,
+}
+
+/// One of the choices of a poll, its votes are kept in TALLIES
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PollOption {
+    pub label: String,
+    pub description: String,
+    pub image_uri: Option<String>,
+}
+
+impl Poll {
+    /// Where the poll sorts in the end time index, polls that never end last
+    pub fn end_key(&self) -> u64 {
+        self.end_time.map_or(u64::MAX, |time| time.nanos())
+    }
+
+    /// Each option's label and votes
+    pub fn tallies(
+        &self,
+        storage: &dyn Storage,
+        poll_id: &str,
+    ) -> StdResult<Vec<(String, Uint128)>> {
+        (0u32..)
+            .zip(&self.options)
+            .map(|(position, option)| {
+                let votes = TALLIES
+                    .may_load(storage, (poll_id, position))?
+                    .unwrap_or_default();
+                Ok((option.label.clone(), votes))
+            })
+            .collect()
+    }
+
+    /// A poll is expired once either of its end conditions has been reached
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.end_height.is_some_and(|height| block.height >= height)
+            || self.end_time.is_some_and(|time| block.time >= time)
+    }
+
+    /// Voting is over once the poll has expired and, for commit-reveal polls, so has the reveal
+    pub fn voting_ended(&self, block: &BlockInfo) -> bool {
+        let reveal_ended = self
+            .reveal_end_height
+            .is_some_and(|height| block.height >= height)
+            || self.reveal_end_time.is_some_and(|time| block.time >= time);
+        self.is_expired(block) && (!self.commit_reveal || reveal_ended)
+    }
+}
+
+/// A transfer out of the contract, through the token contract whose address is the denom for cw20
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payout {
+    pub recipient: Addr,
+    pub amount: Coin,
+    pub cw20: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Ballot {
-    pub option: String,
+    /// The selected options, in order of preference for ranked choice polls
+    pub options: Vec<String>,
+    /// What this ballot added to each selected option's tally
+    pub weight: Uint128,
+    /// Credits spent on each selected option in a quadratic poll, empty otherwise
+    pub credits: Vec<u64>,
+    /// Delegators whose voting power is included in the weight
+    #[serde(default)]
+    pub represented: Vec<Addr>,
+    /// Set once the voter has claimed their share of the reward pool
+    #[serde(default)]
+    pub reward_claimed: bool,
+    /// When the ballot was cast, unknown for ballots migrated from 0.1 or cast by 0.2 before this
+    /// was recorded
+    #[serde(default)]
+    pub cast_at_height: Option<u64>,
+    #[serde(default)]
+    pub cast_at_time: Option<Timestamp>,
+    /// Set when the poll was cancelled, the ballot no longer counts for anything
+    #[serde(default)]
+    pub void: bool,
+}
+
+/// What happened to a ballot, as recorded in the vote log
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VoteAction {
+    Vote,
+    /// A ballot replacing the voter's earlier one
+    Revote,
+    Revoke,
+}
+
+/// One change to a ballot, kept after the ballot itself is replaced or removed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoteLogEntry {
+    pub poll_id: String,
+    pub voter: Addr,
+    pub action: VoteAction,
+    /// The options voted for or revoked, left empty in polls with private ballots
+    pub options: Vec<String>,
+    pub weight: Uint128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+/// A cw3 proposal, voted on as a gov style Yes/No poll
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Proposal {
+    pub poll_id: String,
+    pub description: String,
+}
+
+/// Contract-wide counters, kept up to date as polls are created, voted on and finalized
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Stats {
+    pub polls_created: u64,
+    /// Every ballot cast, counting changed votes again
+    pub votes_cast: u64,
+    /// Addresses that have voted in any poll
+    pub unique_voters: u64,
+    pub polls_finalized: u64,
+}
+
+/// An address's participation, kept up to date as it votes and creates polls
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Reputation {
+    /// Polls the address has a ballot in, revoked ballots don't count
+    pub polls_voted: u64,
+    pub polls_created: u64,
+    /// Consecutive periods, up to the last one, the address voted or created a poll in
+    pub streak: u32,
+    pub last_active_period: u64,
+}
+
+/// An admin handover waiting for the new admin to accept it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingAdmin {
+    pub address: Addr,
+    /// The proposal can no longer be accepted once this passes
+    pub deadline: Expiration,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const POLLS: Map<String, Poll> = Map::new("polls");
-pub const BALLOTS: Map<(Addr, String), Ballot> = Map::new("ballots");
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pending_admin");
+pub const ROLES: Map<Addr, Role> = Map::new("roles");
+pub const CREATION_WINDOWS: Map<Addr, CreationWindow> = Map::new("creation_windows");
+/// Drafts and polls accepting votes per creator. Expired polls keep counting until closed or
+/// finalized.
+pub const ACTIVE_POLLS: Map<Addr, u32> = Map::new("active_polls");
+/// Addresses an admin has barred from creating polls and voting
+pub const BANNED: Map<Addr, Empty> = Map::new("banned");
+/// Lowercased words polls can't be created with
+pub const BANNED_WORDS: Map<String, Empty> = Map::new("banned_words");
+pub const STATS: Item<Stats> = Item::new("stats");
+/// Everyone who has ever voted, so unique voters are only counted once
+pub const VOTERS: Map<Addr, Empty> = Map::new("voters");
+/// Contracts notified of poll events with a PollHookMsg
+pub const HOOKS: Map<Addr, Empty> = Map::new("hooks");
+/// Fees and slashed deposits held by the contract per denom, until the admin withdraws them
+pub const TREASURY: Map<String, Uint128> = Map::new("treasury");
+/// How much of TREASURY each source paid in, (source key, denom)
+pub const TREASURY_SOURCES: Map<(&str, String), Uint128> = Map::new("treasury_sources");
+/// Who was paid the keeper bounty for ending each poll, so it's only paid once
+pub const KEEPER_BOUNTIES: Map<String, Addr> = Map::new("keeper_bounties");
+/// Vote fee shares waiting to be claimed by poll creators, (creator, denom)
+pub const CREATOR_FEES: Map<(Addr, String), Uint128> = Map::new("creator_fees");
+/// Vote fees paid into polls, (poll_id, voter)
+pub const VOTE_FEES_PAID: Map<(String, Addr), Uint128> = Map::new("vote_fees_paid");
+/// Native coins the contract owes someone per denom: the treasury, what polls hold, creator fees,
+/// raffle prizes and failed payouts. Anything the contract holds beyond it can be swept.
+pub const HELD: Map<String, Uint128> = Map::new("held");
+/// Vote fees a poll holds until it is finalized, refunded if it ends without a result
+pub const VOTE_FEES_COLLECTED: Map<&str, Uint128> = Map::new("vote_fees_collected");
+/// Poll fees paid in cw20 tokens, per token contract
+pub const CW20_TREASURY: Map<Addr, Uint128> = Map::new("cw20_treasury");
+/// Poll creations paid for in advance with the accepted cw20 token
+pub const FEE_CREDITS: Map<Addr, u32> = Map::new("fee_credits");
+/// Payouts sent as submessages in the current transaction and waiting for their reply, by reply id
+pub const PENDING_PAYOUTS: Map<u64, Payout> = Map::new("pending_payouts");
+/// The reply id the next payout is sent with
+pub const NEXT_PAYOUT_REPLY_ID: Item<u64> = Item::new("next_payout_reply_id");
+/// Payouts whose transfer failed, kept per recipient until they retry them
+pub const FAILED_PAYOUTS: Map<Addr, Vec<Payout>> = Map::new("failed_payouts");
+/// Polls by id, indexed by creator, by status and by the time and height open polls end
+pub fn polls<'a>() -> IndexedMap<'a, String, Poll, PollIndexes<'a>> {
+    let indexes = PollIndexes {
+        creator: MultiIndex::new(|_, poll| poll.creator.clone(), "polls", "polls__creator"),
+        status: MultiIndex::new(|_, poll| poll.status as u8, "polls", "polls__status"),
+        end_time: MultiIndex::new(
+            |_, poll| (poll.status as u8, poll.end_key()),
+            "polls",
+            "polls__end_time",
+        ),
+        end_height: MultiIndex::new(
+            |_, poll| (poll.status as u8, poll.end_height.unwrap_or(u64::MAX)),
+            "polls",
+            "polls__end_height",
+        ),
+    };
+    IndexedMap::new("polls", indexes)
+}
+
+/// Reputation by address, indexed by its counters so the leaderboard never has to scan
+pub fn reputations<'a>() -> IndexedMap<'a, Addr, Reputation, ReputationIndexes<'a>> {
+    let indexes = ReputationIndexes {
+        polls_created: MultiIndex::new(
+            |_, reputation| reputation.polls_created,
+            "reputation",
+            "reputation__polls_created",
+        ),
+        polls_voted: MultiIndex::new(
+            |_, reputation| reputation.polls_voted,
+            "reputation",
+            "reputation__polls_voted",
+        ),
+    };
+    IndexedMap::new("reputation", indexes)
+}
+
+pub struct ReputationIndexes<'a> {
+    pub polls_created: MultiIndex<'a, u64, Reputation, Addr>,
+    pub polls_voted: MultiIndex<'a, u64, Reputation, Addr>,
+}
+
+impl<'a> IndexList<Reputation> for ReputationIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Reputation>> + '_> {
+        let indexes: Vec<&dyn Index<Reputation>> = vec![&self.polls_created, &self.polls_voted];
+        Box::new(indexes.into_iter())
+    }
+}
+
+/// Every vote, revote and revoke in the order they happened, never changed once written
+pub fn vote_log<'a>() -> IndexedMap<'a, u64, VoteLogEntry, VoteLogIndexes<'a>> {
+    let indexes = VoteLogIndexes {
+        poll: MultiIndex::new(
+            |_, entry| entry.poll_id.clone(),
+            "vote_log",
+            "vote_log__poll",
+        ),
+    };
+    IndexedMap::new("vote_log", indexes)
+}
+
+pub struct VoteLogIndexes<'a> {
+    pub poll: MultiIndex<'a, String, VoteLogEntry, u64>,
+}
+
+impl<'a> IndexList<VoteLogEntry> for VoteLogIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<VoteLogEntry>> + '_> {
+        let indexes: Vec<&dyn Index<VoteLogEntry>> = vec![&self.poll];
+        Box::new(indexes.into_iter())
+    }
+}
 
+/// Entries written to the vote log so far, the next entry's sequence number
+pub const VOTE_LOG_COUNT: Item<u64> = Item::new("vote_log_count");
 
+pub struct PollIndexes<'a> {
+    pub creator: MultiIndex<'a, Addr, Poll, String>,
+    /// By PollStatus as u8, in declaration order
+    pub status: MultiIndex<'a, u8, Poll, String>,
+    /// By status then end time in nanoseconds, polls without an end time last
+    pub end_time: MultiIndex<'a, (u8, u64), Poll, String>,
+    /// By status then end height, polls without an end height last
+    pub end_height: MultiIndex<'a, (u8, u64), Poll, String>,
+}
 
+impl<'a> IndexList<Poll> for PollIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Poll>> + '_> {
+        let indexes: Vec<&dyn Index<Poll>> = vec![
+            &self.creator,
+            &self.status,
+            &self.end_time,
+            &self.end_height,
+        ];
+        Box::new(indexes.into_iter())
+    }
+}
 
+/// Votes per poll and option position, apart from the poll so a vote only writes what it changes.
+/// Every block's changes are kept so TallyAtHeight can read back earlier counts.
+pub const TALLIES: SnapshotMap<(&str, u32), Uint128> = SnapshotMap::new(
+    "tallies",
+    "tallies__checkpoints",
+    "tallies__changelog",
+    Strategy::EveryBlock,
+);
+/// Ballot counts per poll, kept alongside the tallies
+pub const VOTE_COUNTS: Map<&str, VoteCounts> = Map::new("vote_counts");
+/// Summaries of archived polls, whose ids stay taken
+pub const ARCHIVED_POLLS: Map<String, PollSummary> = Map::new("archived_polls");
+pub const SURVEYS: Map<String, Survey> = Map::new("surveys");
+/// Each respondent's answers as the position of the chosen option, (survey_id, respondent)
+pub const SURVEY_ANSWERS: Map<(String, Addr), Vec<u32>> = Map::new("survey_answers");
+/// How often each option was chosen, (survey_id, question, option)
+pub const SURVEY_TALLIES: Map<(&str, u32, u32), u64> = Map::new("survey_tallies");
+/// How many vote receipts have been minted, numbering their token ids
+pub const RECEIPT_COUNT: Item<u64> = Item::new("receipt_count");
+/// Raffles by the id of the poll they're for, which is also their Nois job id
+pub const RAFFLES: Map<String, Raffle> = Map::new("raffles");
+pub const RECURRING_POLLS: Map<String, RecurringPoll> = Map::new("recurring_polls");
+/// Polls spawned from each recurring poll, (template_id, instance) -> poll_id
+pub const RECURRING_INSTANCES: Map<(String, u64), String> = Map::new("recurring_instances");
+/// The last id handed out to a poll created without one
+pub const LAST_POLL_ID: Item<u64> = Item::new("last_poll_id");
+/// Polls by creation number, the polls created count when each was created
+pub const CREATION_ORDER: Map<u64, String> = Map::new("creation_order");
+/// Reverse of CREATION_ORDER, so a poll's entry can be removed with it
+pub const CREATION_NUMBERS: Map<String, u64> = Map::new("creation_numbers");
+/// Number of stored polls, so counting them doesn't take a scan
+pub const POLL_COUNT: Item<u64> = Item::new("poll_count");
+/// Contracts running the polls created in factory mode, by poll id
+pub const POLL_CONTRACTS: Map<String, Addr> = Map::new("poll_contracts");
+/// A factory mode poll waiting for its contract's instantiate reply before it can be created there
+pub const PENDING_POLL_CONTRACT: Item<NewPoll> = Item::new("pending_poll_contract");
+/// Polls created through the cw3 interface, by proposal id
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+/// The last proposal id handed out
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+/// Voting power recorded per weight source and address, read back at a poll's snapshot height
+pub const POWER_CHECKPOINTS: SnapshotMap<(&str, &Addr), Uint128> = SnapshotMap::new(
+    "power_checkpoints",
+    "power_checkpoints__checkpoints",
+    "power_checkpoints__changelog",
+    Strategy::EveryBlock,
+);
+pub const BALLOTS: Map<(Addr, String), Ballot> = Map::new("ballots");
+/// Ballots of private polls, keyed by poll and `helpers::voter_hash` instead of the address
+pub const PRIVATE_BALLOTS: Map<(String, String), Ballot> = Map::new("private_ballots");
+/// The nonce each address's next signed vote has to use, so a signature only ever counts once
+pub const SIG_NONCES: Map<Addr, u64> = Map::new("sig_nonces");
+/// Hex encoded sha256 of each voter's viewing key, the key itself is never stored
+pub const VIEWING_KEYS: Map<Addr, String> = Map::new("viewing_keys");
+/// Open reports against polls with their reasons, (poll_id, reporter)
+pub const REPORTS: Map<(String, Addr), String> = Map::new("reports");
+/// How many open reports each reported poll has, the moderation queue
+pub const REPORT_COUNTS: Map<String, u32> = Map::new("report_counts");
+/// Owners of poll id namespaces, a poll id's namespace is what comes before its first `/`
+pub const NAMESPACES: Map<String, Addr> = Map::new("namespaces");
+/// Addresses besides the owner allowed to create polls in a namespace, (namespace, creator)
+pub const NAMESPACE_CREATORS: Map<(String, Addr), Empty> = Map::new("namespace_creators");
+/// Voters allowed in polls with an allowlist, (poll_id, voter)
+pub const ALLOWED_VOTERS: Map<(String, Addr), Empty> = Map::new("allowed_voters");
+/// Who each address has delegated its vote to
+pub const DELEGATIONS: Map<Addr, Addr> = Map::new("delegations");
+/// Reverse index of DELEGATIONS, (delegate, delegator)
+pub const DELEGATORS: Map<(Addr, Addr), Empty> = Map::new("delegators");
+/// The voter whose ballot carries a delegator's power in a poll, (poll_id, delegator)
+pub const REPRESENTATIVES: Map<(String, Addr), Addr> = Map::new("representatives");
+/// Hex encoded vote commitments of commit-reveal polls, waiting to be revealed
+pub const COMMITMENTS: Map<(String, Addr), String> = Map::new("commitments");
+/// The hex encoded result attestation of each finalized poll
+pub const RESULT_ATTESTATIONS: Map<String, String> = Map::new("result_attestations");
+// Secondary index of BALLOTS keyed poll first, so a poll's ballots can be found without a full scan
+pub const POLL_VOTERS: Map<(String, Addr), Empty> = Map::new("poll_voters");
+/// Secondary index of polls by tag, (tag, poll_id)
+pub const TAG_POLLS: Map<(String, String), Empty> = Map::new("tag_polls");
+pub const TALLY_ROUNDS: Map<String, Vec<TallyRound>> = Map::new("tally_rounds");
+/// Open IBC channels relaying votes, by local channel id, with the counterparty's end
+pub const IBC_CHANNELS: Map<String, IbcEndpoint> = Map::new("ibc_channels");
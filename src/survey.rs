@@ -0,0 +1,342 @@
+//! Surveys: several questions, each with its own options, answered together in one response.
+//!
+//! Unlike a batch of polls, a respondent's answers stay linked, so surveys can be read back per
+//! respondent as well as in aggregate. Answers are stored as option positions and tallied per
+//! question, a new response replacing the respondent's earlier one.
+
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdResult, Storage,
+    Timestamp,
+};
+use cw_storage_plus::Bound;
+
+use crate::contract::{
+    ensure_no_banned_words, ensure_not_banned, has_role, validate_options, validate_question,
+    DEFAULT_LIMIT, MAX_LIMIT,
+};
+use crate::error::ContractError;
+use crate::msg::{SurveyAnswersResponse, SurveyRespondentsResponse, SurveyResponse};
+use crate::state::{Role, Survey, SurveyQuestion, CONFIG, SURVEYS, SURVEY_ANSWERS, SURVEY_TALLIES};
+
+pub const MAX_SURVEY_QUESTIONS: u32 = 20;
+
+pub fn execute_create_survey(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    survey_id: String,
+    title: String,
+    questions: Vec<SurveyQuestion>,
+    end_time: Option<Timestamp>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    // Surveys are created under the same rules as polls
+    if config.restricted_creation
+        && !has_role(deps.storage, &config, &info.sender, Role::PollCreator)?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_not_banned(deps.storage, &info.sender)?;
+    if SURVEYS.has(deps.storage, survey_id.clone()) {
+        return Err(ContractError::SurveyAlreadyExists { survey_id });
+    }
+    if survey_id.is_empty()
+        || questions.is_empty()
+        || questions.len() > MAX_SURVEY_QUESTIONS as usize
+    {
+        return Err(ContractError::InvalidSurvey {
+            max: MAX_SURVEY_QUESTIONS,
+        });
+    }
+    if end_time.is_some_and(|time| time <= env.block.time) {
+        return Err(ContractError::InvalidExpiration {});
+    }
+    validate_question(&config.limits, &title)?;
+    ensure_no_banned_words(deps.storage, [&title])?;
+    for question in &questions {
+        validate_question(&config.limits, &question.question)?;
+        validate_options(&config.limits, &question.options)?;
+        ensure_no_banned_words(
+            deps.storage,
+            std::iter::once(&question.question).chain(&question.options),
+        )?;
+    }
+
+    let survey = Survey {
+        creator: info.sender.clone(),
+        title,
+        questions,
+        created_at_time: env.block.time,
+        end_time,
+    };
+    SURVEYS.save(deps.storage, survey_id.clone(), &survey)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_survey")
+        .add_attribute("survey_id", survey_id)
+        .add_attribute("creator", info.sender)
+        .add_attribute("questions", survey.questions.len().to_string()))
+}
+
+pub fn execute_submit_survey_response(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    survey_id: String,
+    answers: Vec<String>,
+) -> Result<Response, ContractError> {
+    let survey = match SURVEYS.may_load(deps.storage, survey_id.clone())? {
+        Some(survey) => survey,
+        None => return Err(ContractError::SurveyNotFound {}),
+    };
+    if survey.end_time.is_some_and(|time| env.block.time >= time) {
+        return Err(ContractError::SurveyClosed {});
+    }
+    ensure_not_banned(deps.storage, &info.sender)?;
+    if answers.len() != survey.questions.len() {
+        return Err(ContractError::AnswerCountMismatch {
+            expected: survey.questions.len() as u32,
+        });
+    }
+    let positions = survey
+        .questions
+        .iter()
+        .zip(&answers)
+        .map(|(question, answer)| {
+            question
+                .options
+                .iter()
+                .position(|option| option == answer)
+                .map(|position| position as u32)
+                .ok_or_else(|| ContractError::OptionNotFound {
+                    option: answer.clone(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let key = (survey_id.clone(), info.sender.clone());
+    let previous = SURVEY_ANSWERS.may_load(deps.storage, key.clone())?;
+    if let Some(previous) = &previous {
+        update_tallies(deps.storage, &survey_id, previous, false)?;
+    }
+    update_tallies(deps.storage, &survey_id, &positions, true)?;
+    SURVEY_ANSWERS.save(deps.storage, key, &positions)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "submit_survey_response")
+        .add_attribute("survey_id", survey_id)
+        .add_attribute("respondent", info.sender)
+        .add_attribute("replaced", previous.is_some().to_string()))
+}
+
+/// Counts a response's answers in, or back out of, the survey's tallies
+fn update_tallies(
+    storage: &mut dyn Storage,
+    survey_id: &str,
+    positions: &[u32],
+    add: bool,
+) -> StdResult<()> {
+    for (question, option) in (0u32..).zip(positions) {
+        let key = (survey_id, question, *option);
+        let count = SURVEY_TALLIES.may_load(storage, key)?.unwrap_or_default();
+        let count = if add {
+            count + 1
+        } else {
+            count.saturating_sub(1)
+        };
+        SURVEY_TALLIES.save(storage, key, &count)?;
+    }
+    Ok(())
+}
+
+/// The labels of the options a response chose
+fn answer_labels(survey: &Survey, positions: &[u32]) -> Vec<String> {
+    survey
+        .questions
+        .iter()
+        .zip(positions)
+        .map(|(question, position)| question.options[*position as usize].clone())
+        .collect()
+}
+
+pub fn query_survey(deps: Deps, _env: Env, survey_id: String) -> StdResult<Binary> {
+    let survey = SURVEYS.may_load(deps.storage, survey_id.clone())?;
+    let mut results = vec![];
+    for (question, survey_question) in (0u32..).zip(survey.iter().flat_map(|s| &s.questions)) {
+        let counts = (0u32..)
+            .zip(&survey_question.options)
+            .map(|(option, label)| {
+                let count = SURVEY_TALLIES
+                    .may_load(deps.storage, (&survey_id, question, option))?
+                    .unwrap_or_default();
+                Ok((label.clone(), count))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        results.push(counts);
+    }
+    // Every response answers the first question exactly once
+    let respondents = results
+        .first()
+        .map_or(0, |counts| counts.iter().map(|(_, count)| count).sum());
+
+    to_binary(&SurveyResponse {
+        survey,
+        results,
+        respondents,
+    })
+}
+
+pub fn query_survey_answers(
+    deps: Deps,
+    _env: Env,
+    survey_id: String,
+    address: String,
+) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    let survey = SURVEYS.load(deps.storage, survey_id.clone())?;
+    let answers = SURVEY_ANSWERS
+        .may_load(deps.storage, (survey_id, address))?
+        .map(|positions| answer_labels(&survey, &positions));
+    to_binary(&SurveyAnswersResponse { answers })
+}
+
+pub fn query_survey_respondents(
+    deps: Deps,
+    _env: Env,
+    survey_id: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let survey = SURVEYS.load(deps.storage, survey_id.clone())?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?
+        .map(Bound::exclusive);
+    let respondents = SURVEY_ANSWERS
+        .prefix(survey_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (respondent, positions): (Addr, _) = item?;
+            Ok((respondent, answer_labels(&survey, &positions)))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_binary(&SurveyRespondentsResponse { respondents })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::from_binary;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    use super::*;
+    use crate::contract::{execute, instantiate, query};
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+    fn question(question: &str, options: &[&str]) -> SurveyQuestion {
+        SurveyQuestion {
+            question: question.to_string(),
+            options: options.iter().map(|option| option.to_string()).collect(),
+        }
+    }
+
+    fn respond(answers: &[&str]) -> ExecuteMsg {
+        ExecuteMsg::SubmitSurveyResponse {
+            survey_id: "survey".to_string(),
+            answers: answers.iter().map(|answer| answer.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_survey() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let msg = InstantiateMsg {
+            admin: None,
+            poll_fee: None,
+            poll_deposit: None,
+            accepted_token: None,
+            poll_code_id: None,
+            limits: None,
+            bootstrap: false,
+            initial_polls: vec![],
+            settings: None,
+        };
+        let _res = instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+        let msg = ExecuteMsg::CreateSurvey {
+            survey_id: "survey".to_string(),
+            title: "Community survey".to_string(),
+            questions: vec![
+                question("Favourite chain?", &["Juno", "Osmosis"]),
+                question("How often do you vote?", &["Always", "Sometimes", "Never"]),
+            ],
+            end_time: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        // Every question needs exactly one valid answer
+        let info = mock_info("alice", &[]);
+        let msg = respond(&["Juno"]);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::AnswerCountMismatch { expected: 2 }
+        ));
+        let msg = respond(&["Juno", "Daily"]);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::OptionNotFound { .. }));
+
+        // A second response replaces the first
+        for (respondent, answers) in [
+            ("alice", ["Juno", "Never"]),
+            ("alice", ["Juno", "Always"]),
+            ("bob", ["Osmosis", "Always"]),
+        ] {
+            let info = mock_info(respondent, &[]);
+            let _res = execute(deps.as_mut(), env.clone(), info, respond(&answers)).unwrap();
+        }
+
+        let msg = QueryMsg::Survey {
+            survey_id: "survey".to_string(),
+        };
+        let res: SurveyResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.respondents, 2);
+        assert_eq!(
+            res.results[1],
+            vec![
+                ("Always".to_string(), 2),
+                ("Sometimes".to_string(), 0),
+                ("Never".to_string(), 0)
+            ]
+        );
+
+        // Answers stay linked to who gave them
+        let msg = QueryMsg::SurveyAnswers {
+            survey_id: "survey".to_string(),
+            address: "alice".to_string(),
+        };
+        let res: SurveyAnswersResponse =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res.answers,
+            Some(vec!["Juno".to_string(), "Always".to_string()])
+        );
+        let msg = QueryMsg::SurveyRespondents {
+            survey_id: "survey".to_string(),
+            start_after: Some("alice".to_string()),
+            limit: None,
+        };
+        let res: SurveyRespondentsResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(
+            res.respondents,
+            vec![(
+                Addr::unchecked("bob"),
+                vec!["Osmosis".to_string(), "Always".to_string()]
+            )]
+        );
+    }
+}